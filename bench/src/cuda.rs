@@ -0,0 +1,226 @@
+//! CUDA driver API of the NVIDIA driver.
+
+use crate::{check, load, symbol, BenchError, Blas, Precision, Result, Runtime};
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_uint, c_void, CString};
+
+#[cfg(windows)]
+const LIBRARIES: &[&str] = &["nvcuda.dll"];
+#[cfg(not(windows))]
+const LIBRARIES: &[&str] = &["libcuda.so.1", "libcuda.so"];
+
+#[cfg(windows)]
+const BLAS_LIBRARIES: &[&str] = &["cublas64_12.dll", "cublas64_11.dll"];
+#[cfg(not(windows))]
+const BLAS_LIBRARIES: &[&str] = &["libcublas.so", "libcublas.so.12", "libcublas.so.11"];
+
+/// `CUBLAS_OP_N`, matrices are not transposed.
+const OP_N: c_int = 0;
+
+type CuResult = c_int;
+type CuDevice = c_int;
+type CuContext = *mut c_void;
+type CuDevicePtr = u64;
+type CublasStatus = c_int;
+type CublasHandle = *mut c_void;
+/// Signature shared by `cublasSgemm` and `cublasHgemm`, scalars are `f32` or `__half`.
+type Gemm = unsafe extern "C" fn(
+    CublasHandle,
+    c_int,
+    c_int,
+    c_int,
+    c_int,
+    c_int,
+    *const c_void,
+    *const c_void,
+    c_int,
+    *const c_void,
+    c_int,
+    *const c_void,
+    *mut c_void,
+    c_int,
+) -> CublasStatus;
+
+/// Driver functions used by benchmarks.
+struct Api {
+    device_get_by_pci_bus_id: unsafe extern "C" fn(*mut CuDevice, *const c_char) -> CuResult,
+    primary_ctx_retain: unsafe extern "C" fn(*mut CuContext, CuDevice) -> CuResult,
+    primary_ctx_release: unsafe extern "C" fn(CuDevice) -> CuResult,
+    ctx_set_current: unsafe extern "C" fn(CuContext) -> CuResult,
+    ctx_synchronize: unsafe extern "C" fn() -> CuResult,
+    mem_alloc: unsafe extern "C" fn(*mut CuDevicePtr, usize) -> CuResult,
+    mem_free: unsafe extern "C" fn(CuDevicePtr) -> CuResult,
+    memset_d32: unsafe extern "C" fn(CuDevicePtr, c_uint, usize) -> CuResult,
+    memcpy_dtod: unsafe extern "C" fn(CuDevicePtr, CuDevicePtr, usize) -> CuResult,
+}
+
+/// Device with its primary context current on the calling thread.
+pub(crate) struct Cuda {
+    api: Api,
+    device: CuDevice,
+    // keeps `api` loaded.
+    _library: Library,
+}
+
+impl Cuda {
+    /// Opens device `bus_id`, `None` without NVIDIA driver or when it does not know the device.
+    pub(crate) fn open(bus_id: &str) -> Result<Option<Cuda>> {
+        let Some(library) = load(LIBRARIES) else {
+            return Ok(None);
+        };
+        // SAFETY: signatures follow `cuda.h`.
+        let (init, api) = unsafe {
+            let init: unsafe extern "C" fn(c_uint) -> CuResult = symbol(&library, "cuInit")?;
+            let api = Api {
+                device_get_by_pci_bus_id: symbol(&library, "cuDeviceGetByPCIBusId")?,
+                primary_ctx_retain: symbol(&library, "cuDevicePrimaryCtxRetain")?,
+                primary_ctx_release: symbol(&library, "cuDevicePrimaryCtxRelease")?,
+                ctx_set_current: symbol(&library, "cuCtxSetCurrent")?,
+                ctx_synchronize: symbol(&library, "cuCtxSynchronize")?,
+                mem_alloc: symbol(&library, "cuMemAlloc_v2")?,
+                mem_free: symbol(&library, "cuMemFree_v2")?,
+                memset_d32: symbol(&library, "cuMemsetD32_v2")?,
+                memcpy_dtod: symbol(&library, "cuMemcpyDtoD_v2")?,
+            };
+            (init, api)
+        };
+        let Ok(bus_id) = CString::new(bus_id) else {
+            return Ok(None);
+        };
+        let mut device = 0;
+        // SAFETY: out pointers are valid for the duration of the calls.
+        unsafe {
+            // e.g. driver installed, but no NVIDIA device.
+            if init(0) != 0 || (api.device_get_by_pci_bus_id)(&mut device, bus_id.as_ptr()) != 0 {
+                return Ok(None);
+            }
+            let mut context = std::ptr::null_mut();
+            check(
+                "cuDevicePrimaryCtxRetain",
+                (api.primary_ctx_retain)(&mut context, device),
+            )?;
+            let cuda = Cuda {
+                api,
+                device,
+                _library: library,
+            };
+            check("cuCtxSetCurrent", (cuda.api.ctx_set_current)(context))?;
+            Ok(Some(cuda))
+        }
+    }
+}
+
+impl Runtime for Cuda {
+    fn alloc(&self, bytes: usize) -> Result<u64> {
+        let mut ptr = 0;
+        // SAFETY: context is current, `ptr` is a valid out pointer.
+        check("cuMemAlloc", unsafe {
+            (self.api.mem_alloc)(&mut ptr, bytes)
+        })?;
+        Ok(ptr)
+    }
+
+    fn free(&self, ptr: u64) {
+        // SAFETY: `ptr` was allocated by `alloc` and is not used afterwards.
+        unsafe { (self.api.mem_free)(ptr) };
+    }
+
+    fn fill(&self, ptr: u64, value: u32, bytes: usize) -> Result<()> {
+        // SAFETY: `ptr` points to at least `bytes` of device memory.
+        let code = unsafe { (self.api.memset_d32)(ptr, value, bytes / 4) };
+        check("cuMemsetD32", code)
+    }
+
+    fn copy(&self, dst: u64, src: u64, bytes: usize) -> Result<()> {
+        // SAFETY: both buffers hold at least `bytes` of device memory.
+        check("cuMemcpyDtoD", unsafe {
+            (self.api.memcpy_dtod)(dst, src, bytes)
+        })
+    }
+
+    fn synchronize(&self) -> Result<()> {
+        // SAFETY: context is current.
+        check("cuCtxSynchronize", unsafe { (self.api.ctx_synchronize)() })
+    }
+
+    fn blas(&self) -> Result<Box<dyn Blas + '_>> {
+        let library = load(BLAS_LIBRARIES).ok_or(BenchError::LibraryNotFound("cuBLAS"))?;
+        // SAFETY: signatures follow `cublas_api.h`.
+        let (create, destroy, sgemm, hgemm) = unsafe {
+            let create: unsafe extern "C" fn(*mut CublasHandle) -> CublasStatus =
+                symbol(&library, "cublasCreate_v2")?;
+            let destroy: unsafe extern "C" fn(CublasHandle) -> CublasStatus =
+                symbol(&library, "cublasDestroy_v2")?;
+            let sgemm: Gemm = symbol(&library, "cublasSgemm_v2")?;
+            let hgemm: Gemm = symbol(&library, "cublasHgemm")?;
+            (create, destroy, sgemm, hgemm)
+        };
+        let mut handle = std::ptr::null_mut();
+        // SAFETY: context is current, `handle` is a valid out pointer.
+        check("cublasCreate", unsafe { create(&mut handle) })?;
+        Ok(Box::new(Cublas {
+            handle,
+            destroy,
+            sgemm,
+            hgemm,
+            _library: library,
+        }))
+    }
+}
+
+/// cuBLAS handle, working on the current context.
+struct Cublas {
+    handle: CublasHandle,
+    destroy: unsafe extern "C" fn(CublasHandle) -> CublasStatus,
+    sgemm: Gemm,
+    hgemm: Gemm,
+    // keeps functions loaded.
+    _library: Library,
+}
+
+impl Blas for Cublas {
+    fn gemm(&self, precision: Precision, n: usize, a: u64, b: u64, c: u64) -> Result<()> {
+        let (call, gemm, one, zero) = match precision {
+            Precision::Fp32 => ("cublasSgemm", self.sgemm, 1.0f32.to_bits(), 0u32),
+            // `__half` scalars in the low half of the word.
+            Precision::Fp16 => ("cublasHgemm", self.hgemm, 0x3c00, 0u32),
+        };
+        let n = n as c_int;
+        let ptr = |ptr: u64| ptr as usize as *mut c_void;
+        // SAFETY: matrices hold `n * n` elements of `precision`, scalars outlive the call
+        // and are little-endian words, so `__half` is read from their low half.
+        let code = unsafe {
+            gemm(
+                self.handle,
+                OP_N,
+                OP_N,
+                n,
+                n,
+                n,
+                (&one as *const u32).cast(),
+                ptr(a),
+                n,
+                ptr(b),
+                n,
+                (&zero as *const u32).cast(),
+                ptr(c),
+                n,
+            )
+        };
+        check(call, code)
+    }
+}
+
+impl Drop for Cublas {
+    fn drop(&mut self) {
+        // SAFETY: handle was created in `Cuda::blas` and is not used afterwards.
+        unsafe { (self.destroy)(self.handle) };
+    }
+}
+
+impl Drop for Cuda {
+    fn drop(&mut self) {
+        // SAFETY: the context was retained in `open`, buffers are already freed.
+        unsafe { (self.api.primary_ctx_release)(self.device) };
+    }
+}
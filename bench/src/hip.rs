@@ -0,0 +1,210 @@
+//! HIP runtime of ROCm.
+
+use crate::{check, load, symbol, BenchError, Blas, Precision, Result, Runtime};
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_uint, c_void, CString};
+
+#[cfg(windows)]
+const LIBRARIES: &[&str] = &["amdhip64_6.dll", "amdhip64.dll"];
+#[cfg(not(windows))]
+const LIBRARIES: &[&str] = &["libamdhip64.so", "libamdhip64.so.6", "libamdhip64.so.5"];
+
+#[cfg(windows)]
+const BLAS_LIBRARIES: &[&str] = &["hipblas.dll"];
+#[cfg(not(windows))]
+const BLAS_LIBRARIES: &[&str] = &["libhipblas.so", "libhipblas.so.2", "libhipblas.so.1"];
+
+/// `HIPBLAS_OP_N`, matrices are not transposed.
+const OP_N: c_int = 111;
+
+type HipError = c_int;
+type HipDevicePtr = *mut c_void;
+type HipblasStatus = c_int;
+type HipblasHandle = *mut c_void;
+/// Signature shared by `hipblasSgemm` and `hipblasHgemm`, scalars are `f32` or `hipblasHalf`.
+type Gemm = unsafe extern "C" fn(
+    HipblasHandle,
+    c_int,
+    c_int,
+    c_int,
+    c_int,
+    c_int,
+    *const c_void,
+    *const c_void,
+    c_int,
+    *const c_void,
+    c_int,
+    *const c_void,
+    *mut c_void,
+    c_int,
+) -> HipblasStatus;
+
+/// Runtime functions used by benchmarks.
+struct Api {
+    malloc: unsafe extern "C" fn(*mut HipDevicePtr, usize) -> HipError,
+    free: unsafe extern "C" fn(HipDevicePtr) -> HipError,
+    memset_d32: unsafe extern "C" fn(HipDevicePtr, c_int, usize) -> HipError,
+    memcpy_dtod: unsafe extern "C" fn(HipDevicePtr, HipDevicePtr, usize) -> HipError,
+    device_synchronize: unsafe extern "C" fn() -> HipError,
+}
+
+/// Device current on the calling thread.
+pub(crate) struct Hip {
+    api: Api,
+    // keeps `api` loaded.
+    _library: Library,
+}
+
+impl Hip {
+    /// Opens device `bus_id`, `None` without ROCm or when it does not know the device.
+    pub(crate) fn open(bus_id: &str) -> Result<Option<Hip>> {
+        let Some(library) = load(LIBRARIES) else {
+            return Ok(None);
+        };
+        // SAFETY: signatures follow `hip_runtime_api.h`.
+        let (init, device_get_by_pci_bus_id, set_device, api) = unsafe {
+            let init: unsafe extern "C" fn(c_uint) -> HipError = symbol(&library, "hipInit")?;
+            let device_get_by_pci_bus_id: unsafe extern "C" fn(
+                *mut c_int,
+                *const c_char,
+            ) -> HipError = symbol(&library, "hipDeviceGetByPCIBusId")?;
+            let set_device: unsafe extern "C" fn(c_int) -> HipError =
+                symbol(&library, "hipSetDevice")?;
+            let api = Api {
+                malloc: symbol(&library, "hipMalloc")?,
+                free: symbol(&library, "hipFree")?,
+                memset_d32: symbol(&library, "hipMemsetD32")?,
+                memcpy_dtod: symbol(&library, "hipMemcpyDtoD")?,
+                device_synchronize: symbol(&library, "hipDeviceSynchronize")?,
+            };
+            (init, device_get_by_pci_bus_id, set_device, api)
+        };
+        let Ok(bus_id) = CString::new(bus_id) else {
+            return Ok(None);
+        };
+        let mut device = 0;
+        // SAFETY: out pointers are valid for the duration of the calls.
+        unsafe {
+            // e.g. ROCm installed, but no AMD device.
+            if init(0) != 0 || device_get_by_pci_bus_id(&mut device, bus_id.as_ptr()) != 0 {
+                return Ok(None);
+            }
+            check("hipSetDevice", set_device(device))?;
+        }
+        Ok(Some(Hip {
+            api,
+            _library: library,
+        }))
+    }
+}
+
+fn device_ptr(ptr: u64) -> HipDevicePtr {
+    ptr as usize as HipDevicePtr
+}
+
+impl Runtime for Hip {
+    fn alloc(&self, bytes: usize) -> Result<u64> {
+        let mut ptr = std::ptr::null_mut();
+        // SAFETY: device is current, `ptr` is a valid out pointer.
+        check("hipMalloc", unsafe { (self.api.malloc)(&mut ptr, bytes) })?;
+        Ok(ptr as usize as u64)
+    }
+
+    fn free(&self, ptr: u64) {
+        // SAFETY: `ptr` was allocated by `alloc` and is not used afterwards.
+        unsafe { (self.api.free)(device_ptr(ptr)) };
+    }
+
+    fn fill(&self, ptr: u64, value: u32, bytes: usize) -> Result<()> {
+        // SAFETY: `ptr` points to at least `bytes` of device memory.
+        let code = unsafe { (self.api.memset_d32)(device_ptr(ptr), value as c_int, bytes / 4) };
+        check("hipMemsetD32", code)
+    }
+
+    fn copy(&self, dst: u64, src: u64, bytes: usize) -> Result<()> {
+        // SAFETY: both buffers hold at least `bytes` of device memory.
+        check("hipMemcpyDtoD", unsafe {
+            (self.api.memcpy_dtod)(device_ptr(dst), device_ptr(src), bytes)
+        })
+    }
+
+    fn synchronize(&self) -> Result<()> {
+        // SAFETY: device is current.
+        check("hipDeviceSynchronize", unsafe {
+            (self.api.device_synchronize)()
+        })
+    }
+
+    fn blas(&self) -> Result<Box<dyn Blas + '_>> {
+        let library = load(BLAS_LIBRARIES).ok_or(BenchError::LibraryNotFound("hipBLAS"))?;
+        // SAFETY: signatures follow `hipblas.h`.
+        let (create, destroy, sgemm, hgemm) = unsafe {
+            let create: unsafe extern "C" fn(*mut HipblasHandle) -> HipblasStatus =
+                symbol(&library, "hipblasCreate")?;
+            let destroy: unsafe extern "C" fn(HipblasHandle) -> HipblasStatus =
+                symbol(&library, "hipblasDestroy")?;
+            let sgemm: Gemm = symbol(&library, "hipblasSgemm")?;
+            let hgemm: Gemm = symbol(&library, "hipblasHgemm")?;
+            (create, destroy, sgemm, hgemm)
+        };
+        let mut handle = std::ptr::null_mut();
+        // SAFETY: device is current, `handle` is a valid out pointer.
+        check("hipblasCreate", unsafe { create(&mut handle) })?;
+        Ok(Box::new(Hipblas {
+            handle,
+            destroy,
+            sgemm,
+            hgemm,
+            _library: library,
+        }))
+    }
+}
+
+/// hipBLAS handle, working on the current device.
+struct Hipblas {
+    handle: HipblasHandle,
+    destroy: unsafe extern "C" fn(HipblasHandle) -> HipblasStatus,
+    sgemm: Gemm,
+    hgemm: Gemm,
+    // keeps functions loaded.
+    _library: Library,
+}
+
+impl Blas for Hipblas {
+    fn gemm(&self, precision: Precision, n: usize, a: u64, b: u64, c: u64) -> Result<()> {
+        let (call, gemm, one, zero) = match precision {
+            Precision::Fp32 => ("hipblasSgemm", self.sgemm, 1.0f32.to_bits(), 0u32),
+            // `hipblasHalf` scalars in the low half of the word.
+            Precision::Fp16 => ("hipblasHgemm", self.hgemm, 0x3c00, 0u32),
+        };
+        let n = n as c_int;
+        // SAFETY: matrices hold `n * n` elements of `precision`, scalars outlive the call
+        // and are little-endian words, so `hipblasHalf` is read from their low half.
+        let code = unsafe {
+            gemm(
+                self.handle,
+                OP_N,
+                OP_N,
+                n,
+                n,
+                n,
+                (&one as *const u32).cast(),
+                device_ptr(a),
+                n,
+                device_ptr(b),
+                n,
+                (&zero as *const u32).cast(),
+                device_ptr(c),
+                n,
+            )
+        };
+        check(call, code)
+    }
+}
+
+impl Drop for Hipblas {
+    fn drop(&mut self) {
+        // SAFETY: handle was created in `Hip::blas` and is not used afterwards.
+        unsafe { (self.destroy)(self.handle) };
+    }
+}
@@ -0,0 +1,236 @@
+#![deny(missing_docs)]
+//! GPU micro-benchmarks run through CUDA or HIP, see `golem_gpu_info::bench`.
+//!
+//! Runtimes are loaded from the installed drivers, nothing is linked at build time.
+//! Kept out of the main crate, because calling them needs unsafe code.
+
+mod cuda;
+mod hip;
+
+use libloading::Library;
+use std::time::Instant;
+
+/// Size of copied buffer.
+const COPY_BYTES: usize = 256 * 1024 * 1024;
+/// Number of copies timed together.
+const COPY_ITERATIONS: u32 = 10;
+/// Side of multiplied square matrices.
+const GEMM_SIZE: usize = 4096;
+/// Number of multiplications timed together.
+const GEMM_ITERATIONS: u32 = 10;
+
+/// Benchmark error.
+#[derive(Debug, thiserror::Error)]
+pub enum BenchError {
+    /// Neither CUDA nor HIP runtime knows a device with given PCI bus id.
+    #[error("no CUDA or HIP device with PCI bus id {0}")]
+    DeviceNotFound(String),
+    /// BLAS library of the runtime (cuBLAS, hipBLAS) is not installed.
+    #[error("{0} not found")]
+    LibraryNotFound(&'static str),
+    /// Runtime library lacks a function.
+    #[error("runtime library lacks {0}")]
+    MissingSymbol(&'static str),
+    /// Runtime call failed.
+    #[error("{call} failed with error {code}")]
+    Call {
+        /// Name of the failed function.
+        call: &'static str,
+        /// Error code returned by the runtime.
+        code: i32,
+    },
+}
+
+/// Benchmark result.
+pub type Result<T> = std::result::Result<T, BenchError>;
+
+/// Measures device-to-device copy bandwidth in GiB/s of the device with
+/// PCI bus id `bus_id` (e.g. `0000:01:00.0`).
+///
+/// Takes a few hundred milliseconds and `512 MiB` of device memory.
+pub fn copy_bandwidth_gib(bus_id: &str) -> Result<u32> {
+    let runtime = open(bus_id)?;
+    let runtime = &*runtime;
+    let src = Buffer::new(runtime, COPY_BYTES, 0)?;
+    let dst = Buffer::new(runtime, COPY_BYTES, 0)?;
+    let copies = |iterations: u32| -> Result<f64> {
+        let started = Instant::now();
+        for _ in 0..iterations {
+            runtime.copy(dst.ptr, src.ptr, COPY_BYTES)?;
+        }
+        runtime.synchronize()?;
+        Ok(started.elapsed().as_secs_f64())
+    };
+
+    // first copy pays for lazy allocation and clock ramp-up.
+    copies(1)?;
+    let seconds = copies(COPY_ITERATIONS)?;
+    // every copy reads and writes the whole buffer.
+    let bytes = 2.0 * COPY_BYTES as f64 * f64::from(COPY_ITERATIONS);
+    Ok(per_second(bytes, seconds, (1u64 << 30) as f64) as u32)
+}
+
+/// Floating point precision of matrix multiplication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Single precision.
+    Fp32,
+    /// Half precision, on tensor cores or matrix cores where available.
+    Fp16,
+}
+
+impl Precision {
+    fn bytes(self) -> usize {
+        match self {
+            Precision::Fp32 => 4,
+            Precision::Fp16 => 2,
+        }
+    }
+
+    /// `1.0` in every element of a 32-bit word.
+    fn one(self) -> u32 {
+        match self {
+            Precision::Fp32 => 1.0f32.to_bits(),
+            Precision::Fp16 => 0x3c00_3c00,
+        }
+    }
+}
+
+/// Measures matrix multiplication throughput in TFLOPS at `precision` of the device
+/// with PCI bus id `bus_id`.
+///
+/// Multiplies square matrices of 4096 elements per side in cuBLAS or hipBLAS,
+/// which takes under a second on current cards and `192 MiB` of device memory.
+pub fn gemm_tflops(bus_id: &str, precision: Precision) -> Result<f32> {
+    let runtime = open(bus_id)?;
+    let runtime = &*runtime;
+    let bytes = GEMM_SIZE * GEMM_SIZE * precision.bytes();
+    let a = Buffer::new(runtime, bytes, precision.one())?;
+    let b = Buffer::new(runtime, bytes, precision.one())?;
+    let c = Buffer::new(runtime, bytes, 0)?;
+    let blas = runtime.blas()?;
+    let multiplications = |iterations: u32| -> Result<f64> {
+        let started = Instant::now();
+        for _ in 0..iterations {
+            blas.gemm(precision, GEMM_SIZE, a.ptr, b.ptr, c.ptr)?;
+        }
+        runtime.synchronize()?;
+        Ok(started.elapsed().as_secs_f64())
+    };
+
+    // first multiplication loads kernels and ramps clocks up.
+    multiplications(1)?;
+    let seconds = multiplications(GEMM_ITERATIONS)?;
+    // every element of the result takes `GEMM_SIZE` multiplications and additions.
+    let flops = 2.0 * (GEMM_SIZE as f64).powi(3) * f64::from(GEMM_ITERATIONS);
+    Ok(per_second(flops, seconds, 1e12) as f32)
+}
+
+/// Device opened in a vendor runtime, current on the calling thread.
+///
+/// Device memory is addressed by its 64-bit device pointer.
+trait Runtime {
+    fn alloc(&self, bytes: usize) -> Result<u64>;
+    fn free(&self, ptr: u64);
+    /// Fills `bytes` at `ptr` with 32-bit `value`.
+    fn fill(&self, ptr: u64, value: u32, bytes: usize) -> Result<()>;
+    fn copy(&self, dst: u64, src: u64, bytes: usize) -> Result<()>;
+    /// Waits for all submitted work.
+    fn synchronize(&self) -> Result<()>;
+    /// Loads the BLAS library of the runtime.
+    fn blas(&self) -> Result<Box<dyn Blas + '_>>;
+}
+
+/// BLAS library handle of a runtime.
+trait Blas {
+    /// Submits `c = a * b` of square matrices with side `n`.
+    fn gemm(&self, precision: Precision, n: usize, a: u64, b: u64, c: u64) -> Result<()>;
+}
+
+/// Opens device `bus_id` in the first runtime which knows it.
+fn open(bus_id: &str) -> Result<Box<dyn Runtime>> {
+    if let Some(cuda) = cuda::Cuda::open(bus_id)? {
+        return Ok(Box::new(cuda));
+    }
+    if let Some(hip) = hip::Hip::open(bus_id)? {
+        return Ok(Box::new(hip));
+    }
+    Err(BenchError::DeviceNotFound(bus_id.into()))
+}
+
+/// Device memory, freed on drop.
+struct Buffer<'a> {
+    runtime: &'a dyn Runtime,
+    ptr: u64,
+}
+
+impl<'a> Buffer<'a> {
+    /// Allocates `bytes` filled with 32-bit `value`.
+    fn new(runtime: &'a dyn Runtime, bytes: usize, value: u32) -> Result<Self> {
+        let buffer = Buffer {
+            runtime,
+            ptr: runtime.alloc(bytes)?,
+        };
+        runtime.fill(buffer.ptr, value, bytes)?;
+        Ok(buffer)
+    }
+}
+
+impl Drop for Buffer<'_> {
+    fn drop(&mut self) {
+        self.runtime.free(self.ptr);
+    }
+}
+
+/// Rate of `amount` done in `seconds`, in `unit`s per second.
+fn per_second(amount: f64, seconds: f64, unit: f64) -> f64 {
+    amount / seconds / unit
+}
+
+/// Loads the first of `names` found.
+fn load(names: &[&str]) -> Option<Library> {
+    names.iter().find_map(|name| {
+        // SAFETY: vendor runtimes have no initialization routines with preconditions.
+        unsafe { Library::new(name) }.ok()
+    })
+}
+
+/// Looks up function `name`, `T` must be its C signature.
+unsafe fn symbol<T: Copy>(library: &Library, name: &'static str) -> Result<T> {
+    library
+        .get::<T>(name.as_bytes())
+        .map(|symbol| *symbol)
+        .map_err(|_| BenchError::MissingSymbol(name))
+}
+
+/// Turns runtime status `code` of `call` into a result, `0` is success for both runtimes.
+fn check(call: &'static str, code: i32) -> Result<()> {
+    match code {
+        0 => Ok(()),
+        code => Err(BenchError::Call { call, code }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_per_second() {
+        let gib = (1u64 << 30) as f64;
+        assert_eq!(per_second(10.0 * gib, 0.5, gib), 20.0);
+        assert_eq!(
+            check("cuInit", 100).unwrap_err().to_string(),
+            "cuInit failed with error 100"
+        );
+        assert!(check("cuInit", 0).is_ok());
+    }
+
+    #[test]
+    fn test_precision_one() {
+        assert_eq!(f32::from_bits(Precision::Fp32.one()), 1.0);
+        // two `1.0` halves.
+        assert_eq!(Precision::Fp16.one(), 0x3c00_3c00);
+        assert_eq!(Precision::Fp16.bytes() * 2, Precision::Fp32.bytes());
+    }
+}
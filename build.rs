@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "proto")]
+    {
+        println!("cargo:rerun-if-changed=proto/gpu_info.proto");
+        println!("cargo:rerun-if-env-changed=PROTOC");
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc");
+            std::env::set_var("PROTOC", protoc);
+        }
+        prost_build::compile_protos(&["proto/gpu_info.proto"], &["proto"])
+            .expect("failed to compile proto/gpu_info.proto");
+    }
+}
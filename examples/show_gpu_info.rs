@@ -7,6 +7,10 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let gpu = detection.detect()?;
 
-    serde_json::to_writer_pretty(&mut std::io::stdout(), &json!({"gpu": gpu}))?;
+    match std::env::args().nth(1).as_deref() {
+        Some("--table") => print!("{}", gpu.to_table()),
+        Some("--csv") => print!("{}", gpu.to_csv()),
+        _ => serde_json::to_writer_pretty(&mut std::io::stdout(), &json!({"gpu": gpu}))?,
+    }
     Ok(())
 }
@@ -0,0 +1,29 @@
+#ifndef GOLEM_GPU_INFO_H
+#define GOLEM_GPU_INFO_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GOLEM_GPU_OK 0
+#define GOLEM_GPU_NOT_FOUND 1
+#define GOLEM_GPU_ACCESS_ERROR 2
+#define GOLEM_GPU_TIMEOUT 3
+#define GOLEM_GPU_ERROR 4
+#define GOLEM_GPU_INVALID_ARGUMENT 5
+
+/*
+ * Detects GPUs and stores `{"gpu": {...}}` JSON in `*json`.
+ * On failure returns error code and stores `{"error": "..."}` JSON.
+ * The string must be released with `golem_gpu_free`.
+ */
+int golem_gpu_detect(char **json);
+
+/* Releases string returned by `golem_gpu_detect`. Accepts NULL. */
+void golem_gpu_free(char *json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
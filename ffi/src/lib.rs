@@ -0,0 +1,108 @@
+#![deny(missing_docs)]
+//! C interface of GPU detection, see `golem_gpu_info.h`.
+//!
+//! Kept out of the main crate, because exporting symbols needs unsafe code.
+
+use golem_gpu_info::{ErrorKind, Gpu, GpuDetectionBuilder, GpuDetectionError};
+use serde_json::{json, Value};
+use std::ffi::{c_char, c_int, CString};
+
+/// Detection succeeded.
+pub const GOLEM_GPU_OK: c_int = 0;
+/// Driver or device not found.
+pub const GOLEM_GPU_NOT_FOUND: c_int = 1;
+/// Failed to access device or its properties.
+pub const GOLEM_GPU_ACCESS_ERROR: c_int = 2;
+/// Driver call timed out.
+pub const GOLEM_GPU_TIMEOUT: c_int = 3;
+/// Other error.
+pub const GOLEM_GPU_ERROR: c_int = 4;
+/// Null pointer passed.
+pub const GOLEM_GPU_INVALID_ARGUMENT: c_int = 5;
+
+/// Detects GPUs and stores `{"gpu": {...}}` JSON in `*json`.
+///
+/// On failure returns error code and stores `{"error": "..."}` JSON.
+///
+/// # Safety
+///
+/// `json` must be a valid pointer to writable `char *`.
+/// The stored string must be released with [`golem_gpu_free`].
+#[no_mangle]
+pub unsafe extern "C" fn golem_gpu_detect(json: *mut *mut c_char) -> c_int {
+    if json.is_null() {
+        return GOLEM_GPU_INVALID_ARGUMENT;
+    }
+    let (code, value) = match detect() {
+        Ok(gpu) => (GOLEM_GPU_OK, json!({ "gpu": gpu })),
+        Err(e) => (error_code(&e), json!({ "error": e.to_string() })),
+    };
+    *json = into_c_string(value);
+    code
+}
+
+/// Releases string returned by [`golem_gpu_detect`]. Accepts null.
+///
+/// # Safety
+///
+/// `json` must be null or a string returned by this library, not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn golem_gpu_free(json: *mut c_char) {
+    if !json.is_null() {
+        drop(CString::from_raw(json));
+    }
+}
+
+fn detect() -> Result<Gpu, GpuDetectionError> {
+    GpuDetectionBuilder::default().init()?.detect()
+}
+
+fn error_code(e: &GpuDetectionError) -> c_int {
+    match (e, e.kind()) {
+        (_, ErrorKind::NotFound | ErrorKind::DriverNotFound) => GOLEM_GPU_NOT_FOUND,
+        (_, ErrorKind::PermissionDenied | ErrorKind::DeviceLost)
+        | (GpuDetectionError::GpuAccessError(_) | GpuDetectionError::GpuInfoAccessError(_), _) => {
+            GOLEM_GPU_ACCESS_ERROR
+        }
+        (_, ErrorKind::Timeout) => GOLEM_GPU_TIMEOUT,
+        _ => GOLEM_GPU_ERROR,
+    }
+}
+
+fn into_c_string(value: Value) -> *mut c_char {
+    // serialized JSON escapes NUL characters.
+    CString::new(value.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    #[test]
+    fn test_detect() {
+        let mut json = ptr::null_mut();
+        let code = unsafe { golem_gpu_detect(&mut json) };
+        assert!(!json.is_null());
+        let value: Value = serde_json::from_str(
+            unsafe { CStr::from_ptr(json) }
+                .to_str()
+                .expect("utf-8 json"),
+        )
+        .expect("valid json");
+        // test machines may have no GPU.
+        match code {
+            GOLEM_GPU_OK => assert!(value.get("gpu").is_some()),
+            _ => assert!(value.get("error").is_some()),
+        }
+        unsafe { golem_gpu_free(json) };
+
+        assert_eq!(
+            unsafe { golem_gpu_detect(ptr::null_mut()) },
+            GOLEM_GPU_INVALID_ARGUMENT
+        );
+    }
+}
@@ -0,0 +1,27 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // model messages are generated by `golem-gpu-info` with `proto` feature,
+    // together with conversions from the model.
+    let model = std::fs::read_to_string("../proto/gpu_info.proto")?;
+    println!("cargo:rerun-if-env-changed=PROTOC");
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    let mut config = tonic_build::configure()
+        .build_client(true)
+        .build_server(true);
+    for line in model.lines() {
+        if let Some(name) = line
+            .strip_prefix("message ")
+            .or_else(|| line.strip_prefix("enum "))
+            .and_then(|rest| rest.split_whitespace().next())
+        {
+            config = config.extern_path(
+                format!(".golem.gpu_info.v1.{name}"),
+                format!("::golem_gpu_info::proto::{name}"),
+            );
+        }
+    }
+    config.compile(&["../proto/gpu_detection.proto"], &["../proto"])?;
+    println!("cargo:rerun-if-changed=../proto");
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! gRPC detection service.
+//!
+//! Runs next to the provider agent with access to the GPU driver and answers
+//! queries of sandboxed runtimes. Listens on the address given as the first
+//! argument, `127.0.0.1:50051` by default.
+
+mod pb {
+    tonic::include_proto!("golem.gpu_info.v1");
+    pub use golem_gpu_info::proto::*;
+}
+
+use golem_gpu_info::{GpuDetection, GpuDetectionBuilder, GpuDetectionError};
+use pb::gpu_detection_server::{GpuDetection as GpuDetectionService, GpuDetectionServer};
+use std::error::Error;
+use std::net::SocketAddr;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:50051";
+
+struct Service {
+    detection: GpuDetection,
+}
+
+#[tonic::async_trait]
+impl GpuDetectionService for Service {
+    async fn detect(
+        &self,
+        _request: Request<pb::DetectRequest>,
+    ) -> Result<Response<pb::Gpu>, Status> {
+        let gpu = self.detection.detect_async().await.map_err(status)?;
+        Ok(Response::new(gpu.into()))
+    }
+
+    async fn search_by_uuid(
+        &self,
+        request: Request<pb::SearchByUuidRequest>,
+    ) -> Result<Response<pb::Device>, Status> {
+        let uuid = request.into_inner().uuid;
+        let device = blocking(&self.detection, move |detection| {
+            detection.search_by_uuid(&uuid)
+        })
+        .await?;
+        Ok(Response::new(device.into()))
+    }
+
+    async fn telemetry(
+        &self,
+        request: Request<pb::TelemetryRequest>,
+    ) -> Result<Response<pb::Telemetry>, Status> {
+        let uuid = request.into_inner().uuid;
+        let telemetry =
+            blocking(&self.detection, move |detection| detection.telemetry(&uuid)).await?;
+        Ok(Response::new(telemetry.into()))
+    }
+}
+
+// Driver calls block, so they must not run on the async executor.
+async fn blocking<T, F>(detection: &GpuDetection, f: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce(&GpuDetection) -> Result<T, GpuDetectionError> + Send + 'static,
+{
+    let detection = detection.clone();
+    tokio::task::spawn_blocking(move || f(&detection))
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(status)
+}
+
+fn status(e: GpuDetectionError) -> Status {
+    match e {
+        GpuDetectionError::NotFound { .. } => Status::not_found(e.to_string()),
+        GpuDetectionError::Timeout(_) => Status::deadline_exceeded(e.to_string()),
+        e => Status::internal(e.to_string()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let addr: SocketAddr = std::env::args()
+        .nth(1)
+        .as_deref()
+        .unwrap_or(DEFAULT_ADDR)
+        .parse()?;
+    let detection = GpuDetectionBuilder::default().init_async().await?;
+
+    Server::builder()
+        .add_service(GpuDetectionServer::new(Service { detection }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Python bindings of GPU detection.
+//!
+//! ```python
+//! import golem_gpu_info
+//!
+//! gpu = golem_gpu_info.detect()
+//! for uuid in gpu["d0"]["uuids"]:
+//!     print(golem_gpu_info.telemetry(uuid))
+//! ```
+//!
+//! Values are returned as plain dicts with the same keys as the serialized model.
+
+// `::` as `#[pymodule] fn golem_gpu_info` below shadows the crate name.
+use ::golem_gpu_info::{GpuDetection, GpuDetectionBuilder, GpuDetectionError};
+use pyo3::exceptions::{PyLookupError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use serde::Serialize;
+use std::sync::Mutex;
+
+// Backends are initialized on first use and shared by all calls.
+static DETECTION: Mutex<Option<GpuDetection>> = Mutex::new(None);
+
+fn detection() -> Result<GpuDetection, GpuDetectionError> {
+    let mut detection = DETECTION.lock().unwrap();
+    if let Some(detection) = detection.as_ref() {
+        return Ok(detection.clone());
+    }
+    let initialized = GpuDetectionBuilder::default().init()?;
+    *detection = Some(initialized.clone());
+    Ok(initialized)
+}
+
+fn to_py_err(e: GpuDetectionError) -> PyErr {
+    match e {
+        GpuDetectionError::NotFound { .. } => PyLookupError::new_err(e.to_string()),
+        e => PyRuntimeError::new_err(e.to_string()),
+    }
+}
+
+fn to_python(py: Python<'_>, value: &impl Serialize) -> PyResult<PyObject> {
+    let text = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (text,))?.into())
+}
+
+/// Detects available GPUs.
+#[pyfunction]
+fn detect(py: Python<'_>) -> PyResult<PyObject> {
+    let gpu = py
+        .allow_threads(|| detection()?.detect())
+        .map_err(to_py_err)?;
+    to_python(py, &gpu)
+}
+
+/// Finds single device by uuid, raises `LookupError` if not found.
+#[pyfunction]
+fn search_by_uuid(py: Python<'_>, uuid: &str) -> PyResult<PyObject> {
+    let device = py
+        .allow_threads(|| detection()?.search_by_uuid(uuid))
+        .map_err(to_py_err)?;
+    to_python(py, &device)
+}
+
+/// Samples live state of device with given uuid.
+#[pyfunction]
+fn telemetry(py: Python<'_>, uuid: &str) -> PyResult<PyObject> {
+    let telemetry = py
+        .allow_threads(|| detection()?.telemetry(uuid))
+        .map_err(to_py_err)?;
+    to_python(py, &telemetry)
+}
+
+#[pymodule]
+fn golem_gpu_info(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(detect, m)?)?;
+    m.add_function(wrap_pyfunction!(search_by_uuid, m)?)?;
+    m.add_function(wrap_pyfunction!(telemetry, m)?)?;
+    Ok(())
+}
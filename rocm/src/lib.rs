@@ -0,0 +1,133 @@
+#![deny(missing_docs)]
+//! ROCm SMI queries used by `golem_gpu_info` on AMD cards.
+//!
+//! `librocm_smi64` is loaded at run time, from the default library search or a given path.
+//! Kept out of the main crate, because calling it needs unsafe code.
+
+use rocm_smi_lib_sys::bindings::{RsmiFrequencies, RsmiMemoryType};
+use rocm_smi_lib_sys::RawRsmi;
+use std::ffi::CStr;
+use std::path::Path;
+use std::sync::Mutex;
+
+pub use rocm_smi_lib_sys::bindings::RsmiClkType;
+pub use rocm_smi_lib_sys::error::RocmErr;
+
+/// Size of buffers for device names, the same as `rocm_smi_lib` uses.
+const NAME_SIZE: usize = 64;
+
+/// Library paths given to [`RawRsmi::with_path`], which holds them for the lifetime
+/// of the process, leaked once per distinct path.
+static LIB_PATHS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// Initialized ROCm SMI library, shut down on drop.
+pub struct RocmSmi {
+    raw: RawRsmi,
+    device_count: u32,
+}
+
+impl RocmSmi {
+    /// Loads ROCm SMI from `lib_path`, or `librocm_smi64.so` from the default library
+    /// search when `None`, and initializes it.
+    pub fn init(lib_path: Option<&Path>) -> Result<Self, RocmErr> {
+        // SAFETY: `rsmi_init` is called with no flags, as `rocm_smi_lib` does.
+        let mut raw = match lib_path {
+            Some(path) => unsafe { RawRsmi::with_path(0, static_path(path)?) },
+            None => unsafe { RawRsmi::new(0) },
+        }?;
+        let mut device_count = 0u32;
+        // SAFETY: the count is written to a valid `u32`.
+        unsafe { raw.rsmi_num_monitor_devices(&mut device_count) }.try_err()?;
+        Ok(RocmSmi { raw, device_count })
+    }
+
+    /// Number of devices seen at initialization.
+    pub fn device_count(&self) -> u32 {
+        self.device_count
+    }
+
+    /// PCI location of device `dv_ind` as BDFID:
+    /// `domain << 32 | bus << 8 | device << 3 | function`.
+    pub fn pci_id(&mut self, dv_ind: u32) -> Result<u64, RocmErr> {
+        let mut bdfid = 0u64;
+        // SAFETY: the id is written to a valid `u64`.
+        unsafe { self.raw.rsmi_dev_pci_id_get(dv_ind, &mut bdfid) }.try_err()?;
+        Ok(bdfid)
+    }
+
+    /// Marketing name of device `dv_ind`.
+    pub fn name(&mut self, dv_ind: u32) -> Result<String, RocmErr> {
+        let mut buf = [0u8; NAME_SIZE];
+        // SAFETY: ROCm SMI writes at most `NAME_SIZE` bytes, including the terminating nul.
+        unsafe {
+            self.raw
+                .rsmi_dev_name_get(dv_ind, buf.as_mut_ptr().cast(), NAME_SIZE)
+        }
+        .try_err()?;
+        let name =
+            CStr::from_bytes_until_nul(&buf).map_err(|_| RocmErr::RsmiStringConversionError)?;
+        Ok(name.to_string_lossy().into_owned())
+    }
+
+    /// Supported frequencies of clock `clk_type` of device `dv_ind` in MHz.
+    pub fn supported_frequencies(
+        &mut self,
+        dv_ind: u32,
+        clk_type: RsmiClkType,
+    ) -> Result<Vec<u64>, RocmErr> {
+        let mut frequencies = RsmiFrequencies::default();
+        // SAFETY: the frequencies are written to a valid `RsmiFrequencies`.
+        unsafe {
+            self.raw
+                .rsmi_dev_gpu_clk_freq_get(dv_ind, clk_type, &mut frequencies)
+        }
+        .try_err()?;
+        let supported = (frequencies.num_supported as usize).min(frequencies.frequency.len());
+        Ok(frequencies.frequency[..supported].to_vec())
+    }
+
+    /// Total VRAM of device `dv_ind` in bytes.
+    pub fn vram_total(&mut self, dv_ind: u32) -> Result<u64, RocmErr> {
+        let mut total = 0u64;
+        // SAFETY: the size is written to a valid `u64`.
+        unsafe {
+            self.raw
+                .rsmi_dev_memory_total_get(dv_ind, RsmiMemoryType::RsmiMemTypeVram, &mut total)
+        }
+        .try_err()?;
+        Ok(total)
+    }
+}
+
+/// `path` with the lifetime required by [`RawRsmi::with_path`].
+fn static_path(path: &Path) -> Result<&'static str, RocmErr> {
+    // libloading takes the path as `&str` here.
+    let path = path.to_str().ok_or(RocmErr::RsmiLibLoadingError)?;
+    let mut paths = LIB_PATHS.lock().unwrap();
+    if let Some(&known) = paths.iter().find(|&&known| known == path) {
+        return Ok(known);
+    }
+    let leaked: &'static str = path.to_owned().leak();
+    paths.push(leaked);
+    Ok(leaked)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_static_path() {
+        let first = static_path(Path::new("/opt/rocm/lib/librocm_smi64.so")).unwrap();
+        let second = static_path(Path::new("/opt/rocm/lib/librocm_smi64.so")).unwrap();
+        assert_eq!(first, "/opt/rocm/lib/librocm_smi64.so");
+        // the same path is leaked only once.
+        assert!(std::ptr::eq(first, second));
+    }
+
+    #[test]
+    fn test_init_missing_library() {
+        let err = RocmSmi::init(Some(Path::new("/nonexistent/librocm_smi64.so"))).err();
+        assert_eq!(err, Some(RocmErr::RsmiLibLoadingError));
+    }
+}
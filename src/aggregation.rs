@@ -0,0 +1,338 @@
+//! Grouping of identical cards.
+
+use crate::model::{Device, DeviceClocks, DeviceMemory};
+use crate::pci::normalize_bus_id;
+#[cfg(feature = "deserialize")]
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Device properties compared when grouping cards.
+///
+/// Model, CUDA attributes, MIG partitioning and status are always compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "deserialize",
+    derive(Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum AggregationKey {
+    /// Device clocks.
+    Clocks,
+    /// Total memory and bandwidth.
+    Memory,
+    /// Power limits.
+    Power,
+    /// Video encoder.
+    Encoder,
+    /// Driver persistence mode.
+    PersistenceMode,
+    /// PCI identification (board partner).
+    Pci,
+    /// Bound kernel driver and Windows driver model.
+    KernelDriver,
+    /// External GPU flag and laptop graphics role.
+    External,
+}
+
+impl AggregationKey {
+    /// All keys.
+    pub const ALL: [AggregationKey; 8] = [
+        AggregationKey::Clocks,
+        AggregationKey::Memory,
+        AggregationKey::Power,
+        AggregationKey::Encoder,
+        AggregationKey::PersistenceMode,
+        AggregationKey::Pci,
+        AggregationKey::KernelDriver,
+        AggregationKey::External,
+    ];
+}
+
+/// Rules for grouping identical cards into a single [`Device`].
+///
+/// By default all keys are compared with exact equality.
+#[derive(Clone, Debug)]
+pub struct AggregationPolicy {
+    keys: BTreeSet<AggregationKey>,
+    clock_tolerance_mhz: u32,
+    memory_tolerance_gib: f32,
+}
+
+impl Default for AggregationPolicy {
+    fn default() -> Self {
+        Self {
+            keys: AggregationKey::ALL.into_iter().collect(),
+            clock_tolerance_mhz: 0,
+            memory_tolerance_gib: 0.0,
+        }
+    }
+}
+
+impl AggregationPolicy {
+    /// Compares only given keys (in addition to always compared properties).
+    pub fn keys(mut self, keys: impl IntoIterator<Item = AggregationKey>) -> Self {
+        self.keys = keys.into_iter().collect();
+        self
+    }
+
+    /// Treats clocks differing by at most `mhz` as equal.
+    pub fn clock_tolerance_mhz(mut self, mhz: u32) -> Self {
+        self.clock_tolerance_mhz = mhz;
+        self
+    }
+
+    /// Treats total memory differing by at most `gib` as equal.
+    ///
+    /// Memory bandwidth is still compared exactly.
+    pub fn memory_tolerance_gib(mut self, gib: f32) -> Self {
+        self.memory_tolerance_gib = gib;
+        self
+    }
+
+    /// Groups identical cards into a single [`Device`] with summed `quantity`.
+    ///
+    /// Cards are grouped regardless of their order, groups are listed in order of their
+    /// first card. With tolerances a card joins the first group whose first card matches.
+    pub fn aggregate(&self, devices: impl IntoIterator<Item = Device>) -> Vec<Device> {
+        let mut aggregated: Vec<Device> = Vec::new();
+        // candidate groups by hash of exactly compared properties.
+        let mut groups: HashMap<u64, Vec<usize>> = HashMap::new();
+        for dev in devices {
+            let candidates = groups.entry(self.group_hash(&dev)).or_default();
+            match candidates
+                .iter()
+                .copied()
+                .find(|&group| self.matches(&aggregated[group], &dev))
+            {
+                Some(group) => absorb(&mut aggregated[group], dev),
+                None => {
+                    candidates.push(aggregated.len());
+                    aggregated.push(dev);
+                }
+            }
+        }
+        aggregated
+    }
+
+    /// Hashes properties compared exactly, matching cards have equal hashes.
+    fn group_hash(&self, dev: &Device) -> u64 {
+        let compared = |key| self.keys.contains(&key);
+        let mut hasher = DefaultHasher::new();
+        (&dev.model, &dev.cuda, &dev.mig, dev.status).hash(&mut hasher);
+        if compared(AggregationKey::Clocks) && self.clock_tolerance_mhz == 0 {
+            dev.clocks.hash(&mut hasher);
+        }
+        // total memory is left to `matches`, `0.0` equals `-0.0` there.
+        if compared(AggregationKey::Memory) {
+            dev.memory.bandwidth_gib.hash(&mut hasher);
+        }
+        if compared(AggregationKey::Power) {
+            dev.power.hash(&mut hasher);
+        }
+        if compared(AggregationKey::Encoder) {
+            dev.encoder.hash(&mut hasher);
+        }
+        if compared(AggregationKey::PersistenceMode) {
+            dev.persistence_mode.hash(&mut hasher);
+        }
+        if compared(AggregationKey::Pci) {
+            dev.pci.hash(&mut hasher);
+        }
+        if compared(AggregationKey::KernelDriver) {
+            (&dev.kernel_driver, dev.driver_model).hash(&mut hasher);
+        }
+        if compared(AggregationKey::External) {
+            (dev.external, dev.laptop).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Checks if two devices belong to the same group.
+    pub fn matches(&self, a: &Device, b: &Device) -> bool {
+        let ignored = |key| !self.keys.contains(&key);
+        a.model == b.model
+            && a.cuda == b.cuda
+            && a.mig == b.mig
+            && a.status == b.status
+            && (ignored(AggregationKey::Clocks) || self.clocks_match(&a.clocks, &b.clocks))
+            && (ignored(AggregationKey::Memory) || self.memory_match(&a.memory, &b.memory))
+            && (ignored(AggregationKey::Power) || a.power == b.power)
+            && (ignored(AggregationKey::Encoder) || a.encoder == b.encoder)
+            && (ignored(AggregationKey::PersistenceMode)
+                || a.persistence_mode == b.persistence_mode)
+            && (ignored(AggregationKey::Pci) || a.pci == b.pci)
+            && (ignored(AggregationKey::KernelDriver)
+                || (a.kernel_driver == b.kernel_driver && a.driver_model == b.driver_model))
+            && (ignored(AggregationKey::External)
+                || (a.external == b.external && a.laptop == b.laptop))
+    }
+
+    fn clocks_match(&self, a: &DeviceClocks, b: &DeviceClocks) -> bool {
+        let close = |a: u32, b: u32| a.abs_diff(b) <= self.clock_tolerance_mhz;
+        close(a.graphics_mhz, b.graphics_mhz)
+            && close(a.memory_mhz, b.memory_mhz)
+            && close(a.sm_mhz, b.sm_mhz)
+            && match (a.video_mhz, b.video_mhz) {
+                (Some(a), Some(b)) => close(a, b),
+                (a, b) => a == b,
+            }
+            && a.power_source == b.power_source
+    }
+
+    fn memory_match(&self, a: &DeviceMemory, b: &DeviceMemory) -> bool {
+        (a.total_gib - b.total_gib).abs() <= self.memory_tolerance_gib
+            && a.bandwidth_gib == b.bandwidth_gib
+    }
+}
+
+/// Adds cards of `other` to group `dev`.
+pub(crate) fn absorb(dev: &mut Device, other: Device) {
+    dev.quantity += other.quantity;
+    dev.uuids.extend(other.uuids);
+    dev.indices.extend(other.indices);
+    dev.pci_bus_ids.extend(other.pci_bus_ids);
+}
+
+/// Removes cards listed by more than one backend, keeping the most detailed entry
+/// (the earliest backend on a tie).
+///
+/// `backends` lists single cards detected by each backend. Cards are matched by PCI bus id,
+/// cards without one are kept.
+pub(crate) fn dedup_by_bus_id(backends: &mut [Vec<Device>]) {
+    let mut best: HashMap<String, (usize, usize, usize)> = HashMap::new();
+    for (backend, devices) in backends.iter().enumerate() {
+        for (position, dev) in devices.iter().enumerate() {
+            let Some(bus_id) = dev.pci_bus_ids.first() else {
+                continue;
+            };
+            let entry = (backend, position, detail(dev));
+            best.entry(normalize_bus_id(bus_id))
+                .and_modify(|best| {
+                    if entry.2 > best.2 {
+                        *best = entry;
+                    }
+                })
+                .or_insert(entry);
+        }
+    }
+    for (backend, devices) in backends.iter_mut().enumerate() {
+        let mut position = 0;
+        devices.retain(|dev| {
+            let keep = dev.pci_bus_ids.first().is_none_or(|bus_id| {
+                best.get(&normalize_bus_id(bus_id))
+                    .is_some_and(|&(b, p, _)| (b, p) == (backend, position))
+            });
+            position += 1;
+            keep
+        });
+    }
+}
+
+/// Number of properties the backend was able to report.
+fn detail(dev: &Device) -> usize {
+    [
+        dev.cuda.is_some(),
+        dev.clocks != DeviceClocks::default(),
+        dev.memory.bandwidth_gib.is_some(),
+        dev.power.is_some(),
+        dev.encoder.is_some(),
+        dev.persistence_mode.is_some(),
+        dev.pci.is_some(),
+        dev.kernel_driver.is_some(),
+        dev.external.is_some(),
+        dev.laptop.is_some(),
+        dev.driver_model.is_some(),
+        dev.mig.is_some(),
+        dev.dynamic.is_some(),
+    ]
+    .into_iter()
+    .filter(|&known| known)
+    .count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gen_device(graphics_mhz: u32) -> Device {
+        Device {
+            model: "NVIDIA GeForce RTX 3090".into(),
+            clocks: DeviceClocks {
+                graphics_mhz,
+                ..Default::default()
+            },
+            quantity: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clock_tolerance() {
+        let devices = vec![gen_device(2100), gen_device(2101)];
+
+        assert_eq!(
+            AggregationPolicy::default()
+                .aggregate(devices.clone())
+                .len(),
+            2
+        );
+
+        let aggregated = AggregationPolicy::default()
+            .clock_tolerance_mhz(5)
+            .aggregate(devices.clone());
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated[0].quantity, 2);
+
+        let aggregated = AggregationPolicy::default()
+            .keys([AggregationKey::Memory])
+            .aggregate(devices);
+        assert_eq!(aggregated.len(), 1);
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let devices = vec![gen_device(2100), gen_device(1900), gen_device(2100)];
+        let aggregated = AggregationPolicy::default().aggregate(devices.clone());
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(aggregated[0].clocks.graphics_mhz, 2100);
+        assert_eq!(aggregated[0].quantity, 2);
+        assert_eq!(aggregated[1].quantity, 1);
+
+        let aggregated = AggregationPolicy::default()
+            .clock_tolerance_mhz(5)
+            .aggregate(devices);
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(aggregated[0].quantity, 2);
+    }
+
+    #[test]
+    fn test_dedup_by_bus_id() {
+        let card = |model: &str, bus_id: &str| Device {
+            model: model.into(),
+            quantity: 1,
+            pci_bus_ids: vec![bus_id.into()],
+            ..Default::default()
+        };
+        let mut backends = vec![
+            vec![card("AMD Radeon RX 7900 XTX", "0000:03:00.0")],
+            vec![
+                Device {
+                    clocks: DeviceClocks {
+                        graphics_mhz: 2500,
+                        ..Default::default()
+                    },
+                    ..card("Radeon RX 7900 XTX", "03:00.0")
+                },
+                card("Radeon RX 6600", "0000:04:00.0"),
+                card("Radeon RX 7900 XTX", "0000:03:00.0"),
+            ],
+        ];
+        dedup_by_bus_id(&mut backends);
+        assert!(backends[0].is_empty());
+        assert_eq!(backends[1].len(), 2);
+        assert_eq!(backends[1][0].clocks.graphics_mhz, 2500);
+        assert_eq!(backends[1][1].model, "Radeon RX 6600");
+    }
+}
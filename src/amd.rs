@@ -1,16 +1,43 @@
-use super::{bytes_to_gib, GpuDetectionError, Result};
-use crate::model::{Device, DeviceClocks, DeviceMemory, GpuApiInfo};
+//! AMD cards through ROCm System Management Interface (ROCm SMI).
+
+use super::{bytes_to_gib, ErrorKind, GpuDetectionError, Result};
+#[cfg(target_os = "linux")]
+use crate::health::SHUTDOWN_MARGIN_C;
+use crate::health::{HealthReport, HealthStatus};
+use crate::model::{
+    parse_version, Device, DeviceClocks, DeviceMemory, DevicePci, DeviceStatus, GpuApiInfo,
+    GpuProcess, KernelDriver, LaptopGraphics, PowerSource, Telemetry,
+};
 use crate::platform::{Detection, Flags, Platform};
-use rocm_smi_lib::error::RocmErr;
-use rocm_smi_lib::queries::performance::RsmiClkType;
-use rocm_smi_lib::RocmSmi;
+use crate::watch::DeviceErrorEvent;
+#[cfg(target_os = "linux")]
+use crate::watch::DeviceErrorKind;
+use golem_gpu_rocm::{RocmErr, RocmSmi, RsmiClkType};
 use std::fmt::{Debug, Display, Formatter};
-use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use std::io;
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
 use thiserror::Error;
 
+/// ROCm SMI error.
 #[derive(Error, Debug)]
 pub struct AmdError(RocmErr);
 
+/// Oldest ROCm release `(major, minor)` the ROCm SMI bindings work with.
+pub const MIN_SUPPORTED_ROCM: (u32, u32) = (6, 0);
+
+/// How often RAS error counters are read by [`Detection::watch_errors`].
+#[cfg(target_os = "linux")]
+const RAS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 impl From<RocmErr> for GpuDetectionError {
     fn from(value: RocmErr) -> Self {
         GpuDetectionError::AmdError(AmdError(value))
@@ -23,6 +50,18 @@ impl Display for AmdError {
     }
 }
 
+pub(crate) fn error_kind(e: &AmdError) -> ErrorKind {
+    match &e.0 {
+        RocmErr::RsmiStatusInitError => ErrorKind::DriverNotFound,
+        RocmErr::RsmiStatusPermission => ErrorKind::PermissionDenied,
+        RocmErr::RsmiStatusNotSupported | RocmErr::RsmiStatusNotYetImplemented => {
+            ErrorKind::NotSupported
+        }
+        RocmErr::RsmiStatusNotFound => ErrorKind::NotFound,
+        _ => ErrorKind::Other,
+    }
+}
+
 struct AmdPlatform;
 
 impl Platform for AmdPlatform {
@@ -30,15 +69,27 @@ impl Platform for AmdPlatform {
         "amd"
     }
 
-    fn init(&self, _flags: Flags) -> crate::Result<Box<dyn Detection>> {
-        eprintln!("try");
-        let smi = Mutex::new(RocmSmi::init().inspect_err(|e| eprintln!("err={}", e.to_string()))?);
-        Ok(Box::new(AmdDetector { smi }))
+    fn init(&self, flags: Flags) -> crate::Result<Box<dyn Detection>> {
+        check_driver(&flags.sysfs_root, flags.force)?;
+        let smi = RocmSmi::init(flags.rocm_lib_path.as_deref()).map_err(|e| {
+            // ROCm fails the same way on inaccessible device nodes.
+            access_error(&flags.sysfs_root).unwrap_or_else(|| {
+                GpuDetectionError::RuntimeUnavailable {
+                    runtime: "ROCm".into(),
+                    message: AmdError(e).to_string(),
+                }
+            })
+        })?;
+        Ok(Box::new(AmdDetector {
+            smi: Mutex::new(smi),
+            flags,
+        }))
     }
 }
 
 struct AmdDetector {
     smi: Mutex<RocmSmi>,
+    flags: Flags,
 }
 
 impl Detection for AmdDetector {
@@ -47,87 +98,645 @@ impl Detection for AmdDetector {
     }
 
     fn devices(&self) -> crate::Result<Vec<Device>> {
+        self.devices_partial()?.into_iter().collect()
+    }
+
+    fn devices_partial(&self) -> crate::Result<Vec<crate::Result<Device>>> {
         let mut smi = self.smi.lock().unwrap();
-        let device_count = smi.get_device_count();
-        (0..device_count)
-            .map(|dv_ind| device_info(&mut smi, dv_ind))
-            .collect()
+        let device_count = smi.device_count();
+        Ok((0..device_count)
+            .map(|dv_ind| device_info(&mut smi, dv_ind, &self.flags))
+            .collect())
     }
 
     fn device_by_uuid(&self, uuid: &str) -> crate::Result<Option<Device>> {
         let mut smi = self.smi.lock().unwrap();
-        let device_count = smi.get_device_count();
-        Ok(
-            if let Some((_, dv_ind)) = (0..device_count)
-                .filter_map(|dv_ind| Some((smi.get_device_pcie_data(dv_ind).ok()?, dv_ind)))
-                .map(|(pci, dv_ind)| (format!("{:016x}", pci.id), dv_ind))
-                .find(|(id, _)| id == uuid)
-            {
-                Some(device_info(&mut smi, dv_ind)?)
-            } else {
-                None
-            },
-        )
-    }
-}
-
-fn device_info(smi: &mut RocmSmi, dv_ind: u32) -> Result<Device> {
-    let clocks = clocks(smi, dv_ind)?;
-    let memory = memory(smi, dv_ind)?;
-    let ids = smi.get_device_identifiers(dv_ind)?;
-
-    Ok(Device {
-        model: ids.name?,
+        Ok(match find_device(&mut smi, uuid, &self.flags.sysfs_root) {
+            Some(dv_ind) => Some(device_info(&mut smi, dv_ind, &self.flags)?),
+            None => None,
+        })
+    }
+
+    fn device_by_pci_bus_id(&self, pci_bus_id: &str) -> crate::Result<Option<Device>> {
+        let mut smi = self.smi.lock().unwrap();
+        let device_count = smi.device_count();
+        let dv_ind = (0..device_count).find(|&dv_ind| {
+            smi.pci_id(dv_ind)
+                .is_ok_and(|bdfid| bus_id(bdfid) == pci_bus_id)
+        });
+        Ok(match dv_ind {
+            Some(dv_ind) => Some(device_info(&mut smi, dv_ind, &self.flags)?),
+            None => None,
+        })
+    }
+
+    fn device_by_index(&self, index: u32) -> crate::Result<Option<Device>> {
+        let mut smi = self.smi.lock().unwrap();
+        if index >= smi.device_count() {
+            return Ok(None);
+        }
+        Ok(Some(device_info(&mut smi, index, &self.flags)?))
+    }
+
+    fn telemetry(&self, uuid: &str) -> crate::Result<Option<Telemetry>> {
+        let mut smi = self.smi.lock().unwrap();
+        let Some(dv_ind) = find_device(&mut smi, uuid, &self.flags.sysfs_root) else {
+            return Ok(None);
+        };
+        let bus_id = bus_id(smi.pci_id(dv_ind)?);
+        let telemetry = telemetry(&self.flags.sysfs_root, &bus_id).ok_or_else(|| {
+            GpuDetectionError::NotSupported(format!(
+                "reading sensors of device {bus_id} without amdgpu sysfs"
+            ))
+        })?;
+        Ok(Some(telemetry))
+    }
+
+    fn processes(&self, uuid: &str) -> crate::Result<Option<Vec<GpuProcess>>> {
+        let mut smi = self.smi.lock().unwrap();
+        let Some(dv_ind) = find_device(&mut smi, uuid, &self.flags.sysfs_root) else {
+            return Ok(None);
+        };
+        let bus_id = bus_id(smi.pci_id(dv_ind)?);
+        // Not listing anything would falsely report the device as idle.
+        let processes = kfd_processes(&self.flags.sysfs_root, &bus_id).ok_or_else(|| {
+            GpuDetectionError::NotSupported(format!(
+                "listing processes of device {bus_id} without KFD"
+            ))
+        })?;
+        Ok(Some(processes))
+    }
+
+    fn health_check(&self) -> crate::Result<Vec<HealthReport>> {
+        let mut smi = self.smi.lock().unwrap();
+        let device_count = smi.device_count();
+        Ok((0..device_count)
+            .map(|dv_ind| {
+                let uuid = device_uuid(&mut smi, dv_ind, &self.flags.sysfs_root).ok();
+                let status = match (&uuid, smi.pci_id(dv_ind)) {
+                    (Some(_), Ok(bdfid)) => health_status(&self.flags.sysfs_root, &bus_id(bdfid)),
+                    _ => HealthStatus::Lost,
+                };
+                HealthReport {
+                    uuid,
+                    index: dv_ind,
+                    status,
+                }
+            })
+            .collect())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn watch_errors(
+        &self,
+        on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        let sysfs_root = &self.flags.sysfs_root;
+        let devices = {
+            let mut smi = self.smi.lock().unwrap();
+            (0..smi.device_count())
+                .map(|dv_ind| {
+                    let uuid = device_uuid(&mut smi, dv_ind, sysfs_root)?;
+                    Ok((uuid, bus_id(smi.pci_id(dv_ind)?)))
+                })
+                .collect::<crate::Result<Vec<_>>>()?
+        };
+        // ROCm SMI events do not cover ECC, RAS counters are polled instead.
+        let ras_errors = |bus_id: &str| crate::sysfs::amdgpu::ras_errors(sysfs_root, bus_id);
+        let mut counts: Vec<_> = devices
+            .iter()
+            .map(|(_, bus_id)| ras_errors(bus_id))
+            .collect();
+        // consumer cards have no RAS.
+        if counts.iter().all(Option::is_none) {
+            return Ok(());
+        }
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(RAS_POLL_INTERVAL);
+            for ((uuid, bus_id), last) in devices.iter().zip(&mut counts) {
+                let current = ras_errors(bus_id);
+                let (Some((last_ue, last_ce)), Some((ue, ce))) = (*last, current) else {
+                    continue;
+                };
+                *last = current;
+                let kinds = [
+                    (ce > last_ce).then_some(DeviceErrorKind::SingleBitEcc),
+                    (ue > last_ue).then_some(DeviceErrorKind::DoubleBitEcc),
+                ];
+                for kind in kinds.into_iter().flatten() {
+                    let event = DeviceErrorEvent {
+                        uuid: Some(uuid.clone()),
+                        kind,
+                    };
+                    if !on_event(event) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn watch_errors(
+        &self,
+        _on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        _stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        // RAS counters are read from sysfs.
+        Ok(())
+    }
+
+    fn version(&self) -> Option<String> {
+        // library version is not queried, see `check_environment`.
+        None
+    }
+
+    fn check_environment(&self) -> Result<()> {
+        let Some(installed) = rocm_version() else {
+            // distribution packages do not install the version file.
+            return Ok(());
+        };
+        match unsupported_rocm(&installed) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Error for a ROCm release `installed` older than [`MIN_SUPPORTED_ROCM`], unparsable
+/// versions are let through.
+fn unsupported_rocm(installed: &str) -> Option<GpuDetectionError> {
+    let version = parse_version(installed)?;
+    (version < MIN_SUPPORTED_ROCM).then(|| GpuDetectionError::UnsupportedDriver {
+        component: "ROCm".into(),
+        installed: installed.to_string(),
+        required: format!("{}.{}", MIN_SUPPORTED_ROCM.0, MIN_SUPPORTED_ROCM.1),
+    })
+}
+
+/// Reads installed ROCm release (e.g. `6.0.2-115`) from `$ROCM_PATH/.info/version`.
+fn rocm_version() -> Option<String> {
+    let root = std::env::var_os("ROCM_PATH").unwrap_or_else(|| "/opt/rocm".into());
+    let version = std::fs::read_to_string(Path::new(&root).join(".info/version")).ok()?;
+    Some(version.trim().to_string())
+}
+
+/// Device node of the ROCm compute driver.
+#[cfg(target_os = "linux")]
+const KFD: &str = "/dev/kfd";
+/// Directory with DRM render nodes (`renderD128`, ...).
+#[cfg(target_os = "linux")]
+const DRI: &str = "/dev/dri";
+#[cfg(target_os = "linux")]
+const GROUPS: &str = "/etc/group";
+#[cfg(target_os = "linux")]
+const AMD_VENDOR_ID: u16 = 0x1002;
+
+/// Tells apart a missing kernel driver and a driver without compute support,
+/// which ROCm init reports as the same opaque error.
+#[cfg(target_os = "linux")]
+fn check_driver(sysfs_root: &Path, force: bool) -> Result<()> {
+    if let Some(e) = crate::sysfs::missing_module(sysfs_root, "amdgpu", AMD_VENDOR_ID, force) {
+        return Err(e);
+    }
+    if !Path::new(KFD).exists() {
+        // without sysfs modules a host without AMD cards looks the same.
+        let loaded = crate::sysfs::module_loaded(sysfs_root, "amdgpu") == Some(true);
+        return Err(if force || loaded {
+            GpuDetectionError::DeviceNodeMissing { path: KFD.into() }
+        } else {
+            GpuDetectionError::not_found()
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_driver(_sysfs_root: &Path, _force: bool) -> Result<()> {
+    Ok(())
+}
+
+/// Finds ROCm device nodes the current user can not open, render nodes of
+/// other vendors' cards are not needed and not checked.
+#[cfg(target_os = "linux")]
+fn access_error(sysfs_root: &Path) -> Option<GpuDetectionError> {
+    use std::os::unix::fs::MetadataExt;
+
+    let render_nodes = std::fs::read_dir(DRI)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with("renderD") && is_amd_node(sysfs_root, name))
+        })
+        .map(|entry| entry.path());
+    let mut denied: Vec<PathBuf> = std::iter::once(PathBuf::from(KFD))
+        .chain(render_nodes)
+        .filter(|node| {
+            let opened = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(node);
+            matches!(opened, Err(e) if e.kind() == io::ErrorKind::PermissionDenied)
+        })
+        .collect();
+    denied.sort();
+    let groups = std::fs::read_to_string(GROUPS).unwrap_or_default();
+    let mut owners: Vec<String> = denied
+        .iter()
+        .filter_map(|node| group_name(&groups, std::fs::metadata(node).ok()?.gid()))
+        .collect();
+    owners.sort();
+    owners.dedup();
+    Some(GpuDetectionError::PermissionDenied {
+        path: denied.into_iter().next()?,
+        groups: owners,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn access_error(_sysfs_root: &Path) -> Option<GpuDetectionError> {
+    None
+}
+
+/// Checks if DRM node `name` (e.g. `renderD128`) belongs to an AMD card.
+#[cfg(target_os = "linux")]
+fn is_amd_node(sysfs_root: &Path, name: &str) -> bool {
+    let vendor = sysfs_root
+        .join("class/drm")
+        .join(name)
+        .join("device/vendor");
+    std::fs::read_to_string(vendor).is_ok_and(|vendor| {
+        u16::from_str_radix(vendor.trim().trim_start_matches("0x"), 16) == Ok(AMD_VENDOR_ID)
+    })
+}
+
+/// Finds name of group `gid` in `/etc/group` contents.
+#[cfg(target_os = "linux")]
+fn group_name(groups: &str, gid: u32) -> Option<String> {
+    groups.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id = fields.nth(1)?.parse::<u32>().ok()?;
+        (id == gid).then(|| name.to_string())
+    })
+}
+
+fn device_info(smi: &mut RocmSmi, dv_ind: u32, flags: &Flags) -> Result<Device> {
+    let mut unsupported = Vec::new();
+    let bus_id = bus_id(smi.pci_id(dv_ind)?);
+    let clocks = clocks(smi, dv_ind, &flags.sysfs_root, &bus_id, &mut unsupported);
+    let mut clocks = (flags.tolerate(clocks, "clock", &mut unsupported)?).unwrap_or_default();
+    let memory = memory(smi, dv_ind);
+    let memory = (flags.tolerate(memory, "memory", &mut unsupported)?).unwrap_or_default();
+    let model = smi.name(dv_ind)?;
+    let uuids = vec![device_uuid(smi, dv_ind, &flags.sysfs_root)?];
+    let pci = device_pci(&flags.sysfs_root, &bus_id);
+    let kernel_driver = kernel_driver(&flags.sysfs_root, &bus_id);
+    let external = external(&flags.sysfs_root, &bus_id);
+    let laptop = laptop(&flags.sysfs_root, &bus_id);
+    // max clocks of laptop GPUs are lower on battery.
+    if laptop.is_some() {
+        clocks.power_source = power_source(&flags.sysfs_root);
+    }
+
+    let mut device = Device {
+        model,
+        model_normalized: None,
         cuda: None,
         clocks,
         memory,
+        power: None,
+        encoder: None,
+        persistence_mode: None,
+        pci,
+        kernel_driver,
+        external,
+        laptop,
+        driver_model: None,
+        mig: None,
+        status: DeviceStatus::Available,
         quantity: 1,
-    })
+        uuids,
+        indices: vec![dv_ind],
+        pci_bus_ids: vec![bus_id],
+        dynamic: None,
+        compute: None,
+        enriched: Vec::new(),
+        estimated: Vec::new(),
+        unsupported,
+    };
+    flags.apply(&mut device);
+    Ok(device)
 }
 
-fn clocks(smi: &mut RocmSmi, dv_ind: u32) -> Result<DeviceClocks> {
-    let sm_mhz = smi
-        .get_device_frequency(dv_ind, RsmiClkType::RsmiClkTypeSys)?
-        .supported
-        .into_iter()
-        .filter_map(|x| x.try_into().ok())
-        .max()
-        .unwrap_or_default();
-    let memory_mhz = smi
-        .get_device_frequency(dv_ind, RsmiClkType::RsmiClkTypeMem)?
-        .supported
-        .into_iter()
-        .filter_map(|x| x.try_into().ok())
-        .max()
-        .unwrap_or_default();
-    let graphics_mhz = smi
-        .get_device_frequency(dv_ind, RsmiClkType::RsmiClkTypeDcef)?
-        .supported
+fn device_uuid(smi: &mut RocmSmi, dv_ind: u32, sysfs_root: &Path) -> Result<String> {
+    Ok(device_ids(smi, dv_ind, sysfs_root)?.swap_remove(0))
+}
+
+/// Identifiers of a card, most stable first, the first one is reported as its uuid.
+///
+/// amdgpu exposes the unique id (`rsmi_dev_unique_id`, "Unique ID" in rocm-smi) and
+/// board serial number on Vega and newer cards. Older cards only have the PCI location
+/// (BDFID), which changes when the card moves to another slot.
+fn device_ids(smi: &mut RocmSmi, dv_ind: u32, sysfs_root: &Path) -> Result<Vec<String>> {
+    let bdfid = smi.pci_id(dv_ind)?;
+    let bus_id = bus_id(bdfid);
+    let mut ids: Vec<String> = ["unique_id", "serial_number"]
         .into_iter()
-        .filter_map(|x| x.try_into().ok())
-        .max()
-        .unwrap_or_default();
+        .filter_map(|name| device_attr(sysfs_root, &bus_id, name))
+        .collect();
+    // kept for lookups by ids reported before unique ids were used.
+    ids.push(format!("{bdfid:016x}"));
+    Ok(ids)
+}
+
+/// Index of the device identified by `uuid`, see [`device_ids`].
+fn find_device(smi: &mut RocmSmi, uuid: &str, sysfs_root: &Path) -> Option<u32> {
+    (0..smi.device_count())
+        .find(|&dv_ind| device_ids(smi, dv_ind, sysfs_root).is_ok_and(|ids| id_matches(&ids, uuid)))
+}
+
+/// Checks if `query` is one of `ids`, ignoring case and `0x` prefix
+/// (rocm-smi prints unique id as `0x...`).
+fn id_matches(ids: &[String], query: &str) -> bool {
+    let normalize = |id: &str| {
+        let id = id.trim();
+        let id = id
+            .strip_prefix("0x")
+            .or_else(|| id.strip_prefix("0X"))
+            .unwrap_or(id);
+        id.to_ascii_lowercase()
+    };
+    let query = normalize(query);
+    ids.iter().any(|id| normalize(id) == query)
+}
+
+// ROCm SMI reports PCI location as BDFID: domain << 32 | bus << 8 | device << 3 | function.
+fn bus_id(bdfid: u64) -> String {
+    format!(
+        "{:04x}:{:02x}:{:02x}.{:x}",
+        (bdfid >> 32) & 0xffff_ffff,
+        (bdfid >> 8) & 0xff,
+        (bdfid >> 3) & 0x1f,
+        bdfid & 0x7
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn device_attr(sysfs_root: &Path, bus_id: &str, name: &str) -> Option<String> {
+    crate::sysfs::device_attr(sysfs_root, bus_id, name)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn device_attr(_sysfs_root: &Path, _bus_id: &str, _name: &str) -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn device_pci(sysfs_root: &Path, bus_id: &str) -> Option<DevicePci> {
+    crate::sysfs::pci_ids(sysfs_root, bus_id).map(|ids| DevicePci {
+        vendor_id: ids.vendor_id,
+        device_id: ids.device_id,
+        subsystem_vendor_id: ids.subsystem_vendor_id,
+        subsystem_device_id: ids.subsystem_device_id,
+        partner: crate::pci::vendor_name(ids.subsystem_vendor_id).map(Into::into),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn device_pci(_sysfs_root: &Path, _bus_id: &str) -> Option<DevicePci> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn kernel_driver(sysfs_root: &Path, bus_id: &str) -> Option<KernelDriver> {
+    crate::sysfs::kernel_driver(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_driver(_sysfs_root: &Path, _bus_id: &str) -> Option<KernelDriver> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn external(sysfs_root: &Path, bus_id: &str) -> Option<bool> {
+    crate::sysfs::is_external(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn external(_sysfs_root: &Path, _bus_id: &str) -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn laptop(sysfs_root: &Path, bus_id: &str) -> Option<LaptopGraphics> {
+    crate::sysfs::laptop_graphics(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn laptop(_sysfs_root: &Path, _bus_id: &str) -> Option<LaptopGraphics> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn kfd_processes(sysfs_root: &Path, bus_id: &str) -> Option<Vec<GpuProcess>> {
+    let processes = crate::sysfs::amdgpu::kfd_processes(sysfs_root, bus_id)?;
+    Some(
+        processes
+            .into_iter()
+            .map(|process| GpuProcess {
+                pid: process.pid,
+                name: std::fs::read_to_string(format!("/proc/{}/comm", process.pid))
+                    .ok()
+                    .map(|name| name.trim().to_string()),
+                used_memory_gib: process.vram_bytes.map(bytes_to_gib),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kfd_processes(_sysfs_root: &Path, _bus_id: &str) -> Option<Vec<GpuProcess>> {
+    None
+}
+
+/// Samples amdgpu sensors, ROCm SMI reads the same files.
+#[cfg(target_os = "linux")]
+fn telemetry(sysfs_root: &Path, bus_id: &str) -> Option<Telemetry> {
+    let sensors = crate::sysfs::amdgpu::sensors(sysfs_root, bus_id)?;
+    let utilization = sensors
+        .busy_pct
+        .map(|gpu_pct| crate::model::DeviceUtilization {
+            gpu_pct,
+            memory_pct: sensors.memory_busy_pct.unwrap_or_default(),
+        });
+    let clocks = sensors
+        .sclk_mhz
+        .zip(sensors.mclk_mhz)
+        .map(|(sclk_mhz, mclk_mhz)| DeviceClocks {
+            graphics_mhz: sclk_mhz,
+            memory_mhz: mclk_mhz,
+            sm_mhz: sclk_mhz,
+            video_mhz: sensors.vclk_mhz,
+            power_source: None,
+        });
+    Some(Telemetry {
+        utilization,
+        memory_used_gib: sensors.vram_used_bytes.map(bytes_to_gib),
+        temperature_c: sensors.temperature_c,
+        power_draw_w: sensors.power_w,
+        clocks,
+        compute_mode: None,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn telemetry(_sysfs_root: &Path, _bus_id: &str) -> Option<Telemetry> {
+    None
+}
+
+/// Checks RAS (ECC) counters and temperature of the device given by bus id.
+#[cfg(target_os = "linux")]
+fn health_status(sysfs_root: &Path, bus_id: &str) -> HealthStatus {
+    let Some(sensors) = crate::sysfs::amdgpu::sensors(sysfs_root, bus_id) else {
+        return HealthStatus::Unknown {
+            reason: format!("amdgpu sysfs of device {bus_id} is not readable"),
+        };
+    };
+    let mut reasons = Vec::new();
+    // counters reset on driver load, so that errors before last reboot do not mark device forever.
+    if let Some(errors @ 1..) = sensors.ras_uncorrected {
+        reasons.push(format!("{errors} uncorrected ECC errors since reboot"));
+    }
+    if let (Some(temperature), Some(critical)) = (sensors.temperature_c, sensors.temperature_crit_c)
+    {
+        if temperature + SHUTDOWN_MARGIN_C >= critical {
+            reasons.push(format!(
+                "temperature {temperature}C close to critical threshold {critical}C"
+            ));
+        }
+    }
+    HealthStatus::from_reasons(reasons)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn health_status(_sysfs_root: &Path, _bus_id: &str) -> HealthStatus {
+    HealthStatus::Unknown {
+        reason: "amdgpu sensors are only read on Linux".into(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn dpm_max_mhz(sysfs_root: &Path, bus_id: &str, clock: &str) -> Option<u32> {
+    crate::sysfs::amdgpu::dpm_max_mhz(sysfs_root, bus_id, clock)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn dpm_max_mhz(_sysfs_root: &Path, _bus_id: &str, _clock: &str) -> Option<u32> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn power_source(sysfs_root: &Path) -> Option<PowerSource> {
+    crate::sysfs::power_source(sysfs_root)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn power_source(_sysfs_root: &Path) -> Option<PowerSource> {
+    None
+}
+
+fn clocks(
+    smi: &mut RocmSmi,
+    dv_ind: u32,
+    sysfs_root: &Path,
+    bus_id: &str,
+    unsupported: &mut Vec<String>,
+) -> Result<DeviceClocks> {
+    let sm_mhz = max_clock(smi, dv_ind, RsmiClkType::RsmiClkTypeSys)?.unwrap_or_default();
+    let memory_mhz = max_clock(smi, dv_ind, RsmiClkType::RsmiClkTypeMem)?.unwrap_or_default();
+    let graphics_mhz = max_clock(smi, dv_ind, RsmiClkType::RsmiClkTypeDcef)?.unwrap_or_default();
+    // VCN clocks, VCLK drives encoding and DCLK decoding. Cards without VCN do not
+    // expose them, the ROCm SMI bindings have no VCN clock types.
+    let video_mhz =
+        dpm_max_mhz(sysfs_root, bus_id, "vclk").or_else(|| dpm_max_mhz(sysfs_root, bus_id, "dclk"));
+    if video_mhz.is_none() {
+        unsupported.push("clock.video.mhz".into());
+    }
 
     Ok(DeviceClocks {
         graphics_mhz,
         memory_mhz,
         sm_mhz,
-        video_mhz: None,
+        video_mhz,
+        power_source: None,
     })
 }
 
+/// Highest supported frequency of a clock domain in MHz.
+fn max_clock(smi: &mut RocmSmi, dv_ind: u32, clk_type: RsmiClkType) -> Result<Option<u32>> {
+    Ok(smi
+        .supported_frequencies(dv_ind, clk_type)?
+        .into_iter()
+        .filter_map(|x| x.try_into().ok())
+        .max())
+}
+
 fn memory(smi: &mut RocmSmi, dv_ind: u32) -> Result<DeviceMemory> {
-    let mem = smi.get_device_memory_data(dv_ind)?;
-    let total_gib = bytes_to_gib(mem.vram_total);
+    let total_gib = bytes_to_gib(smi.vram_total(dv_ind)?);
 
     Ok(DeviceMemory {
         bandwidth_gib: None,
+        measured_bandwidth_gib: None,
         total_gib,
     })
 }
 
-static AMD_PLATFORM: AmdPlatform = AmdPlatform;
+pub(crate) fn platform() -> Arc<dyn Platform> {
+    Arc::new(AmdPlatform)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_id_matches() {
+        let ids = vec![
+            "0x5a2c81d2e47f3b01".to_string(),
+            "692140100123".to_string(),
+            format!("{:016x}", 0x0300u64),
+        ];
+        // rocm-smi prints the unique id with the prefix, sysfs without it.
+        assert!(id_matches(&ids, "0x5a2c81d2e47f3b01"));
+        assert!(id_matches(&ids, "5a2c81d2e47f3b01"));
+        assert!(id_matches(&ids, "0X5A2C81D2E47F3B01"));
+        assert!(id_matches(&ids, "692140100123"));
+        // BDFID of 0000:03:00.0, reported as uuid by older versions.
+        assert!(id_matches(&ids, "0000000000000300"));
+        assert!(!id_matches(&ids, "0x5a2c81d2e47f3b02"));
+        assert!(!id_matches(&ids, ""));
+    }
+
+    #[test]
+    fn test_unsupported_rocm() {
+        assert_eq!(parse_version("5.7.1"), Some((5, 7)));
+        let too_old = unsupported_rocm("5.7.1").map(|e| e.kind());
+        assert_eq!(too_old, Some(ErrorKind::DriverTooOld));
+        assert_eq!(parse_version("6.0.2-115"), Some(MIN_SUPPORTED_ROCM));
+        assert!(unsupported_rocm("6.0.2-115").is_none());
+        assert!(unsupported_rocm("unknown").is_none());
+    }
 
-pub fn platform() -> &'static dyn Platform {
-    &AMD_PLATFORM
+    #[test]
+    fn test_bus_id() {
+        assert_eq!(bus_id(0x0300), "0000:03:00.0");
+        assert_eq!(bus_id(1 << 32 | 0xc1 << 8 | 1 << 3 | 1), "0001:c1:01.1");
+    }
 }
@@ -0,0 +1,67 @@
+//! Status of initialized platforms.
+
+use crate::platform::Detection;
+use crate::{ErrorKind, NotFoundReason};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long queries of lazy detection repeat a failed initialization before retrying it.
+pub(crate) const LAZY_INIT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Outcome of platform initialization, see [`GpuDetection::backends`](crate::GpuDetection::backends).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendInfo {
+    /// Platform name (e.g. `"cuda"`).
+    pub name: String,
+    /// Version of the management library (e.g. NVML), if known.
+    pub version: Option<String>,
+    /// Whether platform is used for detection.
+    pub status: BackendStatus,
+}
+
+/// Platform initialization status.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackendStatus {
+    /// Platform initialized and is queried during detection.
+    Ready,
+    /// Platform was skipped because its initialization failed.
+    Skipped {
+        /// Initialization error message.
+        reason: String,
+    },
+    /// Platform was left out with [`GpuDetectionBuilder::enable_only`](crate::GpuDetectionBuilder::enable_only)
+    /// or [`GpuDetectionBuilder::disable`](crate::GpuDetectionBuilder::disable) and not probed.
+    Disabled,
+}
+
+/// Backends shared by clones of `GpuDetection`, initialized on first use in lazy mode.
+#[derive(Default)]
+pub(crate) enum BackendsState {
+    /// Not initialized yet.
+    #[default]
+    Pending,
+    Ready(Arc<Backends>),
+    /// Last initialization failed at `at`.
+    Failed {
+        at: Instant,
+        kind: ErrorKind,
+        message: String,
+    },
+}
+
+/// Initialized platforms, shared by clones of `GpuDetection`.
+#[derive(Default)]
+pub(crate) struct Backends {
+    /// Platforms used for detection.
+    pub list: Vec<Backend>,
+    /// Outcome of every probed platform.
+    pub info: Vec<BackendInfo>,
+    /// Why skipped platforms failed to initialize.
+    pub skipped: Vec<NotFoundReason>,
+}
+
+pub(crate) struct Backend {
+    pub name: String,
+    pub forced: bool,
+    pub detection: Box<dyn Detection>,
+}
@@ -0,0 +1,63 @@
+//! Power source of Windows laptops, read from WMI `Win32_Battery`.
+
+use crate::model::PowerSource;
+use ::wmi::{COMLibrary, WMIConnection};
+use serde::Deserialize;
+
+/// `BatteryStatus` values of a battery plugged in: unknown (reported on AC),
+/// fully charged and charging (high, low, critical).
+const ON_AC: [u16; 6] = [2, 3, 6, 7, 8, 9];
+
+/// `BatteryStatus` values of a battery in use: discharging, low, critical
+/// and partially charged.
+const ON_BATTERY: [u16; 4] = [1, 4, 5, 11];
+
+#[derive(Deserialize)]
+#[serde(rename = "Win32_Battery", rename_all = "PascalCase")]
+struct Battery {
+    battery_status: Option<u16>,
+}
+
+/// Reads whether the computer runs on battery, `None` without a battery or when unknown.
+pub(crate) fn power_source() -> Option<PowerSource> {
+    // `COMLibrary::new` fails on threads which initialized COM single-threaded
+    // (e.g. UI threads), a new thread joins the multithreaded apartment instead.
+    std::thread::scope(|scope| scope.spawn(query_power_source).join().ok()?)
+}
+
+fn query_power_source() -> Option<PowerSource> {
+    let connection = WMIConnection::new(COMLibrary::new().ok()?).ok()?;
+    let batteries: Vec<Battery> = connection.query().ok()?;
+    from_statuses(
+        batteries
+            .iter()
+            .filter_map(|battery| battery.battery_status),
+    )
+}
+
+fn from_statuses(statuses: impl Iterator<Item = u16>) -> Option<PowerSource> {
+    let mut source = None;
+    for status in statuses {
+        if ON_AC.contains(&status) {
+            return Some(PowerSource::Ac);
+        }
+        if ON_BATTERY.contains(&status) {
+            source = Some(PowerSource::Battery);
+        }
+    }
+    source
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_statuses() {
+        assert_eq!(from_statuses([2].into_iter()), Some(PowerSource::Ac));
+        assert_eq!(from_statuses([1, 6].into_iter()), Some(PowerSource::Ac));
+        assert_eq!(from_statuses([1].into_iter()), Some(PowerSource::Battery));
+        assert_eq!(from_statuses([10].into_iter()), None);
+        assert_eq!(from_statuses([].into_iter()), None);
+    }
+}
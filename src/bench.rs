@@ -0,0 +1,69 @@
+//! Opt-in micro-benchmarks.
+//!
+//! Benchmarks run through the CUDA driver API and cuBLAS or the HIP runtime and
+//! hipBLAS of ROCm, loaded from the installed drivers. They take up to a few seconds
+//! per device and compete with running jobs, so they should run only on idle devices.
+
+use crate::model::{DeviceCompute, Gpu};
+use crate::{ErrorKind, GpuDetectionError, Result};
+use golem_gpu_bench::{BenchError, Precision};
+
+/// Measures device-to-device copy bandwidth of every device in `gpu`.
+///
+/// Result is stored in `memory.measured_bandwidth_gib`, next to the theoretical
+/// `memory.bandwidth_gib`. Aggregated devices are measured on their first card.
+/// Devices not visible to CUDA or HIP are left untouched.
+pub fn measure_memory_bandwidth(gpu: &mut Gpu) -> Result<()> {
+    for device in &mut gpu.devices {
+        let Some(bus_id) = device.pci_bus_ids.first() else {
+            continue;
+        };
+        match golem_gpu_bench::copy_bandwidth_gib(bus_id) {
+            Ok(gib) => device.memory.measured_bandwidth_gib = Some(gib),
+            Err(BenchError::DeviceNotFound(_)) => (),
+            Err(e) => return Err(bench_error("Bandwidth", e)),
+        }
+    }
+    Ok(())
+}
+
+/// Measures FP32 and FP16 matrix multiplication throughput of every device in `gpu`.
+///
+/// Result is stored in `compute`. Multiplications run in cuBLAS or hipBLAS, devices
+/// not visible to CUDA or HIP or without the BLAS library are left untouched.
+/// Aggregated devices are measured on their first card.
+pub fn measure_compute(gpu: &mut Gpu) -> Result<()> {
+    for device in &mut gpu.devices {
+        let Some(bus_id) = device.pci_bus_ids.first() else {
+            continue;
+        };
+        let fp32_tflops = match golem_gpu_bench::gemm_tflops(bus_id, Precision::Fp32) {
+            Ok(tflops) => tflops,
+            Err(BenchError::DeviceNotFound(_) | BenchError::LibraryNotFound(_)) => continue,
+            Err(e) => return Err(bench_error("FP32 GEMM", e)),
+        };
+        // older BLAS releases and cards lack half precision multiplication.
+        let fp16_tflops = golem_gpu_bench::gemm_tflops(bus_id, Precision::Fp16).ok();
+        device.compute = Some(DeviceCompute {
+            fp32_tflops,
+            fp16_tflops,
+        });
+    }
+    Ok(())
+}
+
+fn bench_error(benchmark: &str, source: BenchError) -> GpuDetectionError {
+    GpuDetectionError::Benchmark {
+        benchmark: benchmark.into(),
+        source,
+    }
+}
+
+pub(crate) fn error_kind(e: &BenchError) -> ErrorKind {
+    match e {
+        BenchError::DeviceNotFound(_) => ErrorKind::NotFound,
+        BenchError::LibraryNotFound(_) => ErrorKind::DriverNotFound,
+        BenchError::MissingSymbol(_) => ErrorKind::DriverTooOld,
+        BenchError::Call { .. } => ErrorKind::Other,
+    }
+}
@@ -0,0 +1,183 @@
+//! Command line access to GPU detection, showing exactly what the library sees.
+
+use golem_gpu_info::model::{Device, Gpu};
+use golem_gpu_info::{DetectionPolicy, GpuDetection, GpuDetectionBuilder};
+use serde::Serialize;
+use serde_json::json;
+use std::error::Error;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: golem-gpu-info <COMMAND> [OPTIONS]
+
+Commands:
+  detect                  Detect GPUs
+  find --uuid <UUID>      Find device by uuid
+  find --pci <BUS_ID>     Find device by PCI bus id
+  find --model <PATTERN>  Find devices by model glob pattern
+  watch                   Print device hot-plug and driver error events
+  bench                   Measure memory bandwidth and FP32/FP16 TFLOPS (requires `bench` feature)
+  doctor                  Show backend status and device health
+
+Options:
+  --format <json|table|csv>  Output format of `detect` and `bench` [default: json]
+  --unstable                 Include properties which are not certain
+  --dynamic                  Include volatile device state
+  --host                     Include host environment
+  --lenient                  Skip failing devices and platforms instead of failing
+";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Table,
+    Csv,
+}
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+
+    let mut builder = GpuDetectionBuilder::default();
+    let mut format = Format::Json;
+    let mut find = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("json") => Format::Json,
+                    Some("table") => Format::Table,
+                    Some("csv") => Format::Csv,
+                    other => return Err(format!("invalid format: {other:?}").into()),
+                }
+            }
+            "--unstable" => builder = builder.unstable_props(),
+            "--dynamic" => builder = builder.dynamic_props(),
+            "--host" => builder = builder.host_info(),
+            "--lenient" => builder = builder.policy(DetectionPolicy::Lenient),
+            "--uuid" | "--pci" | "--model" => {
+                let value = args.next().ok_or(format!("missing value of {arg}"))?;
+                find = Some((arg, value));
+            }
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            _ => return Err(format!("unexpected argument: {arg}\n\n{USAGE}").into()),
+        }
+    }
+
+    let detection = builder.init()?;
+    match command.as_str() {
+        "detect" => print_gpu(&detection.detect()?, format),
+        "find" => match find {
+            Some((by, value)) => find_devices(&detection, &by, &value),
+            None => Err("find requires --uuid, --pci or --model".into()),
+        },
+        "watch" => watch(&detection),
+        "bench" => bench(&detection, format),
+        "doctor" => doctor(&detection),
+        _ => Err(format!("unknown command: {command}\n\n{USAGE}").into()),
+    }
+}
+
+fn print_gpu(gpu: &Gpu, format: Format) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => print_json(&json!({ "gpu": gpu })),
+        Format::Table => {
+            print!("{}", gpu.to_table());
+            Ok(())
+        }
+        Format::Csv => {
+            print!("{}", gpu.to_csv());
+            Ok(())
+        }
+    }
+}
+
+fn print_json(value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer_pretty(&mut std::io::stdout(), value)?;
+    println!();
+    Ok(())
+}
+
+fn find_devices(detection: &GpuDetection, by: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    let devices: Vec<Device> = match by {
+        "--uuid" => vec![detection.search_by_uuid(value)?],
+        "--pci" => vec![detection.search_by_pci_bus_id(value)?],
+        _ => detection.search_by_model(value)?,
+    };
+    print_json(&devices)
+}
+
+fn watch(detection: &GpuDetection) -> Result<(), Box<dyn Error>> {
+    let watcher = detection.watch(Duration::from_secs(5))?;
+    let errors = detection.watch_errors()?;
+    loop {
+        if let Some(event) = watcher.next_timeout(Duration::from_millis(500)) {
+            println!("{event:?}");
+        }
+        while let Some(event) = errors.next_timeout(Duration::ZERO) {
+            match event {
+                Ok(event) => println!("{event:?}"),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bench")]
+fn bench(detection: &GpuDetection, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut gpu = detection.detect()?;
+    golem_gpu_info::bench::measure_memory_bandwidth(&mut gpu)?;
+    golem_gpu_info::bench::measure_compute(&mut gpu)?;
+    print_gpu(&gpu, format)
+}
+
+#[cfg(not(feature = "bench"))]
+fn bench(_detection: &GpuDetection, _format: Format) -> Result<(), Box<dyn Error>> {
+    Err("built without `bench` feature".into())
+}
+
+fn doctor(detection: &GpuDetection) -> Result<(), Box<dyn Error>> {
+    println!("Backends:");
+    for backend in detection.backends() {
+        let version = backend.version.unwrap_or_else(|| "unknown".into());
+        println!(
+            "  {} (version {version}): {:?}",
+            backend.name, backend.status
+        );
+    }
+    match detection.check_environment() {
+        Ok(()) => println!("Environment: supported"),
+        Err(e) => println!("Environment: {e}"),
+    }
+    println!("Devices:");
+    for report in detection.health_check()? {
+        let uuid = report.uuid.unwrap_or_else(|| "unknown".into());
+        println!("  #{} {uuid}: {:?}", report.index, report.status);
+    }
+    // cold NVML attach is slow and ECC memory is scrubbed on every attach.
+    let devices = detection.detect_all().unwrap_or_default();
+    for dev in devices
+        .iter()
+        .filter(|dev| dev.persistence_mode == Some(false))
+    {
+        println!(
+            "Warning: persistence mode is off on {} ({}), enable it with nvidia-persistenced or `nvidia-smi -pm 1`",
+            dev.model,
+            dev.uuids.join(", ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,537 @@
+//! Recording of backend responses and their replay, for reproducing
+//! detection problems without the user's hardware.
+//!
+//! A capture written by [`GpuDetection::record`] on the affected host is loaded with
+//! [`Capture::load`] and replayed with
+//! [`GpuDetectionBuilder::replay_capture`](crate::GpuDetectionBuilder::replay_capture),
+//! or served by single [`ReplayPlatform`]s registered with
+//! [`GpuDetectionBuilder::replay`](crate::GpuDetectionBuilder::replay).
+
+use crate::backend::BackendStatus;
+use crate::health::{HealthReport, HealthStatus};
+use crate::model::{Device, Gpu, GpuApiInfo, GpuProcess, Telemetry};
+use crate::platform::{Detection, Flags, Platform};
+use crate::{ErrorKind, GpuDetection, GpuDetectionError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+/// Recorded responses of all initialized backends.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Capture {
+    /// Version of this crate which made the recording.
+    pub crate_version: String,
+    /// Recorded backends, in probing order, including the ones which failed to initialize.
+    pub backends: Vec<BackendCapture>,
+    /// Platforms left out with `enable_only` or `disable`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
+    /// Initialization error of lazy detection, when there is no backend to record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_error: Option<RecordedError>,
+}
+
+/// Recorded responses of a single backend.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BackendCapture {
+    /// Platform name (e.g. `"cuda"`).
+    pub name: String,
+    /// Version of the management library.
+    pub version: Option<String>,
+    /// SDK & driver information.
+    pub api: Response<GpuApiInfo>,
+    /// Cards, one entry per backend native index.
+    pub devices: Response<Vec<Response<Device>>>,
+    /// Telemetry of every readable card, by uuid.
+    pub telemetry: BTreeMap<String, Response<Option<Telemetry>>>,
+    /// Processes of every readable card, by uuid.
+    pub processes: BTreeMap<String, Response<Option<Vec<GpuProcess>>>>,
+    /// Health of all cards.
+    pub health: Response<Vec<HealthReport>>,
+    /// Why the platform was skipped, replayed by its initialization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_error: Option<RecordedError>,
+}
+
+/// Recorded response, value or error.
+pub type Response<T> = Result<T, RecordedError>;
+
+/// Recorded error, replayed as [`GpuDetectionError::Replayed`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RecordedError {
+    /// Category of the error.
+    pub kind: ErrorKind,
+    /// Error message.
+    pub message: String,
+}
+
+impl From<GpuDetectionError> for RecordedError {
+    fn from(e: GpuDetectionError) -> Self {
+        RecordedError {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<RecordedError> for GpuDetectionError {
+    fn from(e: RecordedError) -> Self {
+        GpuDetectionError::Replayed {
+            kind: e.kind,
+            message: e.message,
+        }
+    }
+}
+
+impl Capture {
+    /// Reads capture written by [`GpuDetection::record`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Capture> {
+        let data = fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Writes capture as pretty printed JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Platforms serving recorded backends, see also
+    /// [`GpuDetectionBuilder::replay_capture`](crate::GpuDetectionBuilder::replay_capture).
+    pub fn into_platforms(self) -> Vec<ReplayPlatform> {
+        self.backends.into_iter().map(ReplayPlatform::new).collect()
+    }
+}
+
+impl BackendCapture {
+    // Backend answering everything with `e`.
+    fn failed(name: String, version: Option<String>, e: RecordedError) -> Self {
+        BackendCapture {
+            name,
+            version,
+            api: Err(e.clone()),
+            devices: Err(e.clone()),
+            telemetry: BTreeMap::new(),
+            processes: BTreeMap::new(),
+            health: Err(e),
+            init_error: None,
+        }
+    }
+}
+
+impl GpuDetection {
+    /// Records responses of every backend to `path`, see [`capture`](crate::capture).
+    ///
+    /// Backend errors are recorded as well, only writing the file can fail.
+    /// Devices hidden with `visible_devices` or `exclude_device` are included.
+    pub fn record(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.capture().save(path)
+    }
+
+    /// Records responses of every backend.
+    ///
+    /// Platforms which failed to initialize are recorded with their error, so is
+    /// the failed initialization of lazy detection.
+    pub fn capture(&self) -> Capture {
+        let mut capture = Capture {
+            crate_version: env!("CARGO_PKG_VERSION").into(),
+            ..Default::default()
+        };
+        let backends = match self.ready() {
+            Ok(backends) => backends,
+            Err(e) => {
+                capture.init_error = Some(e.into());
+                return capture;
+            }
+        };
+        for info in &backends.info {
+            let name = info.name.clone();
+            match &info.status {
+                BackendStatus::Ready => {
+                    let Some(idx) = backends.list.iter().position(|b| b.name == name) else {
+                        continue;
+                    };
+                    let recorded = self.call(idx, |detection| Ok(record_backend(detection)));
+                    capture.backends.push(match recorded {
+                        Ok((version, api, devices, telemetry, processes, health)) => {
+                            BackendCapture {
+                                name,
+                                version,
+                                api,
+                                devices,
+                                telemetry,
+                                processes,
+                                health,
+                                init_error: None,
+                            }
+                        }
+                        // hung backend, record timeout as the response to everything.
+                        Err(e) => BackendCapture::failed(name, None, e.into()),
+                    });
+                }
+                BackendStatus::Skipped { reason } => {
+                    let kind = (backends.skipped.iter())
+                        .find(|skipped| skipped.backend == name)
+                        .map_or(ErrorKind::Other, |skipped| skipped.code);
+                    let e = RecordedError {
+                        kind,
+                        message: reason.clone(),
+                    };
+                    capture.backends.push(BackendCapture {
+                        init_error: Some(e.clone()),
+                        ..BackendCapture::failed(name, None, e)
+                    });
+                }
+                BackendStatus::Disabled => capture.disabled.push(name),
+            }
+        }
+        capture
+    }
+}
+
+type RecordedBackend = (
+    Option<String>,
+    Response<GpuApiInfo>,
+    Response<Vec<Response<Device>>>,
+    BTreeMap<String, Response<Option<Telemetry>>>,
+    BTreeMap<String, Response<Option<Vec<GpuProcess>>>>,
+    Response<Vec<HealthReport>>,
+);
+
+fn record_backend(detection: &dyn Detection) -> RecordedBackend {
+    let mut api = GpuApiInfo::default();
+    let api = detection.detect_api(&mut api).map(|()| api);
+    let devices = detection.devices_partial().map(|devices| {
+        devices
+            .into_iter()
+            .map(|dev| dev.map_err(RecordedError::from))
+            .collect::<Vec<_>>()
+    });
+    let uuids = devices
+        .iter()
+        .flatten()
+        .flatten()
+        .flat_map(|dev| dev.uuids.clone())
+        .collect::<Vec<_>>();
+    let telemetry = uuids
+        .iter()
+        .map(|uuid| (uuid.clone(), detection.telemetry(uuid).map_err(Into::into)))
+        .collect();
+    let processes = uuids
+        .iter()
+        .map(|uuid| (uuid.clone(), detection.processes(uuid).map_err(Into::into)))
+        .collect();
+    (
+        detection.version(),
+        api.map_err(Into::into),
+        devices.map_err(Into::into),
+        telemetry,
+        processes,
+        detection.health_check().map_err(Into::into),
+    )
+}
+
+/// Platform serving a recorded backend under its original name.
+#[derive(Clone, Debug)]
+pub struct ReplayPlatform {
+    backend: BackendCapture,
+}
+
+impl ReplayPlatform {
+    /// Serves responses of `backend`.
+    pub fn new(backend: BackendCapture) -> Self {
+        ReplayPlatform { backend }
+    }
+
+    /// Serves devices from detection output (`Gpu` JSON) in `path`,
+    /// see `GOLEM_GPU_FAKE` in [`GpuDetectionBuilder::init`](crate::GpuDetectionBuilder::init).
+    pub(crate) fn fake(path: &Path) -> crate::Result<Self> {
+        let gpu: Gpu = fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice(&data).map_err(|e| e.to_string()))
+            .map_err(|message| GpuDetectionError::ConfigError {
+                path: path.to_path_buf(),
+                message,
+            })?;
+        let devices = split_cards(gpu.devices);
+        let health = devices
+            .iter()
+            .filter_map(|dev| Some((dev.uuids.first()?, *dev.indices.first()?)))
+            .map(|(uuid, index)| HealthReport {
+                uuid: Some(uuid.clone()),
+                index,
+                status: HealthStatus::Healthy,
+            })
+            .collect();
+        Ok(ReplayPlatform::new(BackendCapture {
+            name: "fake".into(),
+            version: None,
+            api: Ok(gpu.api),
+            devices: Ok(devices.into_iter().map(Ok).collect()),
+            telemetry: BTreeMap::new(),
+            processes: BTreeMap::new(),
+            health: Ok(health),
+            init_error: None,
+        }))
+    }
+
+    /// Like [`fake`](Self::fake), but reads each path only once, so that initializations
+    /// and refreshes share a single platform.
+    pub(crate) fn fake_cached(path: &Path) -> crate::Result<Arc<Self>> {
+        static FAKES: Mutex<BTreeMap<PathBuf, Arc<ReplayPlatform>>> = Mutex::new(BTreeMap::new());
+        let mut fakes = FAKES.lock().unwrap();
+        if let Some(platform) = fakes.get(path) {
+            return Ok(Arc::clone(platform));
+        }
+        let platform = Arc::new(Self::fake(path)?);
+        fakes.insert(path.to_path_buf(), Arc::clone(&platform));
+        Ok(platform)
+    }
+
+    fn readable_devices(&self) -> impl Iterator<Item = &Device> {
+        self.backend.devices.iter().flatten().flatten()
+    }
+}
+
+impl Platform for ReplayPlatform {
+    fn name(&self) -> &str {
+        &self.backend.name
+    }
+
+    fn init(&self, _flags: Flags) -> crate::Result<Box<dyn Detection>> {
+        if let Some(e) = &self.backend.init_error {
+            return Err(e.clone().into());
+        }
+        Ok(Box::new(self.clone()))
+    }
+}
+
+impl Detection for ReplayPlatform {
+    fn detect_api(&self, api: &mut GpuApiInfo) -> crate::Result<()> {
+        let recorded = self.backend.api.clone()?;
+        if recorded.cuda.is_some() {
+            api.cuda = recorded.cuda;
+        }
+        Ok(())
+    }
+
+    fn devices(&self) -> crate::Result<Vec<Device>> {
+        self.devices_partial()?.into_iter().collect()
+    }
+
+    fn devices_partial(&self) -> crate::Result<Vec<crate::Result<Device>>> {
+        Ok(self
+            .backend
+            .devices
+            .clone()?
+            .into_iter()
+            .map(|dev| dev.map_err(Into::into))
+            .collect())
+    }
+
+    fn device_by_uuid(&self, uuid: &str) -> crate::Result<Option<Device>> {
+        Ok(self
+            .readable_devices()
+            .find(|dev| dev.uuids.iter().any(|id| id == uuid))
+            .cloned())
+    }
+
+    fn device_by_pci_bus_id(&self, bus_id: &str) -> crate::Result<Option<Device>> {
+        Ok(self
+            .readable_devices()
+            .find(|dev| {
+                dev.pci_bus_ids
+                    .iter()
+                    .any(|id| crate::pci::normalize_bus_id(id) == bus_id)
+            })
+            .cloned())
+    }
+
+    fn device_by_index(&self, index: u32) -> crate::Result<Option<Device>> {
+        Ok(self
+            .readable_devices()
+            .find(|dev| dev.indices.contains(&index))
+            .cloned())
+    }
+
+    fn telemetry(&self, uuid: &str) -> crate::Result<Option<Telemetry>> {
+        match self.backend.telemetry.get(uuid) {
+            Some(recorded) => Ok(recorded.clone()?),
+            None => Ok(None),
+        }
+    }
+
+    fn processes(&self, uuid: &str) -> crate::Result<Option<Vec<GpuProcess>>> {
+        match self.backend.processes.get(uuid) {
+            Some(recorded) => Ok(recorded.clone()?),
+            None => Ok(None),
+        }
+    }
+
+    fn health_check(&self) -> crate::Result<Vec<HealthReport>> {
+        Ok(self.backend.health.clone()?)
+    }
+
+    fn watch_errors(
+        &self,
+        _on_event: &mut dyn FnMut(crate::watch::DeviceErrorEvent) -> bool,
+        _stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn version(&self) -> Option<String> {
+        self.backend.version.clone()
+    }
+}
+
+/// Splits device groups into single cards, numbering cards without index.
+fn split_cards(groups: Vec<Device>) -> Vec<Device> {
+    let mut cards = Vec::new();
+    for group in groups {
+        for i in 0..group.quantity {
+            let index = group.indices.get(i).copied().unwrap_or(cards.len() as u32);
+            cards.push(Device {
+                quantity: 1,
+                uuids: group.uuids.get(i).cloned().into_iter().collect(),
+                indices: vec![index],
+                pci_bus_ids: group.pci_bus_ids.get(i).cloned().into_iter().collect(),
+                ..group.clone()
+            });
+        }
+    }
+    cards
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockPlatform;
+    use crate::GpuDetectionBuilder;
+
+    #[test]
+    fn test_record_replay() {
+        let card = |uuid: &str, index: u32| Device {
+            model: "NVIDIA GeForce RTX 3090".into(),
+            quantity: 1,
+            uuids: vec![uuid.into()],
+            indices: vec![index],
+            pci_bus_ids: vec![format!("00000000:0{}:00.0", index + 1)],
+            ..Default::default()
+        };
+        let mock = MockPlatform::new(vec![card("GPU-a", 0), card("GPU-b", 1)])
+            .name("nvidia")
+            .device_error(1, || {
+                GpuDetectionError::GpuAccessError("GPU is lost".into())
+            });
+        let failing = MockPlatform::new(vec![])
+            .name("rocm")
+            .init_error(|| GpuDetectionError::NotSupported("ROCm".into()));
+        let detection = GpuDetectionBuilder::default()
+            .mock(mock)
+            .mock(failing)
+            .enable_only(&["nvidia", "rocm"])
+            .init()
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.json");
+        detection.record(&path).unwrap();
+        let capture = Capture::load(&path).unwrap();
+        assert_eq!(capture, detection.capture());
+
+        let replayed = GpuDetectionBuilder::default()
+            .replay_capture(capture)
+            .force("nvidia")
+            .init()
+            .unwrap();
+        assert_eq!(replayed.backends(), detection.backends());
+        let report = replayed.detect_report();
+        assert_eq!(report.gpu, detection.detect_report().gpu);
+        assert_eq!(report.warnings, detection.detect_report().warnings);
+        assert_eq!(
+            report.warnings[0].message,
+            "Failed to access GPU error: GPU is lost"
+        );
+        assert!(replayed.search_by_pci_bus_id("0000:01:00.0").is_ok());
+        assert!(replayed.telemetry("GPU-a").is_ok());
+        assert!(matches!(
+            replayed.detect(),
+            Err(GpuDetectionError::Replayed {
+                kind: ErrorKind::Other,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_capture_init_error() {
+        let detection = GpuDetectionBuilder::default()
+            .mock(MockPlatform::new(vec![]).name("nvidia"))
+            .enable_only(&["nvidia"])
+            .force("missing")
+            .lazy()
+            .init()
+            .unwrap();
+        let capture = detection.capture();
+        assert!(capture.backends.is_empty());
+        assert!(capture.init_error.is_some());
+
+        let replayed = GpuDetectionBuilder::default()
+            .replay_capture(capture)
+            .lazy()
+            .init()
+            .unwrap();
+        assert_eq!(
+            replayed.detect().unwrap_err().to_string(),
+            detection.detect().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_fake() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("devices.json");
+        let devices = serde_json::json!({
+            "cuda": { "version": "12.4" },
+            "d0": {
+                "model": "NVIDIA GeForce RTX 4090",
+                "cuda": null,
+                "clock": { "graphics.mhz": 2235, "memory.mhz": 10501, "sm.mhz": 2235 },
+                "memory": { "total.gib": 23.99 },
+                "quantity": 2,
+                "uuids": ["GPU-a", "GPU-b"]
+            }
+        });
+        fs::write(&path, devices.to_string()).unwrap();
+        let platform = ReplayPlatform::fake(&path).unwrap();
+        let cached = ReplayPlatform::fake_cached(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(Arc::ptr_eq(
+            &cached,
+            &ReplayPlatform::fake_cached(&path).unwrap()
+        ));
+
+        let detection = GpuDetectionBuilder::default()
+            .replay(platform)
+            .enable_only(&["fake"])
+            .init()
+            .unwrap();
+        let gpu = detection.detect().unwrap();
+        assert_eq!(gpu.api.cuda.unwrap().version, "12.4");
+        assert_eq!(gpu.devices.len(), 1);
+        assert_eq!(gpu.devices[0].quantity, 2);
+        assert_eq!(gpu.devices[0].indices, vec![0, 1]);
+        assert_eq!(
+            detection.search_by_index("fake", 1).unwrap().uuids,
+            ["GPU-b"]
+        );
+        assert!(ReplayPlatform::fake(&path).is_err());
+    }
+}
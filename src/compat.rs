@@ -0,0 +1,150 @@
+//! CUDA toolkit compatibility checks.
+
+use crate::model::{parse_version, ComputeCapability, Device, DriverVersion, Gpu};
+
+/// Minimal driver versions (Linux, Windows) required by CUDA toolkit releases,
+/// from the CUDA toolkit release notes. Newest first.
+const MIN_DRIVERS: &[((u32, u32), DriverVersion, DriverVersion)] = &[
+    ((12, 6), linux(560, 28, 3), windows(560, 76)),
+    ((12, 5), linux(555, 42, 2), windows(555, 85)),
+    ((12, 4), linux(550, 54, 14), windows(551, 61)),
+    ((12, 3), linux(545, 23, 6), windows(545, 84)),
+    ((12, 2), linux(535, 54, 3), windows(536, 25)),
+    ((12, 1), linux(530, 30, 2), windows(531, 14)),
+    ((12, 0), linux(525, 60, 13), windows(527, 41)),
+    ((11, 8), linux(520, 61, 5), windows(520, 6)),
+    ((11, 7), linux(515, 43, 4), windows(516, 1)),
+    ((11, 6), linux(510, 39, 1), windows(511, 23)),
+    ((11, 5), linux(495, 29, 5), windows(496, 4)),
+    ((11, 4), linux(470, 42, 1), windows(471, 11)),
+    ((11, 3), linux(465, 19, 1), windows(465, 89)),
+    ((11, 2), linux(460, 27, 3), windows(460, 82)),
+    ((11, 1), linux(455, 23, 0), windows(456, 38)),
+    ((11, 0), linux(450, 36, 6), windows(451, 22)),
+];
+
+/// Oldest compute capability supported by CUDA toolkit major versions.
+const MIN_CAPS: &[(u32, ComputeCapability)] = &[
+    (12, ComputeCapability::new(5, 0)),
+    (11, ComputeCapability::new(3, 5)),
+];
+
+const fn linux(major: u32, minor: u32, patch: u32) -> DriverVersion {
+    DriverVersion {
+        major,
+        minor,
+        patch: Some(patch),
+    }
+}
+
+const fn windows(major: u32, minor: u32) -> DriverVersion {
+    DriverVersion::new(major, minor)
+}
+
+impl Gpu {
+    /// Checks if applications built with CUDA toolkit `version` (e.g. `"12.1"`)
+    /// can run on this host.
+    ///
+    /// Requires the installed driver to support that version and at least one
+    /// device architecture not dropped by the toolkit.
+    pub fn supports_cuda(&self, version: &str) -> bool {
+        let Some(required) = parse_version(version) else {
+            return false;
+        };
+        let Some(cuda) = &self.api.cuda else {
+            return false;
+        };
+        let driver_supported = match parse_version(&cuda.version) {
+            Some(available) => available >= required,
+            None => min_driver(required)
+                .zip(cuda.driver_version)
+                .is_some_and(|(min, installed)| installed >= min),
+        };
+        driver_supported
+            && self
+                .devices
+                .iter()
+                .any(|dev| architecture_supported(dev, required))
+    }
+}
+
+impl Device {
+    /// Oldest driver able to run applications built with CUDA toolkit `version`
+    /// (e.g. `"12.1"`) on this device, for the current operating system.
+    ///
+    /// Returns `None` for unknown versions and when the toolkit no longer supports
+    /// the device architecture.
+    pub fn min_driver_for_cuda(&self, version: &str) -> Option<DriverVersion> {
+        let required = parse_version(version)?;
+        architecture_supported(self, required)
+            .then(|| min_driver(required))
+            .flatten()
+    }
+}
+
+fn min_driver(version: (u32, u32)) -> Option<DriverVersion> {
+    MIN_DRIVERS
+        .iter()
+        .find(|(cuda, _, _)| *cuda == version)
+        .map(|&(_, linux, windows)| if cfg!(windows) { windows } else { linux })
+}
+
+fn architecture_supported(dev: &Device, (major, _): (u32, u32)) -> bool {
+    let Some(cuda) = &dev.cuda else {
+        return false;
+    };
+    MIN_CAPS
+        .iter()
+        .find(|(cuda_major, _)| *cuda_major == major)
+        .is_none_or(|(_, min_caps)| cuda.caps >= *min_caps)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{Cuda, DeviceCuda, GpuApiInfo};
+
+    fn gen_device(caps: ComputeCapability) -> Device {
+        Device {
+            model: "NVIDIA GeForce GTX 780".into(),
+            cuda: Some(DeviceCuda {
+                enabled: true,
+                cores: 2304,
+                caps,
+            }),
+            quantity: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_supports_cuda() {
+        let gpu = Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.2".into(),
+                    driver_version: Some("535.146.02".parse().unwrap()),
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![gen_device(ComputeCapability::new(8, 6))],
+            ..Default::default()
+        };
+        assert!(gpu.supports_cuda("12.1"));
+        assert!(gpu.supports_cuda("11.8"));
+        assert!(!gpu.supports_cuda("12.4"));
+        assert!(!gpu.supports_cuda("latest"));
+    }
+
+    #[test]
+    fn test_min_driver_for_cuda() {
+        let kepler = gen_device(ComputeCapability::new(3, 5));
+        assert_eq!(kepler.min_driver_for_cuda("12.0"), None);
+        let expected = if cfg!(windows) { "520.06" } else { "520.61.05" };
+        assert_eq!(
+            kepler.min_driver_for_cuda("11.8").map(|v| v.to_string()),
+            Some(expected.into())
+        );
+        assert_eq!(kepler.min_driver_for_cuda("9.2"), None);
+    }
+}
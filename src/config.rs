@@ -0,0 +1,162 @@
+//! Detection settings read from a TOML file, see [`GpuDetectionBuilder::from_config`].
+
+use crate::aggregation::{AggregationKey, AggregationPolicy};
+use crate::{GpuDetectionBuilder, GpuDetectionError, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    force: Vec<String>,
+    enable_only: Option<Vec<String>>,
+    disable: Vec<String>,
+    nvml_lib_path: Option<PathBuf>,
+    rocm_lib_path: Option<PathBuf>,
+    sysfs_root: Option<PathBuf>,
+    visible_devices: Option<Vec<String>>,
+    exclude_devices: Vec<String>,
+    aggregation: Option<AggregationConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct AggregationConfig {
+    keys: Option<Vec<AggregationKey>>,
+    clock_tolerance_mhz: u32,
+    memory_tolerance_gib: f32,
+}
+
+impl GpuDetectionBuilder {
+    /// Builder configured by a TOML file, so deployments can tune detection without
+    /// recompiling the embedding program.
+    ///
+    /// All settings are optional, omitted ones keep their defaults:
+    ///
+    /// ```toml
+    /// force = ["cuda"]
+    /// # probes only listed platforms, applied before `disable`.
+    /// enable-only = ["cuda", "amd"]
+    /// disable = ["amd"]
+    /// nvml-lib-path = "/usr/lib/x86_64-linux-gnu/libnvidia-ml.so.1"
+    /// rocm-lib-path = "/opt/rocm/lib/librocm_smi64.so"
+    /// sysfs-root = "/host/sys"
+    /// visible-devices = ["GPU-8f6d1f7c-..."]
+    /// exclude-devices = ["GPU-0d3e5b2a-..."]
+    ///
+    /// [aggregation]
+    /// # compared properties, see `AggregationKey` (e.g. "persistence-mode").
+    /// keys = ["clocks", "memory"]
+    /// clock-tolerance-mhz = 15
+    /// memory-tolerance-gib = 0.5
+    /// ```
+    ///
+    /// Unknown settings are rejected, further builder calls override the file.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<GpuDetectionBuilder> {
+        let path = path.as_ref();
+        let config_error = |message: String| GpuDetectionError::ConfigError {
+            path: path.to_path_buf(),
+            message,
+        };
+        let text = fs::read_to_string(path).map_err(|e| config_error(e.to_string()))?;
+        let config: Config = toml::from_str(&text).map_err(|e| config_error(e.to_string()))?;
+        Ok(config.apply(GpuDetectionBuilder::default()))
+    }
+}
+
+impl Config {
+    fn apply(self, mut builder: GpuDetectionBuilder) -> GpuDetectionBuilder {
+        for name in &self.force {
+            builder = builder.force(name);
+        }
+        if let Some(names) = &self.enable_only {
+            builder = builder.enable_only(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        for name in &self.disable {
+            builder = builder.disable(name);
+        }
+        if let Some(path) = self.nvml_lib_path {
+            builder = builder.nvml_lib_path(path);
+        }
+        if let Some(path) = self.rocm_lib_path {
+            builder = builder.rocm_lib_path(path);
+        }
+        if let Some(path) = self.sysfs_root {
+            builder = builder.sysfs_root(path);
+        }
+        if let Some(uuids) = &self.visible_devices {
+            builder =
+                builder.visible_devices(&uuids.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        for uuid in &self.exclude_devices {
+            builder = builder.exclude_device(uuid);
+        }
+        if let Some(aggregation) = self.aggregation {
+            let mut policy = AggregationPolicy::default()
+                .clock_tolerance_mhz(aggregation.clock_tolerance_mhz)
+                .memory_tolerance_gib(aggregation.memory_tolerance_gib);
+            if let Some(keys) = aggregation.keys {
+                policy = policy.keys(keys);
+            }
+            builder = builder.aggregation(policy);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu.toml");
+        fs::write(
+            &path,
+            r#"
+            disable = ["cuda"]
+            nvml-lib-path = "/opt/nvidia/libnvidia-ml.so.1"
+            rocm-lib-path = "/opt/rocm/lib/librocm_smi64.so"
+            visible-devices = ["GPU-1", "GPU-2"]
+            exclude-devices = ["GPU-2"]
+
+            [aggregation]
+            keys = ["clocks", "persistence-mode"]
+            "#,
+        )
+        .unwrap();
+        let builder = GpuDetectionBuilder::from_config(&path).unwrap();
+        assert!(builder.platforms.iter().all(|p| p.name() != "cuda"));
+        assert_eq!(
+            builder.nvml_lib_path,
+            Some("/opt/nvidia/libnvidia-ml.so.1".into())
+        );
+        assert_eq!(
+            builder.rocm_lib_path,
+            Some("/opt/rocm/lib/librocm_smi64.so".into())
+        );
+        assert_eq!(
+            builder.visible,
+            Some(["GPU-1", "GPU-2"].map(String::from).into())
+        );
+        assert_eq!(builder.excluded, ["GPU-2"].map(String::from).into());
+        assert_eq!(
+            format!("{:?}", builder.aggregation),
+            format!(
+                "{:?}",
+                AggregationPolicy::default()
+                    .keys([AggregationKey::Clocks, AggregationKey::PersistenceMode])
+            )
+        );
+
+        fs::write(&path, "force = [\"cuda\"]\nunknown = 1\n").unwrap();
+        let Err(e) = GpuDetectionBuilder::from_config(&path) else {
+            panic!("unknown setting accepted");
+        };
+        assert_eq!(e.kind(), ErrorKind::Other);
+        assert!(e.to_string().contains("unknown"), "{e}");
+    }
+}
@@ -0,0 +1,396 @@
+//! Evaluation of Golem market demand constraints against detected GPUs.
+//!
+//! Supports the LDAP-like subset used for hardware requirements:
+//! `&`, `|` and `!` operators, `=`, `<`, `<=`, `>`, `>=` comparisons,
+//! presence checks (`(prop=*)`) and `*` wildcards in string equality.
+//!
+//! ```
+//! use golem_gpu_info::constraints::Constraint;
+//!
+//! let constraint: Constraint = "(&(golem.inf.gpu.memory.total.gib>=16)(golem.inf.gpu.cuda.version>=12.1))"
+//!     .parse()
+//!     .unwrap();
+//! ```
+
+use crate::model::{parse_version, Device, Gpu};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Deepest accepted nesting of filters, demands come from the market and a deeper one
+/// would overflow the stack of the recursive parser.
+const MAX_DEPTH: usize = 64;
+
+/// Constraint expression could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("invalid constraint at {position}: {message}")]
+pub struct ConstraintError {
+    /// Byte offset of the problem.
+    pub position: usize,
+    /// What was expected.
+    pub message: String,
+}
+
+/// Parsed demand constraint.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// All inner constraints hold.
+    And(Vec<Constraint>),
+    /// Any inner constraint holds.
+    Or(Vec<Constraint>),
+    /// Inner constraint does not hold.
+    Not(Box<Constraint>),
+    /// Property comparison.
+    Compare {
+        /// Property name.
+        property: String,
+        /// Comparison operator.
+        op: Operator,
+        /// Literal value, `*` for presence check.
+        value: String,
+    },
+}
+
+/// Comparison operator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// `=`
+    Equal,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl FromStr for Constraint {
+    type Err = ConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            pos: 0,
+            depth: 0,
+        };
+        let constraint = parser.filter()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("end of constraint"));
+        }
+        Ok(constraint)
+    }
+}
+
+impl Constraint {
+    /// Evaluates against flat offer properties.
+    ///
+    /// Comparisons with missing properties do not hold.
+    pub fn matches(&self, properties: &BTreeMap<String, Value>) -> bool {
+        match self {
+            Constraint::And(inner) => inner.iter().all(|c| c.matches(properties)),
+            Constraint::Or(inner) => inner.iter().any(|c| c.matches(properties)),
+            Constraint::Not(inner) => !inner.matches(properties),
+            Constraint::Compare {
+                property,
+                op,
+                value,
+            } => properties
+                .get(property)
+                .is_some_and(|actual| compare(actual, *op, value)),
+        }
+    }
+
+    /// Evaluates against offer properties of `gpu`, see [`Gpu::to_offer_properties`].
+    ///
+    /// Device properties are addressed by group, e.g. `golem.inf.gpu.d0.model`.
+    pub fn matches_gpu(&self, gpu: &Gpu, prefix: &str) -> bool {
+        self.matches(&gpu.to_offer_properties(prefix))
+    }
+
+    /// Device groups of `gpu` satisfying the constraint on their own.
+    ///
+    /// Device properties are addressed without group, e.g. `golem.inf.gpu.model`,
+    /// driver and host properties are shared by all devices.
+    pub fn matching_devices<'a>(&self, gpu: &'a Gpu, prefix: &str) -> Vec<&'a Device> {
+        let prefix = prefix.trim_end_matches('.');
+        let group_prefix = format!("{prefix}.d0.");
+        gpu.devices
+            .iter()
+            .filter(|dev| {
+                let single = Gpu {
+                    devices: vec![(*dev).clone()],
+                    ..gpu.clone()
+                };
+                let properties = single
+                    .to_offer_properties(prefix)
+                    .into_iter()
+                    .map(|(key, value)| match key.strip_prefix(&group_prefix) {
+                        Some(name) => (format!("{prefix}.{name}"), value),
+                        None => (key, value),
+                    })
+                    .collect();
+                self.matches(&properties)
+            })
+            .collect()
+    }
+}
+
+fn compare(actual: &Value, op: Operator, literal: &str) -> bool {
+    if op == Operator::Equal && literal == "*" {
+        return true;
+    }
+    let ordering = match actual {
+        Value::Array(items) => {
+            return op == Operator::Equal && items.iter().any(|item| compare(item, op, literal))
+        }
+        Value::Number(number) => literal
+            .parse::<f64>()
+            .ok()
+            .zip(number.as_f64())
+            .and_then(|(literal, number)| number.partial_cmp(&literal)),
+        Value::Bool(flag) => literal
+            .parse::<bool>()
+            .ok()
+            .map(|literal| flag.cmp(&literal)),
+        Value::String(text) if op == Operator::Equal && literal.contains('*') => {
+            return crate::glob::matches(literal, text)
+        }
+        Value::String(text) => match (parse_version(text), parse_version(literal)) {
+            (Some(version), Some(literal)) if op != Operator::Equal => Some(version.cmp(&literal)),
+            _ => Some(text.as_str().cmp(literal)),
+        },
+        _ => None,
+    };
+    ordering.is_some_and(|ordering| match op {
+        Operator::Equal => ordering == Ordering::Equal,
+        Operator::Less => ordering == Ordering::Less,
+        Operator::LessOrEqual => ordering != Ordering::Greater,
+        Operator::Greater => ordering == Ordering::Greater,
+        Operator::GreaterOrEqual => ordering != Ordering::Less,
+    })
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn filter(&mut self) -> Result<Constraint, ConstraintError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ConstraintError {
+                position: self.pos,
+                message: format!("nesting deeper than {MAX_DEPTH} filters"),
+            });
+        }
+        self.depth += 1;
+        let constraint = self.nested();
+        self.depth -= 1;
+        constraint
+    }
+
+    fn nested(&mut self) -> Result<Constraint, ConstraintError> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        let constraint = match self.peek() {
+            Some('&') => {
+                self.pos += 1;
+                Constraint::And(self.filters()?)
+            }
+            Some('|') => {
+                self.pos += 1;
+                Constraint::Or(self.filters()?)
+            }
+            Some('!') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Constraint::Not(Box::new(self.filter()?))
+            }
+            _ => self.comparison()?,
+        };
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(constraint)
+    }
+
+    fn filters(&mut self) -> Result<Vec<Constraint>, ConstraintError> {
+        let mut filters = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('(') {
+                return Ok(filters);
+            }
+            filters.push(self.filter()?);
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Constraint, ConstraintError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !matches!(c, '=' | '<' | '>' | '(' | ')'))
+        {
+            self.pos += self.peek().map_or(1, char::len_utf8);
+        }
+        let property = self.input[start..self.pos].trim().to_string();
+        if property.is_empty() {
+            return Err(self.error("property name"));
+        }
+        let op = match self.peek() {
+            Some('=') => Operator::Equal,
+            Some('<') if self.input[self.pos..].starts_with("<=") => Operator::LessOrEqual,
+            Some('<') => Operator::Less,
+            Some('>') if self.input[self.pos..].starts_with(">=") => Operator::GreaterOrEqual,
+            Some('>') => Operator::Greater,
+            _ => return Err(self.error("comparison operator")),
+        };
+        self.pos += match op {
+            Operator::LessOrEqual | Operator::GreaterOrEqual => 2,
+            _ => 1,
+        };
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                ')' => break,
+                '\\' => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("escaped character"))?;
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+            self.pos += self.peek().map_or(1, char::len_utf8);
+        }
+        Ok(Constraint::Compare {
+            property,
+            op,
+            value: value.trim().to_string(),
+        })
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ConstraintError> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("`{expected}`")));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += self.peek().map_or(1, char::len_utf8);
+        }
+    }
+
+    fn error(&self, expected: &str) -> ConstraintError {
+        ConstraintError {
+            position: self.pos,
+            message: format!("expected {expected}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{Cuda, DeviceMemory, GpuApiInfo};
+
+    fn gen_gpu() -> Gpu {
+        let device = |model: &str, total_gib| Device {
+            model: model.into(),
+            memory: DeviceMemory {
+                total_gib,
+                ..Default::default()
+            },
+            quantity: 1,
+            uuids: vec![format!("GPU-{total_gib}")],
+            ..Default::default()
+        };
+        Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.10".into(),
+                    driver_version: None,
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![
+                device("NVIDIA GeForce RTX 3090", 24.0),
+                device("NVIDIA GeForce RTX 3060", 12.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "( & (a>=16) (!(b=x\\)y)) )".parse(),
+            Ok(Constraint::And(vec![
+                Constraint::Compare {
+                    property: "a".into(),
+                    op: Operator::GreaterOrEqual,
+                    value: "16".into(),
+                },
+                Constraint::Not(Box::new(Constraint::Compare {
+                    property: "b".into(),
+                    op: Operator::Equal,
+                    value: "x)y".into(),
+                })),
+            ]))
+        );
+        assert!("(a>=16".parse::<Constraint>().is_err());
+        assert!("(>=16)".parse::<Constraint>().is_err());
+        assert!("(a>=16))".parse::<Constraint>().is_err());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let nested = |depth| format!("{}(a=1){}", "(!".repeat(depth), ")".repeat(depth));
+        assert!(nested(MAX_DEPTH - 1).parse::<Constraint>().is_ok());
+        let e = nested(200_000).parse::<Constraint>().unwrap_err();
+        assert_eq!(e.position, 2 * MAX_DEPTH);
+        assert!(e.message.contains("nesting"), "{e}");
+    }
+
+    #[test]
+    fn test_matching_devices() {
+        let gpu = gen_gpu();
+        let constraint: Constraint =
+            "(&(golem.inf.gpu.memory.total.gib>=16)(golem.inf.gpu.cuda.version>=12.9))"
+                .parse()
+                .unwrap();
+        let devices = constraint.matching_devices(&gpu, "golem.inf.gpu");
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].model, "NVIDIA GeForce RTX 3090");
+
+        let constraint: Constraint = "(|(golem.inf.gpu.model=*3060)(golem.inf.gpu.dynamic=*))"
+            .parse()
+            .unwrap();
+        let devices = constraint.matching_devices(&gpu, "golem.inf.gpu.");
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].model, "NVIDIA GeForce RTX 3060");
+    }
+
+    #[test]
+    fn test_matches_gpu() {
+        let gpu = gen_gpu();
+        let constraint: Constraint =
+            "(&(golem.inf.gpu.d1.memory.total.gib<16)(golem.inf.gpu.d0.uuids=GPU-24))"
+                .parse()
+                .unwrap();
+        assert!(constraint.matches_gpu(&gpu, "golem.inf.gpu"));
+        let constraint: Constraint = "(!(golem.inf.gpu.d2.model=*))".parse().unwrap();
+        assert!(constraint.matches_gpu(&gpu, "golem.inf.gpu"));
+    }
+}
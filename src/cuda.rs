@@ -1,8 +1,52 @@
-use crate::model::{Cuda, Device as GpuDevice, DeviceClocks, DeviceCuda, DeviceMemory, GpuApiInfo};
+//! NVIDIA cards through NVIDIA Management Library (NVML).
+
+use crate::health::{HealthReport, HealthStatus, SHUTDOWN_MARGIN_C};
+use crate::model::{
+    ComputeCapability, ComputeMode, Cuda, Device as GpuDevice, DeviceClocks, DeviceCuda,
+    DeviceDriverModel, DeviceDynamic, DeviceEncoder, DeviceFans, DeviceHealth, DeviceMemory,
+    DeviceMig, DevicePci, DevicePower, DeviceStatus, DeviceUtilization, DriverVersion, GpuApiInfo,
+    GpuProcess, KernelDriver, LaptopGraphics, PowerSource, Telemetry, ThrottleReason,
+};
+#[cfg(any(windows, test))]
+use crate::model::{DriverModel, DriverModelRecommendation};
 use crate::platform::{Detection, Flags, Platform};
-use crate::{bytes_to_gib, GpuDetectionError};
-use nvml_wrapper::error::NvmlError;
-use nvml_wrapper::{enum_wrappers::device::Clock, Device, Nvml};
+use crate::watch::DeviceErrorEvent;
+#[cfg(target_os = "linux")]
+use crate::watch::DeviceErrorKind;
+use crate::{bytes_to_gib, pci, specs, ErrorKind, GpuDetectionError, NotFoundReason};
+use nvml_wrapper::bitmasks::device::ThrottleReasons;
+#[cfg(target_os = "linux")]
+use nvml_wrapper::bitmasks::event::EventTypes;
+use nvml_wrapper::enum_wrappers::device::{
+    Brand, Clock, EccCounter, EncoderType, MemoryError, RetirementCause, TemperatureSensor,
+    TemperatureThreshold,
+};
+use nvml_wrapper::enums::device::UsedGpuMemory;
+#[cfg(target_os = "linux")]
+use nvml_wrapper::enums::event::XidError;
+pub use nvml_wrapper::error::NvmlError;
+use nvml_wrapper::{Device, Nvml};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Oldest driver providing every NVML function used by detection.
+///
+/// Process listing needs `nvmlDeviceGetComputeRunningProcesses_v3`, added in the R510
+/// branch, older drivers (e.g. 472 on Windows) fail with missing function errors.
+#[cfg(windows)]
+pub const MIN_SUPPORTED_DRIVER: DriverVersion = DriverVersion::new(511, 23);
+
+/// Oldest driver providing every NVML function used by detection.
+///
+/// Process listing needs `nvmlDeviceGetComputeRunningProcesses_v3`, added in the R510
+/// branch, older drivers fail with missing function errors.
+#[cfg(not(windows))]
+pub const MIN_SUPPORTED_DRIVER: DriverVersion = DriverVersion {
+    major: 510,
+    minor: 39,
+    patch: Some(1),
+};
 
 pub(crate) struct CudaDetection {
     flags: Flags,
@@ -11,43 +55,179 @@ pub(crate) struct CudaDetection {
 
 impl Detection for CudaDetection {
     fn detect_api(&self, api: &mut GpuApiInfo) -> crate::Result<()> {
-        let version = self
-            .cuda_version()
-            .map_err(|e| GpuDetectionError::GpuInfoAccessError(e.to_string()))?;
-        let driver_version = self.nvml.sys_driver_version().ok();
+        let version = self.cuda_version()?;
+        let installed = self.nvml.sys_driver_version().ok();
+        let driver_version = installed.as_ref().and_then(|version| version.parse().ok());
+        // kept as reported, e.g. for vGPU drivers with suffixed versions.
+        let driver_version_raw = installed.filter(|_| driver_version.is_none());
         api.cuda = Some(Cuda {
             version,
             driver_version,
+            driver_version_raw,
         });
         Ok(())
     }
 
     fn devices(&self) -> Result<Vec<GpuDevice>, GpuDetectionError> {
-        let gpu_count = self.nvml.device_count().map_err(|err| {
-            GpuDetectionError::Unknown(format!("Failed to get device count. Err {}", err))
-        })?;
-
-        (0..gpu_count)
-            .map(|index| {
-                self.nvml
-                    .device_by_index(index)
-                    .and_then(|device| device_info(device, &self.flags))
-            })
-            .collect::<Result<_, NvmlError>>()
-            .map_err(|e| GpuDetectionError::GpuAccessError(e.to_string()))
+        self.devices_partial()?.into_iter().collect()
+    }
+
+    fn devices_partial(&self) -> crate::Result<Vec<crate::Result<GpuDevice>>> {
+        // dormant card of a hybrid laptop reports zeros, it is kept awake until listed.
+        #[cfg(target_os = "linux")]
+        let _resume = (self.flags.runtime_resume)
+            .then(|| crate::sysfs::RuntimeResume::new(&self.flags.sysfs_root, NVIDIA_VENDOR_ID));
+        let gpu_count = self.nvml.device_count()?;
+        // shared by all cards, on Windows every read is a WMI query.
+        let power_source = power_source(&self.flags.sysfs_root);
+
+        Ok(parallel_map(gpu_count, self.flags.threads, |index| {
+            match self
+                .nvml
+                .device_by_index(index)
+                .and_then(|device| device_info(device, &self.flags, power_source))
+            {
+                // a card which fell off the bus must not hide the others.
+                Err(NvmlError::GpuLost) => Ok(lost_device(&self.nvml, index)),
+                result => result.map_err(GpuDetectionError::from),
+            }
+        }))
     }
 
     fn device_by_uuid(&self, uuid: &str) -> super::Result<Option<GpuDevice>> {
         let device = match self.nvml.device_by_uuid(uuid) {
             Ok(device) => device,
             Err(NvmlError::NotFound) => return Ok(None),
-            Err(e) => return Err(GpuDetectionError::GpuAccessError(e.to_string())),
+            Err(e) => return Err(e.into()),
         };
 
-        let dev_info = device_info(device, &self.flags)
-            .map_err(|e| GpuDetectionError::GpuInfoAccessError(e.to_string()))?;
+        let power_source = power_source(&self.flags.sysfs_root);
+        let dev_info = device_info(device, &self.flags, power_source)?;
         Ok(Some(dev_info))
     }
+
+    fn device_by_pci_bus_id(&self, bus_id: &str) -> super::Result<Option<GpuDevice>> {
+        let device = match self.nvml.device_by_pci_bus_id(bus_id) {
+            Ok(device) => device,
+            Err(NvmlError::NotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let power_source = power_source(&self.flags.sysfs_root);
+        let dev_info = device_info(device, &self.flags, power_source)?;
+        Ok(Some(dev_info))
+    }
+
+    fn device_by_index(&self, index: u32) -> super::Result<Option<GpuDevice>> {
+        let device = match self.nvml.device_by_index(index) {
+            Ok(device) => device,
+            Err(NvmlError::InvalidArg) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let power_source = power_source(&self.flags.sysfs_root);
+        let dev_info = device_info(device, &self.flags, power_source)?;
+        Ok(Some(dev_info))
+    }
+
+    fn telemetry(&self, uuid: &str) -> crate::Result<Option<Telemetry>> {
+        let device = match self.nvml.device_by_uuid(uuid) {
+            Ok(device) => device,
+            Err(NvmlError::NotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let telemetry = telemetry(&device)?;
+        Ok(Some(telemetry))
+    }
+
+    fn processes(&self, uuid: &str) -> crate::Result<Option<Vec<GpuProcess>>> {
+        let device = match self.nvml.device_by_uuid(uuid) {
+            Ok(device) => device,
+            Err(NvmlError::NotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let processes = processes(&device)?;
+        Ok(Some(processes))
+    }
+
+    fn health_check(&self) -> crate::Result<Vec<HealthReport>> {
+        let gpu_count = self.nvml.device_count()?;
+        Ok((0..gpu_count)
+            .map(|index| device_health(&self.nvml, index))
+            .collect())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn watch_errors(
+        &self,
+        on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        let mut set = self.nvml.create_event_set()?;
+        for index in 0..self.nvml.device_count()? {
+            let device = self.nvml.device_by_index(index)?;
+            let supported = optional(device.supported_event_types())?;
+            let events = supported.unwrap_or_else(EventTypes::empty) & WATCHED_EVENTS;
+            if events.is_empty() {
+                continue;
+            }
+            set = device.register_events(events, set).map_err(|e| e.error)?;
+        }
+
+        while !stop.load(Ordering::Relaxed) {
+            let data = match set.wait(EVENT_WAIT_MS) {
+                Ok(data) => data,
+                Err(NvmlError::Timeout) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let uuid = data.device.uuid().ok();
+            for kind in error_kinds(data.event_type, data.event_data) {
+                let event = DeviceErrorEvent {
+                    uuid: uuid.clone(),
+                    kind,
+                };
+                if !on_event(event) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn watch_errors(
+        &self,
+        _on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        _stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        // NVML events are supported only on Linux.
+        Ok(())
+    }
+
+    fn version(&self) -> Option<String> {
+        self.nvml.sys_nvml_version().ok()
+    }
+
+    fn check_environment(&self) -> crate::Result<()> {
+        let installed = self.nvml.sys_driver_version()?;
+        match unsupported_driver(&installed) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Error for a driver `installed` older than [`MIN_SUPPORTED_DRIVER`], unparsable
+/// versions are let through.
+fn unsupported_driver(installed: &str) -> Option<GpuDetectionError> {
+    let version: DriverVersion = installed.parse().ok()?;
+    (version < MIN_SUPPORTED_DRIVER).then(|| GpuDetectionError::UnsupportedDriver {
+        component: "NVIDIA driver".into(),
+        installed: installed.to_string(),
+        required: MIN_SUPPORTED_DRIVER.to_string(),
+    })
 }
 
 impl CudaDetection {
@@ -59,50 +239,238 @@ impl CudaDetection {
     }
 }
 
-fn device_info(dev: Device, flags: &Flags) -> Result<GpuDevice, NvmlError> {
+/// Maps indices `0..count` with `f` on up to `threads` threads, keeping their order.
+fn parallel_map<T, F>(count: u32, threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(u32) -> T + Sync,
+{
+    let threads = threads.min(count as usize);
+    if threads <= 1 {
+        return (0..count).map(f).collect();
+    }
+    let next = AtomicU32::new(0);
+    let mut results: Vec<(u32, T)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            return done;
+                        }
+                        done.push((index, f(index)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// `power_source` of the host is reported in clocks of laptop cards.
+fn device_info(
+    dev: Device,
+    flags: &Flags,
+    power_source: Option<PowerSource>,
+) -> Result<GpuDevice, NvmlError> {
+    let mut enriched = Vec::new();
+    let mut unsupported = Vec::new();
+    let mut estimated = Vec::new();
     let model = dev.name()?;
-    let cuda = Some(cuda(&dev, flags)?);
-    let clocks = clocks(&dev)?;
-    let memory = memory(&dev, flags)?;
-    Ok(GpuDevice {
+    let pci = pci(&dev)?;
+    let cuda = cuda(&dev, &model, pci.as_ref(), &mut enriched, &mut unsupported);
+    let cuda = flags.tolerate(cuda, "cuda", &mut unsupported)?.flatten();
+    let clocks = clocks(&dev, &mut unsupported);
+    let mut clocks = (flags.tolerate(clocks, "clock", &mut unsupported)?).unwrap_or_default();
+    let memory = memory(&dev, flags, &mut estimated, &mut unsupported);
+    let memory = (flags.tolerate(memory, "memory", &mut unsupported)?).unwrap_or_default();
+    let power = flags
+        .tolerate(power(&dev), "power", &mut unsupported)?
+        .flatten();
+    let encoder = flags
+        .tolerate(encoder(&dev), "encoder", &mut unsupported)?
+        .flatten();
+    let persistence_mode = persistence_mode(&dev);
+    let persistence_mode = flags
+        .tolerate(persistence_mode, "persistence-mode", &mut unsupported)?
+        .flatten();
+    let driver_model = driver_model(&dev);
+    let driver_model = flags
+        .tolerate(driver_model, "driver-model", &mut unsupported)?
+        .flatten();
+    let bus_id = pci::normalize_bus_id(&dev.pci_info()?.bus_id);
+    let kernel_driver = kernel_driver(&flags.sysfs_root, &bus_id);
+    let external = external(&flags.sysfs_root, &bus_id);
+    let laptop = laptop(&flags.sysfs_root, &bus_id);
+    // max clocks of laptop GPUs are lower on battery, on Windows only a battery tells a laptop.
+    if laptop.is_some() || cfg!(windows) {
+        clocks.power_source = power_source;
+    }
+    let mig = flags
+        .tolerate(mig(&dev), "mig", &mut unsupported)?
+        .flatten();
+    let uuids = vec![dev.uuid()?];
+    let indices = vec![dev.index()?];
+    let pci_bus_ids = vec![bus_id];
+    let dynamic = if flags.dynamic {
+        flags.tolerate(dynamic(&dev), "dynamic", &mut unsupported)?
+    } else {
+        None
+    };
+    let mut device = GpuDevice {
         model,
+        model_normalized: None,
         cuda,
         clocks,
         memory,
+        power,
+        encoder,
+        persistence_mode,
+        pci,
+        kernel_driver,
+        external,
+        laptop,
+        driver_model,
+        mig,
+        status: DeviceStatus::Available,
         quantity: 1,
-    })
+        uuids,
+        indices,
+        pci_bus_ids,
+        dynamic,
+        compute: None,
+        enriched,
+        estimated,
+        unsupported,
+    };
+    flags.apply(&mut device);
+    Ok(device)
 }
 
-fn cuda(dev: &Device, _flags: &Flags) -> Result<DeviceCuda, NvmlError> {
+/// Lists card which fell off the bus with identifiers the driver still remembers.
+fn lost_device(nvml: &Nvml, index: u32) -> GpuDevice {
+    let dev = nvml.device_by_index(index).ok();
+    let model = dev.as_ref().and_then(|dev| dev.name().ok());
+    let uuid = dev.as_ref().and_then(|dev| dev.uuid().ok());
+    let bus_id = dev
+        .as_ref()
+        .and_then(|dev| dev.pci_info().ok())
+        .map(|info| pci::normalize_bus_id(&info.bus_id));
+    GpuDevice {
+        model: model.unwrap_or_else(|| "NVIDIA GPU".into()),
+        status: DeviceStatus::Lost,
+        quantity: 1,
+        uuids: uuid.into_iter().collect(),
+        indices: vec![index],
+        pci_bus_ids: bus_id.into_iter().collect(),
+        ..Default::default()
+    }
+}
+
+// Properties older drivers and vGPUs do not report are looked up in the spec table.
+fn cuda(
+    dev: &Device,
+    model: &str,
+    pci: Option<&DevicePci>,
+    enriched: &mut Vec<String>,
+    unsupported: &mut Vec<String>,
+) -> Result<Option<DeviceCuda>, NvmlError> {
     let enabled = true;
-    let cores = dev.num_cores()?;
-    let caps = compute_capability(dev)?;
-    Ok(DeviceCuda {
+    // core count query was added in 520 drivers and is not supported on Kepler and Maxwell.
+    let cores = or_spec(
+        optional(dev.num_cores())?,
+        specs::CORES_KEY,
+        || specs::cores(model, pci),
+        enriched,
+        unsupported,
+    );
+    let caps = or_spec(
+        optional(compute_capability(dev))?,
+        specs::CAPS_KEY,
+        || specs::compute_capability(model, pci),
+        enriched,
+        unsupported,
+    );
+    let (Some(cores), Some(caps)) = (cores, caps) else {
+        return Ok(None);
+    };
+    Ok(Some(DeviceCuda {
         enabled,
         cores,
         caps,
-    })
+    }))
 }
 
-fn compute_capability(dev: &Device) -> Result<String, NvmlError> {
+fn compute_capability(dev: &Device) -> Result<ComputeCapability, NvmlError> {
     let capability = dev.cuda_compute_capability()?;
-    Ok(format!("{}.{}", capability.major, capability.minor))
+    Ok(ComputeCapability::new(
+        capability.major as u32,
+        capability.minor as u32,
+    ))
 }
 
-fn clocks(dev: &Device) -> Result<DeviceClocks, NvmlError> {
-    let graphics_mhz = dev.max_clock_info(Clock::Graphics)?;
-    let memory_mhz = dev.max_clock_info(Clock::Memory)?;
-    let sm_mhz = dev.max_clock_info(Clock::SM)?;
-    let video_mhz = Some(dev.max_clock_info(Clock::Video)?);
+/// Falls back to the spec table for property `key` the driver did not report,
+/// recording where the value came from.
+fn or_spec<T>(
+    value: Option<T>,
+    key: &str,
+    spec: impl FnOnce() -> Option<T>,
+    enriched: &mut Vec<String>,
+    unsupported: &mut Vec<String>,
+) -> Option<T> {
+    if value.is_some() {
+        return value;
+    }
+    let value = spec();
+    match value {
+        Some(_) => enriched.push(key.into()),
+        None => unsupported.push(key.into()),
+    }
+    value
+}
+
+fn clocks(dev: &Device, unsupported: &mut Vec<String>) -> Result<DeviceClocks, NvmlError> {
+    let mut max_clock = |clock: Clock, key: &str| -> Result<Option<u32>, NvmlError> {
+        // older cards without boost report only application clocks.
+        let mhz = match optional(dev.max_clock_info(clock.clone()))? {
+            Some(mhz) => Some(mhz),
+            None => optional(dev.default_applications_clock(clock))?,
+        };
+        if mhz.is_none() {
+            unsupported.push(key.into());
+        }
+        Ok(mhz)
+    };
+    let graphics_mhz = max_clock(Clock::Graphics, "clock.graphics.mhz")?.unwrap_or_default();
+    let memory_mhz = max_clock(Clock::Memory, "clock.memory.mhz")?.unwrap_or_default();
+    let sm_mhz = max_clock(Clock::SM, "clock.sm.mhz")?.unwrap_or_default();
+    let video_mhz = max_clock(Clock::Video, "clock.video.mhz")?;
     Ok(DeviceClocks {
         graphics_mhz,
         memory_mhz,
         sm_mhz,
         video_mhz,
+        power_source: None,
     })
 }
 
-fn memory(dev: &Device, flags: &Flags) -> Result<DeviceMemory, NvmlError> {
+fn memory(
+    dev: &Device,
+    flags: &Flags,
+    estimated: &mut Vec<String>,
+    unsupported: &mut Vec<String>,
+) -> Result<DeviceMemory, NvmlError> {
     let total_bytes = dev.memory_info()?.total;
     let total_gib = bytes_to_gib(total_bytes);
     let bandwidth_gib = if flags.unstable {
@@ -110,24 +478,535 @@ fn memory(dev: &Device, flags: &Flags) -> Result<DeviceMemory, NvmlError> {
     } else {
         None
     };
+    if flags.unstable {
+        match bandwidth_gib {
+            Some(_) => estimated.push("memory.bandwidth.gib".into()),
+            None => unsupported.push("memory.bandwidth.gib".into()),
+        }
+    }
 
     Ok(DeviceMemory {
         bandwidth_gib,
+        measured_bandwidth_gib: None,
         total_gib,
     })
 }
 
 fn bandwidth_gib(dev: &Device) -> Result<Option<u32>, NvmlError> {
-    let memory_bus_width = dev.memory_bus_width()?;
-    let max_memory_clock = dev.max_clock_info(Clock::Memory)?;
+    let Some(memory_bus_width) = optional(dev.memory_bus_width())? else {
+        return Ok(None);
+    };
+    let Some(max_memory_clock) = optional(dev.max_clock_info(Clock::Memory))? else {
+        return Ok(None);
+    };
 
-    // `nvml` does not provide `memTransferRatemax` like `nvidia-settings` tool does.
-    // Transfer rate is a result of memory clock, bus width,
-    // and memory specific multiplier (for DDR it is 2)
-    let data_rate = 2; // value for DDR
-    let bandwidth_gib = max_memory_clock * memory_bus_width * data_rate / (1000 * 8);
+    Ok(Some(estimate_bandwidth_gib(
+        max_memory_clock,
+        memory_bus_width,
+    )))
+}
+
+/// Peak bandwidth of memory with given max clock and bus width.
+///
+/// `nvml` does not provide `memTransferRatemax` like `nvidia-settings` tool does.
+/// The memory clock it reports is half of the per-pin data rate for every memory
+/// technology (GDDR5, GDDR5X, GDDR6, GDDR6X and HBM2/2e/3 alike, e.g. 9751 MHz for
+/// 19.5 Gbps GDDR6X), technology specific multipliers (x4 write clock of GDDR6,
+/// PAM4 of GDDR6X) are already applied, so the transfer rate is always twice the clock.
+fn estimate_bandwidth_gib(max_memory_clock_mhz: u32, memory_bus_width: u32) -> u32 {
+    let data_rate = 2;
+    let bytes_per_sec =
+        max_memory_clock_mhz as u64 * 1_000_000 * data_rate * memory_bus_width as u64 / 8;
+    (bytes_per_sec / (1 << 30)) as u32
+}
+
+fn power(dev: &Device) -> Result<Option<DevicePower>, NvmlError> {
+    let Some(constraints) = optional(dev.power_management_limit_constraints())? else {
+        return Ok(None);
+    };
+    let limit_w = optional(dev.enforced_power_limit())?.map(mw_to_w);
+    let default_limit_w = optional(dev.power_management_limit_default())?.map(mw_to_w);
+    Ok(Some(DevicePower {
+        min_limit_w: mw_to_w(constraints.min_limit),
+        max_limit_w: mw_to_w(constraints.max_limit),
+        limit_w,
+        default_limit_w,
+    }))
+}
+
+fn mw_to_w(milliwatts: u32) -> u32 {
+    milliwatts / 1000
+}
 
-    Ok(Some(bandwidth_gib))
+fn encoder(dev: &Device) -> Result<Option<DeviceEncoder>, NvmlError> {
+    if optional(dev.encoder_capacity(EncoderType::H264))?.is_none() {
+        return Ok(None);
+    }
+    let sessions_max = match dev.brand()? {
+        Brand::GeForce | Brand::GeForceRTX | Brand::Titan | Brand::TitanRTX => {
+            let driver_version = dev.nvml().sys_driver_version()?;
+            Some(geforce_encoder_sessions_max(&driver_version))
+        }
+        _ => None,
+    };
+    Ok(Some(DeviceEncoder {
+        enabled: true,
+        sessions_max,
+    }))
+}
+
+// NVML does not report the session limit. For consumer cards (newer drivers report RTX
+// ones as separate brands) it is enforced by the driver and was raised from 3 to 5 in 530
+// and to 8 in 551 drivers.
+fn geforce_encoder_sessions_max(driver_version: &str) -> u32 {
+    match driver_version.parse::<DriverVersion>() {
+        Ok(version) if version >= DriverVersion::new(551, 0) => 8,
+        Ok(version) if version >= DriverVersion::new(530, 0) => 5,
+        _ => 3,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn persistence_mode(dev: &Device) -> Result<Option<bool>, NvmlError> {
+    optional(dev.is_in_persistent_mode())
+}
+
+// persistence mode is a Linux only feature.
+#[cfg(not(target_os = "linux"))]
+fn persistence_mode(_dev: &Device) -> Result<Option<bool>, NvmlError> {
+    Ok(None)
+}
+
+#[cfg(windows)]
+fn driver_model(dev: &Device) -> Result<Option<DeviceDriverModel>, NvmlError> {
+    use nvml_wrapper::enum_wrappers::device::DriverModel as NvmlDriverModel;
+
+    let Some(state) = optional(dev.driver_model())? else {
+        return Ok(None);
+    };
+    let convert = |model| match model {
+        NvmlDriverModel::WDDM => DriverModel::Wddm,
+        NvmlDriverModel::WDM => DriverModel::Tcc,
+    };
+    let current = convert(state.current);
+    let pending = Some(convert(state.pending)).filter(|pending| *pending != current);
+    Ok(Some(DeviceDriverModel {
+        current,
+        pending,
+        recommendation: driver_model_recommendation(dev.brand()?, pending.unwrap_or(current)),
+    }))
+}
+
+// driver models are a Windows only feature.
+#[cfg(not(windows))]
+fn driver_model(_dev: &Device) -> Result<Option<DeviceDriverModel>, NvmlError> {
+    Ok(None)
+}
+
+/// Suggests TCC for datacenter cards, consumer cards can not leave WDDM.
+///
+/// Workstation cards often drive a display, which TCC does not allow, so they
+/// are left as they are. A pending switch counts as done.
+#[cfg(any(windows, test))]
+fn driver_model_recommendation(
+    brand: Brand,
+    model: DriverModel,
+) -> Option<DriverModelRecommendation> {
+    match (brand, model) {
+        (Brand::Tesla | Brand::Nvidia, DriverModel::Wddm) => {
+            Some(DriverModelRecommendation::SwitchToTcc)
+        }
+        (Brand::GeForce | Brand::GeForceRTX, _) => Some(DriverModelRecommendation::TccUnsupported),
+        _ => None,
+    }
+}
+
+fn pci(dev: &Device) -> Result<Option<DevicePci>, NvmlError> {
+    let info = dev.pci_info()?;
+    // sub system id is not reported by some older drivers.
+    let Some(sub_system_id) = info.pci_sub_system_id else {
+        return Ok(None);
+    };
+    let subsystem_vendor_id = sub_system_id as u16;
+    Ok(Some(DevicePci {
+        vendor_id: info.pci_device_id as u16,
+        device_id: (info.pci_device_id >> 16) as u16,
+        subsystem_vendor_id,
+        subsystem_device_id: (sub_system_id >> 16) as u16,
+        partner: pci::vendor_name(subsystem_vendor_id).map(Into::into),
+    }))
+}
+
+#[cfg(target_os = "linux")]
+fn kernel_driver(sysfs_root: &Path, bus_id: &str) -> Option<KernelDriver> {
+    crate::sysfs::kernel_driver(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_driver(_sysfs_root: &Path, _bus_id: &str) -> Option<KernelDriver> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn external(sysfs_root: &Path, bus_id: &str) -> Option<bool> {
+    crate::sysfs::is_external(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn external(_sysfs_root: &Path, _bus_id: &str) -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn laptop(sysfs_root: &Path, bus_id: &str) -> Option<LaptopGraphics> {
+    crate::sysfs::laptop_graphics(sysfs_root, bus_id)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn laptop(_sysfs_root: &Path, _bus_id: &str) -> Option<LaptopGraphics> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn power_source(sysfs_root: &Path) -> Option<PowerSource> {
+    crate::sysfs::power_source(sysfs_root)
+}
+
+#[cfg(all(windows, feature = "wmi"))]
+fn power_source(_sysfs_root: &Path) -> Option<PowerSource> {
+    crate::battery::power_source()
+}
+
+#[cfg(not(any(target_os = "linux", all(windows, feature = "wmi"))))]
+fn power_source(_sysfs_root: &Path) -> Option<PowerSource> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+const NVIDIA_VENDOR_ID: u16 = 0x10de;
+
+/// Present while the NVIDIA kernel module is loaded.
+#[cfg(target_os = "linux")]
+const NVIDIA_VERSION: &str = "/proc/driver/nvidia/version";
+
+/// NVIDIA driver capability files, listing created MIG instances.
+#[cfg(target_os = "linux")]
+const NVIDIA_CAPABILITIES: &str = "/proc/driver/nvidia/capabilities";
+
+// `nvml-wrapper` does not expose MIG queries, instances are read from the files used
+// by the driver to grant access to them.
+#[cfg(target_os = "linux")]
+fn mig(dev: &Device) -> Result<Option<DeviceMig>, NvmlError> {
+    let Some(minor) = optional(dev.minor_number())? else {
+        return Ok(None);
+    };
+    Ok(mig_instances(Path::new(NVIDIA_CAPABILITIES), minor))
+}
+
+// MIG is a Linux only feature.
+#[cfg(not(target_os = "linux"))]
+fn mig(_dev: &Device) -> Result<Option<DeviceMig>, NvmlError> {
+    Ok(None)
+}
+
+/// Counts `gpu<minor>/mig/gi<N>` GPU instances and their `ci<N>` compute instances.
+#[cfg(target_os = "linux")]
+fn mig_instances(root: &Path, minor: u32) -> Option<DeviceMig> {
+    let instances = |dir: &Path, prefix: &str| -> Vec<std::path::PathBuf> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix(prefix))
+                    .is_some_and(|id| id.parse::<u32>().is_ok())
+            })
+            .collect()
+    };
+    let gpu_instances = instances(&root.join(format!("gpu{minor}/mig")), "gi");
+    if gpu_instances.is_empty() {
+        return None;
+    }
+    let compute_instances = gpu_instances
+        .iter()
+        .map(|gi| instances(gi, "ci").len())
+        .sum::<usize>();
+    Some(DeviceMig {
+        gpu_instances: gpu_instances.len() as u32,
+        compute_instances: compute_instances as u32,
+    })
+}
+
+fn utilization(dev: &Device) -> Result<Option<DeviceUtilization>, NvmlError> {
+    Ok(
+        optional(dev.utilization_rates())?.map(|utilization| DeviceUtilization {
+            gpu_pct: utilization.gpu,
+            memory_pct: utilization.memory,
+        }),
+    )
+}
+
+fn telemetry(dev: &Device) -> Result<Telemetry, NvmlError> {
+    let utilization = utilization(dev)?;
+    let memory_used_gib = Some(bytes_to_gib(dev.memory_info()?.used));
+    let temperature_c = optional(dev.temperature(TemperatureSensor::Gpu))?;
+    let power_draw_w = optional(dev.power_usage())?.map(mw_to_w);
+    let clocks = Some(DeviceClocks {
+        graphics_mhz: dev.clock_info(Clock::Graphics)?,
+        memory_mhz: dev.clock_info(Clock::Memory)?,
+        sm_mhz: dev.clock_info(Clock::SM)?,
+        video_mhz: optional(dev.clock_info(Clock::Video))?,
+        power_source: None,
+    });
+    let compute_mode = optional(dev.compute_mode())?.map(compute_mode);
+    Ok(Telemetry {
+        utilization,
+        memory_used_gib,
+        temperature_c,
+        power_draw_w,
+        clocks,
+        compute_mode,
+    })
+}
+
+fn compute_mode(mode: nvml_wrapper::enum_wrappers::device::ComputeMode) -> ComputeMode {
+    use nvml_wrapper::enum_wrappers::device::ComputeMode as NvmlComputeMode;
+    match mode {
+        NvmlComputeMode::Default => ComputeMode::Default,
+        // exclusive thread mode was removed from drivers, it allowed a single process too.
+        NvmlComputeMode::ExclusiveThread | NvmlComputeMode::ExclusiveProcess => {
+            ComputeMode::ExclusiveProcess
+        }
+        NvmlComputeMode::Prohibited => ComputeMode::Prohibited,
+    }
+}
+
+/// Longest process name returned by NVML.
+const PROCESS_NAME_MAX: usize = 64;
+
+fn processes(dev: &Device) -> Result<Vec<GpuProcess>, NvmlError> {
+    let mut infos = dev.running_compute_processes()?;
+    infos.extend(optional(dev.running_graphics_processes())?.unwrap_or_default());
+
+    let mut processes: Vec<GpuProcess> = Vec::with_capacity(infos.len());
+    for info in infos {
+        // process using both compute and graphics is listed twice.
+        if processes.iter().any(|process| process.pid == info.pid) {
+            continue;
+        }
+        let used_memory_gib = match info.used_gpu_memory {
+            UsedGpuMemory::Used(bytes) => Some(bytes_to_gib(bytes)),
+            UsedGpuMemory::Unavailable => None,
+        };
+        processes.push(GpuProcess {
+            pid: info.pid,
+            name: dev.nvml().sys_process_name(info.pid, PROCESS_NAME_MAX).ok(),
+            used_memory_gib,
+        });
+    }
+    Ok(processes)
+}
+
+fn dynamic(dev: &Device) -> Result<DeviceDynamic, NvmlError> {
+    let fans = fans(dev)?;
+    let health = Some(health(dev)?);
+    let throttle_reasons = optional(dev.current_throttle_reasons())?.map(throttle_reasons);
+    let utilization = utilization(dev)?;
+    Ok(DeviceDynamic {
+        fans,
+        health,
+        throttle_reasons,
+        utilization,
+    })
+}
+
+const THROTTLE_REASONS: [(ThrottleReasons, ThrottleReason); 9] = [
+    (ThrottleReasons::GPU_IDLE, ThrottleReason::GpuIdle),
+    (
+        ThrottleReasons::APPLICATIONS_CLOCKS_SETTING,
+        ThrottleReason::ApplicationsClocksSetting,
+    ),
+    (ThrottleReasons::SW_POWER_CAP, ThrottleReason::SwPowerCap),
+    (ThrottleReasons::HW_SLOWDOWN, ThrottleReason::HwSlowdown),
+    (ThrottleReasons::SYNC_BOOST, ThrottleReason::SyncBoost),
+    (
+        ThrottleReasons::SW_THERMAL_SLOWDOWN,
+        ThrottleReason::SwThermalSlowdown,
+    ),
+    (
+        ThrottleReasons::HW_THERMAL_SLOWDOWN,
+        ThrottleReason::HwThermalSlowdown,
+    ),
+    (
+        ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN,
+        ThrottleReason::HwPowerBrakeSlowdown,
+    ),
+    (
+        ThrottleReasons::DISPLAY_CLOCK_SETTING,
+        ThrottleReason::DisplayClockSetting,
+    ),
+];
+
+fn throttle_reasons(reasons: ThrottleReasons) -> Vec<ThrottleReason> {
+    THROTTLE_REASONS
+        .iter()
+        .filter(|(flag, _)| reasons.contains(*flag))
+        .map(|(_, reason)| *reason)
+        .collect()
+}
+
+fn fans(dev: &Device) -> Result<Option<DeviceFans>, NvmlError> {
+    // passively cooled cards have no fans to report.
+    let Some(count) = optional(dev.num_fans())? else {
+        return Ok(None);
+    };
+    let speeds_pct = fan_speeds(count, |fan_idx| dev.fan_speed(fan_idx))?;
+    Ok(Some(DeviceFans { count, speeds_pct }))
+}
+
+// some boards report fans without speed readings, those are left out.
+fn fan_speeds(
+    count: u32,
+    fan_speed: impl Fn(u32) -> Result<u32, NvmlError>,
+) -> Result<Vec<u32>, NvmlError> {
+    let mut speeds_pct = Vec::new();
+    for fan_idx in 0..count {
+        speeds_pct.extend(optional(fan_speed(fan_idx))?);
+    }
+    Ok(speeds_pct)
+}
+
+fn health(dev: &Device) -> Result<DeviceHealth, NvmlError> {
+    let ecc_corrected =
+        optional(dev.total_ecc_errors(MemoryError::Corrected, EccCounter::Aggregate))?;
+    let ecc_uncorrected =
+        optional(dev.total_ecc_errors(MemoryError::Uncorrected, EccCounter::Aggregate))?;
+    // Ampere and newer remap rows instead of retiring pages, NVML reports those as not supported.
+    let retired_pages = match (
+        optional(dev.retired_pages(RetirementCause::MultipleSingleBitEccErrors))?,
+        optional(dev.retired_pages(RetirementCause::DoubleBitEccError))?,
+    ) {
+        (Some(single_bit), Some(double_bit)) => Some((single_bit.len() + double_bit.len()) as u32),
+        _ => None,
+    };
+    let retired_pages_pending = optional(dev.are_pages_pending_retired())?;
+    Ok(DeviceHealth {
+        ecc_corrected,
+        ecc_uncorrected,
+        retired_pages,
+        retired_pages_pending,
+    })
+}
+
+#[cfg(target_os = "linux")]
+const WATCHED_EVENTS: EventTypes = EventTypes::CRITICAL_XID_ERROR
+    .union(EventTypes::SINGLE_BIT_ECC_ERROR)
+    .union(EventTypes::DOUBLE_BIT_ECC_ERROR)
+    .union(EventTypes::CLOCK_CHANGE);
+
+/// How often `stop` flag is checked while waiting for events.
+#[cfg(target_os = "linux")]
+const EVENT_WAIT_MS: u32 = 500;
+
+#[cfg(target_os = "linux")]
+fn error_kinds(events: EventTypes, xid: Option<XidError>) -> Vec<DeviceErrorKind> {
+    let mut kinds = Vec::new();
+    if events.contains(EventTypes::CRITICAL_XID_ERROR) {
+        kinds.push(DeviceErrorKind::Xid(match xid {
+            Some(XidError::Value(code)) => Some(code),
+            Some(XidError::Unknown) | None => None,
+        }));
+    }
+    if events.contains(EventTypes::SINGLE_BIT_ECC_ERROR) {
+        kinds.push(DeviceErrorKind::SingleBitEcc);
+    }
+    if events.contains(EventTypes::DOUBLE_BIT_ECC_ERROR) {
+        kinds.push(DeviceErrorKind::DoubleBitEcc);
+    }
+    if events.contains(EventTypes::CLOCK_CHANGE) {
+        kinds.push(DeviceErrorKind::ClockChange);
+    }
+    kinds
+}
+
+fn device_health(nvml: &Nvml, index: u32) -> HealthReport {
+    let device = nvml.device_by_index(index);
+    let uuid = device.as_ref().ok().and_then(|dev| dev.uuid().ok());
+    let status = match device.and_then(|dev| health_status(&dev)) {
+        Ok(status) => status,
+        Err(NvmlError::GpuLost) => HealthStatus::Lost,
+        Err(e) => HealthStatus::Degraded {
+            reasons: vec![e.to_string()],
+        },
+    };
+    HealthReport {
+        uuid,
+        index,
+        status,
+    }
+}
+
+fn health_status(dev: &Device) -> Result<HealthStatus, NvmlError> {
+    let mut reasons = Vec::new();
+    // volatile counter, so that errors before last reboot do not mark device forever.
+    let ecc_uncorrected =
+        optional(dev.total_ecc_errors(MemoryError::Uncorrected, EccCounter::Volatile))?;
+    if let Some(errors @ 1..) = ecc_uncorrected {
+        reasons.push(format!("{errors} uncorrected ECC errors since reboot"));
+    }
+    if optional(dev.are_pages_pending_retired())? == Some(true) {
+        reasons.push("memory pages pending retirement".to_string());
+    }
+    let temperature = dev.temperature(TemperatureSensor::Gpu)?;
+    if let Some(shutdown) = optional(dev.temperature_threshold(TemperatureThreshold::Shutdown))? {
+        if temperature + SHUTDOWN_MARGIN_C >= shutdown {
+            reasons.push(format!(
+                "temperature {temperature}C close to shutdown threshold {shutdown}C"
+            ));
+        }
+    }
+    Ok(HealthStatus::from_reasons(reasons))
+}
+
+// Maps `NotSupported` to `None`, for properties not available on every device.
+//
+// Functions missing from drivers older than the NVML headers (common with old Windows
+// drivers) are treated the same, so such drivers get a reduced detection instead of none.
+// `nvml-wrapper` resolves every symbol at init and keeps the lookup result, so the call
+// itself is the probe.
+fn optional<T>(result: Result<T, NvmlError>) -> Result<Option<T>, NvmlError> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(NvmlError::NotSupported) => Ok(None),
+        Err(e) if missing_symbol(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn missing_symbol(e: &NvmlError) -> bool {
+    matches!(
+        e,
+        NvmlError::FunctionNotFound | NvmlError::FailedToLoadSymbol(_)
+    )
+}
+
+pub(crate) fn error_kind(e: &NvmlError) -> ErrorKind {
+    match e {
+        NvmlError::LibloadingError(_) | NvmlError::LibraryNotFound | NvmlError::DriverNotLoaded => {
+            ErrorKind::DriverNotFound
+        }
+        e if missing_symbol(e) => ErrorKind::DriverTooOld,
+        NvmlError::NoPermission | NvmlError::OperatingSystem => ErrorKind::PermissionDenied,
+        NvmlError::NotSupported => ErrorKind::NotSupported,
+        NvmlError::LibRmVersionMismatch => ErrorKind::DriverVersionMismatch,
+        NvmlError::GpuLost | NvmlError::ResetRequired => ErrorKind::DeviceLost,
+        NvmlError::NotFound => ErrorKind::NotFound,
+        NvmlError::Timeout => ErrorKind::Timeout,
+        _ => ErrorKind::Other,
+    }
 }
 
 struct CudaPlatform;
@@ -138,21 +1017,75 @@ impl Platform for CudaPlatform {
     }
 
     fn init(&self, flags: Flags) -> crate::Result<Box<dyn Detection>> {
-        let nvml = match nvml_init() {
+        check_kernel_module(&flags.sysfs_root, flags.force)?;
+        let nvml = match &flags.nvml_lib_path {
+            Some(lib_path) => Nvml::builder().lib_path(lib_path.as_os_str()).init(),
+            None => nvml_init(),
+        };
+        let nvml = match nvml {
             Ok(nvlm) => nvlm,
             Err(NvmlError::LibloadingError(e)) => {
+                let e = GpuDetectionError::LibloadingError(e);
                 return if flags.force {
-                    Err(GpuDetectionError::LibloadingError(e))
+                    Err(e)
                 } else {
-                    Err(GpuDetectionError::NotFound)
-                }
+                    Err(GpuDetectionError::NotFound {
+                        reasons: NotFoundReason::from_error("cuda", &e),
+                    })
+                };
+            }
+            Err(NvmlError::LibRmVersionMismatch) => {
+                return Err(version_mismatch(&flags.sysfs_root, None)
+                    .unwrap_or_else(|| NvmlError::LibRmVersionMismatch.into()))
             }
-            Err(e) => return Err(GpuDetectionError::Unknown(e.to_string())),
+            Err(e) => return Err(e.into()),
         };
+        // after an upgrade without reboot the old module may still accept the new library,
+        // but fail later with confusing errors.
+        if let Ok(library) = nvml.sys_driver_version() {
+            if let Some(e) = version_mismatch(&flags.sysfs_root, Some(library)) {
+                return Err(e);
+            }
+        }
         Ok(Box::new(CudaDetection { nvml, flags }))
     }
 }
 
+/// Fails before loading NVML when the `nvidia` kernel module is not loaded,
+/// NVML would report a missing library or an unloaded driver instead.
+#[cfg(target_os = "linux")]
+fn check_kernel_module(sysfs_root: &Path, force: bool) -> crate::Result<()> {
+    // WSL exposes the Windows driver through `/dev/dxg`, without a kernel module.
+    if Path::new(NVIDIA_VERSION).exists() || Path::new("/dev/dxg").exists() {
+        return Ok(());
+    }
+    match crate::sysfs::missing_module(sysfs_root, "nvidia", NVIDIA_VENDOR_ID, force) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_kernel_module(_sysfs_root: &Path, _force: bool) -> crate::Result<()> {
+    Ok(())
+}
+
+/// Compares driver `library` version with the loaded kernel module.
+#[cfg(target_os = "linux")]
+fn version_mismatch(sysfs_root: &Path, library: Option<String>) -> Option<GpuDetectionError> {
+    let kernel = crate::sysfs::module_version(sysfs_root, "nvidia")?;
+    if library.as_ref() == Some(&kernel) {
+        return None;
+    }
+    Some(GpuDetectionError::DriverVersionMismatch { kernel, library })
+}
+
+// the driver library is installed together with the kernel driver on Windows.
+#[cfg(not(target_os = "linux"))]
+fn version_mismatch(_sysfs_root: &Path, _library: Option<String>) -> Option<GpuDetectionError> {
+    None
+}
+
 // On systems without a full development environment there may not
 // be `libnvidia-ml.so`. Because there is a convention to name `lib<name>.so.<version>` files
 // as runtime lib.
@@ -172,8 +1105,130 @@ fn nvml_init() -> std::result::Result<Nvml, NvmlError> {
     Nvml::init()
 }
 
-static CUDA_PLATFORM: CudaPlatform = CudaPlatform;
+pub(crate) fn platform() -> Arc<dyn crate::platform::Platform> {
+    Arc::new(CudaPlatform)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mig_instances() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for dir in [
+            "gpu0/mig/gi1/ci0",
+            "gpu0/mig/gi2/ci0",
+            "gpu0/mig/gi2/ci1",
+            "gpu1/mig",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("gpu0/mig/config"), "").unwrap();
+
+        let mig = mig_instances(root, 0);
+        let disabled = mig_instances(root, 1);
+        assert_eq!(
+            mig,
+            Some(DeviceMig {
+                gpu_instances: 2,
+                compute_instances: 3,
+            })
+        );
+        assert_eq!(disabled, None);
+    }
+
+    #[test]
+    fn test_fan_speeds() {
+        let speeds = fan_speeds(3, |fan_idx| match fan_idx {
+            1 => Err(NvmlError::NotSupported),
+            fan_idx => Ok(40 + fan_idx),
+        });
+        assert_eq!(speeds.unwrap(), vec![40, 42]);
+
+        let lost = fan_speeds(2, |_| Err(NvmlError::GpuLost));
+        assert!(matches!(lost, Err(NvmlError::GpuLost)));
+    }
+
+    #[test]
+    fn test_estimate_bandwidth_gib() {
+        // Tesla T4, GDDR6 320 GB/s.
+        assert_eq!(estimate_bandwidth_gib(5001, 256), 298);
+        // GeForce RTX 3090, GDDR6X 936 GB/s.
+        assert_eq!(estimate_bandwidth_gib(9751, 384), 871);
+        // A100-PCIE-40GB, HBM2e 1555 GB/s.
+        assert_eq!(estimate_bandwidth_gib(1215, 5120), 1448);
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let squares = |threads| parallel_map(10, threads, |index| index * index);
+        let expected: Vec<u32> = (0..10).map(|index| index * index).collect();
+        assert_eq!(squares(1), expected);
+        assert_eq!(squares(4), expected);
+        assert_eq!(squares(16), expected);
+        assert!(parallel_map(0, 4, |index| index).is_empty());
+    }
+
+    #[test]
+    fn test_driver_model_recommendation() {
+        assert_eq!(
+            driver_model_recommendation(Brand::Tesla, DriverModel::Wddm),
+            Some(DriverModelRecommendation::SwitchToTcc)
+        );
+        assert_eq!(
+            driver_model_recommendation(Brand::Nvidia, DriverModel::Tcc),
+            None
+        );
+        assert_eq!(
+            driver_model_recommendation(Brand::GeForceRTX, DriverModel::Wddm),
+            Some(DriverModelRecommendation::TccUnsupported)
+        );
+        assert_eq!(
+            driver_model_recommendation(Brand::QuadroRTX, DriverModel::Wddm),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unsupported_driver() {
+        assert_eq!(
+            unsupported_driver("470.239.06").unwrap().to_string(),
+            "NVIDIA driver 470.239.06 is older than the oldest supported 510.39.01, update it"
+        );
+        assert!(unsupported_driver("510.39.01").is_none());
+        assert!(unsupported_driver("550.54.14").is_none());
+        assert!(unsupported_driver("unknown").is_none());
+    }
+
+    #[test]
+    fn test_min_supported_driver() {
+        // last Windows driver without `nvmlDeviceGetComputeRunningProcesses_v3`.
+        let too_old = unsupported_driver("472.12").map(|e| e.kind());
+        assert_eq!(too_old, Some(ErrorKind::DriverTooOld));
+        assert!(unsupported_driver(&MIN_SUPPORTED_DRIVER.to_string()).is_none());
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::create_dir_all(root.join("module/nvidia")).unwrap();
+        std::fs::write(root.join("module/nvidia/version"), "550.54.14\n").unwrap();
 
-pub fn platform() -> &'static dyn crate::platform::Platform {
-    &CUDA_PLATFORM
+        let matching = version_mismatch(root, Some("550.54.14".into()));
+        let upgraded = version_mismatch(root, Some("550.90.07".into()));
+        let refused = version_mismatch(root, None);
+        assert!(matching.is_none());
+        assert_eq!(
+            upgraded.unwrap().to_string(),
+            "NVIDIA kernel module 550.54.14 does not match driver library 550.90.07, \
+             reboot to finish the driver upgrade"
+        );
+        assert_eq!(
+            refused.unwrap().kind(),
+            crate::ErrorKind::DriverVersionMismatch
+        );
+    }
 }
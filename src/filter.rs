@@ -0,0 +1,109 @@
+//! Declarative device selection.
+
+use crate::model::{ComputeCapability, Device, Vendor};
+
+/// Hardware floor a device must meet.
+///
+/// ```
+/// use golem_gpu_info::{DeviceFilter, model::Vendor};
+///
+/// let filter = DeviceFilter::new()
+///     .min_memory_gib(12.0)
+///     .min_cuda_caps("8.0")
+///     .vendor(Vendor::Nvidia);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DeviceFilter {
+    min_memory_gib: Option<f32>,
+    min_cuda_caps: Option<String>,
+    vendor: Option<Vendor>,
+}
+
+impl DeviceFilter {
+    /// Filter accepting all devices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts devices with at least `gib` of total memory.
+    pub fn min_memory_gib(mut self, gib: f32) -> Self {
+        self.min_memory_gib = Some(gib);
+        self
+    }
+
+    /// Accepts CUDA devices with compute capability of at least `caps` (e.g. "8.0").
+    ///
+    /// Capability that can not be parsed does not match any device.
+    pub fn min_cuda_caps(mut self, caps: &str) -> Self {
+        self.min_cuda_caps = Some(caps.to_string());
+        self
+    }
+
+    /// Accepts devices of given vendor.
+    pub fn vendor(mut self, vendor: Vendor) -> Self {
+        self.vendor = Some(vendor);
+        self
+    }
+
+    /// Checks if device meets all thresholds.
+    pub fn matches(&self, dev: &Device) -> bool {
+        if let Some(min_memory_gib) = self.min_memory_gib {
+            if dev.memory.total_gib < min_memory_gib {
+                return false;
+            }
+        }
+        if let Some(min_cuda_caps) = &self.min_cuda_caps {
+            let caps = dev.cuda.as_ref().map(|cuda| cuda.caps);
+            match (caps, min_cuda_caps.parse::<ComputeCapability>().ok()) {
+                (Some(caps), Some(min_caps)) if caps >= min_caps => (),
+                _ => return false,
+            }
+        }
+        if let Some(vendor) = self.vendor {
+            if dev.vendor() != Some(vendor) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{DeviceCuda, DeviceMemory};
+
+    fn gen_device(caps: &str, total_gib: f32) -> Device {
+        Device {
+            model: "NVIDIA".into(),
+            cuda: Some(DeviceCuda {
+                enabled: true,
+                cores: 0,
+                caps: caps.parse().unwrap(),
+            }),
+            memory: DeviceMemory {
+                bandwidth_gib: None,
+                measured_bandwidth_gib: None,
+                total_gib,
+            },
+            quantity: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = DeviceFilter::new()
+            .min_memory_gib(12.0)
+            .min_cuda_caps("8.0")
+            .vendor(Vendor::Nvidia);
+
+        assert!(filter.matches(&gen_device("8.6", 24.0)));
+        assert!(filter.matches(&gen_device("10.0", 24.0)));
+        assert!(!filter.matches(&gen_device("7.5", 24.0)));
+        assert!(!filter.matches(&gen_device("8.6", 8.0)));
+        assert!(!DeviceFilter::new()
+            .vendor(Vendor::Amd)
+            .matches(&gen_device("8.6", 24.0)));
+    }
+}
@@ -0,0 +1,235 @@
+//! Human readable output of detected GPUs.
+
+use crate::model::{normalize_name, Cuda, Device, Gpu};
+use std::fmt;
+
+const COLUMNS: &[&str] = &[
+    "model",
+    "quantity",
+    "memory.total.gib",
+    "memory.bandwidth.gib",
+    "memory.bandwidth.measured.gib",
+    "compute.fp32.tflops",
+    "compute.fp16.tflops",
+    "cuda.caps",
+    "clock.graphics.mhz",
+    "clock.memory.mhz",
+    "uuids",
+];
+
+impl Gpu {
+    /// Formats devices as an aligned text table, one row per device group.
+    pub fn to_table(&self) -> String {
+        let rows = self.rows();
+        let widths = COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(idx, header)| {
+                rows.iter()
+                    .map(|row| row[idx].len())
+                    .chain([header.len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let headers = COLUMNS.iter().map(|header| header.to_uppercase()).collect();
+        [headers]
+            .into_iter()
+            .chain(rows)
+            .map(|row| {
+                let line = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:width$}"))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                format!("{}\n", line.trim_end())
+            })
+            .collect()
+    }
+
+    /// Formats devices as CSV with header, one row per device group.
+    ///
+    /// Multiple uuids are separated with `;`.
+    pub fn to_csv(&self) -> String {
+        let headers = COLUMNS.iter().map(|header| header.to_string()).collect();
+        [headers]
+            .into_iter()
+            .chain(self.rows())
+            .map(|row| {
+                let line = row
+                    .iter()
+                    .map(|cell| csv_escape(cell))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{line}\n")
+            })
+            .collect()
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.devices.iter().map(row).collect()
+    }
+}
+
+fn row(dev: &Device) -> Vec<String> {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    vec![
+        dev.model.clone(),
+        dev.quantity.to_string(),
+        dev.memory.total_gib.to_string(),
+        optional(dev.memory.bandwidth_gib.map(|gib| gib.to_string())),
+        optional(dev.memory.measured_bandwidth_gib.map(|gib| gib.to_string())),
+        optional(dev.compute.as_ref().map(|c| c.fp32_tflops.to_string())),
+        optional(
+            dev.compute
+                .as_ref()
+                .and_then(|c| c.fp16_tflops)
+                .map(|tflops| tflops.to_string()),
+        ),
+        optional(dev.cuda.as_ref().map(|cuda| cuda.caps.to_string())),
+        dev.clocks.graphics_mhz.to_string(),
+        dev.clocks.memory_mhz.to_string(),
+        dev.uuids.join(";"),
+    ]
+}
+
+/// One-line summary, e.g. `2× RTX 3090 24GiB, CUDA 12.4, driver 555.85`.
+impl fmt::Display for Gpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.devices.is_empty() {
+            f.write_str("no devices")?;
+        }
+        for (idx, dev) in self.devices.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{dev}")?;
+        }
+        match &self.api.cuda {
+            Some(cuda) => write!(f, ", {cuda}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// One-line summary, e.g. `2× RTX 3090 24GiB`, quantity is omitted for single cards.
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.quantity != 1 {
+            write!(f, "{}× ", self.quantity)?;
+        }
+        let model = self
+            .model_normalized
+            .clone()
+            .unwrap_or_else(|| normalize_name(&self.model));
+        if !self.status.is_available() {
+            return write!(f, "{model} (lost)");
+        }
+        let total_gib = self.memory.total_gib;
+        if total_gib.fract() == 0.0 {
+            write!(f, "{model} {total_gib}GiB")
+        } else {
+            write!(f, "{model} {total_gib:.1}GiB")
+        }
+    }
+}
+
+/// `CUDA 12.4, driver 555.85`, without driver when unknown.
+impl fmt::Display for Cuda {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CUDA {}", self.version)?;
+        match (&self.driver_version, &self.driver_version_raw) {
+            (Some(driver), _) => write!(f, ", driver {driver}"),
+            (None, Some(driver)) => write!(f, ", driver {driver}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{Cuda, Device, DeviceCompute, DeviceMemory, Gpu, GpuApiInfo};
+
+    #[test]
+    fn test_table_and_csv() {
+        let gpu = Gpu {
+            devices: vec![Device {
+                model: "NVIDIA A30, PCIe".into(),
+                memory: DeviceMemory {
+                    total_gib: 24.0,
+                    measured_bandwidth_gib: Some(812),
+                    ..Default::default()
+                },
+                compute: Some(DeviceCompute {
+                    fp32_tflops: 10.5,
+                    fp16_tflops: None,
+                }),
+                quantity: 2,
+                uuids: vec!["GPU-a".into(), "GPU-b".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            gpu.to_csv(),
+            "model,quantity,memory.total.gib,memory.bandwidth.gib,memory.bandwidth.measured.gib,compute.fp32.tflops,compute.fp16.tflops,cuda.caps,clock.graphics.mhz,clock.memory.mhz,uuids\n\
+             \"NVIDIA A30, PCIe\",2,24,,812,10.5,,,0,0,GPU-a;GPU-b\n"
+        );
+        let table = gpu.to_table();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("MODEL             QUANTITY"));
+        assert!(lines[1].starts_with("NVIDIA A30, PCIe  2"));
+        assert!(lines[1].ends_with("GPU-a;GPU-b"));
+    }
+
+    #[test]
+    fn test_display() {
+        let device = |model: &str, total_gib, quantity| Device {
+            model: model.into(),
+            memory: DeviceMemory {
+                total_gib,
+                ..Default::default()
+            },
+            quantity,
+            ..Default::default()
+        };
+        let mut gpu = Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.4".into(),
+                    driver_version: Some("555.85".parse().unwrap()),
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![
+                device("NVIDIA GeForce RTX 3090", 24.0, 2),
+                device("NVIDIA GeForce GTX 1650 Ti", 3.8147, 1),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            gpu.to_string(),
+            "2× RTX 3090 24GiB, GTX 1650 Ti 3.8GiB, CUDA 12.4, driver 555.85"
+        );
+        gpu.devices.clear();
+        gpu.api.cuda.as_mut().unwrap().driver_version = None;
+        assert_eq!(gpu.to_string(), "no devices, CUDA 12.4");
+        gpu.api.cuda.as_mut().unwrap().driver_version_raw = Some("535.129.03-grid".into());
+        assert_eq!(
+            gpu.to_string(),
+            "no devices, CUDA 12.4, driver 535.129.03-grid"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Minimal glob matching for device selection.
+
+/// Matches `text` against a case-insensitive glob `pattern`.
+///
+/// Supports `*` (any sequence) and `?` (any single character).
+pub(crate) fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // position of last `*` in pattern and text position it matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::matches;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*RTX 40*", "NVIDIA GeForce RTX 4090"));
+        assert!(matches("*rtx 3090", "NVIDIA GeForce RTX 3090"));
+        assert!(matches("NVIDIA A?0", "NVIDIA A30"));
+        assert!(!matches("*RTX 40*", "NVIDIA GeForce RTX 3090"));
+        assert!(!matches("A30", "NVIDIA A30"));
+    }
+}
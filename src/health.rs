@@ -0,0 +1,56 @@
+//! Device health reporting.
+
+#[cfg(feature = "deserialize")]
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Health of a single device, see [`GpuDetection::health_check`](crate::GpuDetection::health_check).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthReport {
+    /// Device uuid, `None` if the device no longer responds.
+    pub uuid: Option<String>,
+    /// Backend native device index.
+    pub index: u32,
+    /// Device status.
+    #[serde(flatten)]
+    pub status: HealthStatus,
+}
+
+/// Device health status.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum HealthStatus {
+    /// No problems detected.
+    Healthy,
+    /// Device works, but jobs are likely to fail
+    /// (e.g. uncorrected ECC errors, temperature close to shutdown threshold).
+    Degraded {
+        /// Human readable description of detected problems.
+        reasons: Vec<String>,
+    },
+    /// Device has fallen off the bus or is otherwise inaccessible.
+    Lost,
+    /// Backend can not read the device state.
+    Unknown {
+        /// Why the state could not be read.
+        reason: String,
+    },
+}
+
+/// Device is reported as degraded when it gets this close to thermal shutdown.
+#[cfg(any(feature = "cuda", feature = "amd"))]
+pub(crate) const SHUTDOWN_MARGIN_C: u32 = 5;
+
+impl HealthStatus {
+    /// Builds status from a list of detected problems.
+    pub(crate) fn from_reasons(reasons: Vec<String>) -> Self {
+        if reasons.is_empty() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded { reasons }
+        }
+    }
+}
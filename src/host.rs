@@ -0,0 +1,212 @@
+//! Host environment detection.
+
+use crate::model::{Device, Host};
+
+/// Collects information about the host operating system and its detected `devices`.
+pub(crate) fn detect(devices: &[Device]) -> Host {
+    Host {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        kernel_version: imp::kernel_version(),
+        virtualization: imp::virtualization(),
+        container: imp::container(),
+        container_toolkit: imp::container_toolkit(),
+        persistence_mode: persistence_mode(devices),
+    }
+}
+
+/// Persistence mode of the host, off when any card has it off.
+fn persistence_mode(devices: &[Device]) -> Option<bool> {
+    (devices.iter())
+        .filter_map(|dev| dev.persistence_mode)
+        .reduce(|all, on| all && on)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use crate::model::ContainerToolkit;
+    use std::fs;
+    use std::path::Path;
+
+    fn read(path: &str) -> Option<String> {
+        fs::read_to_string(path)
+            .ok()
+            .map(|value| value.trim().to_string())
+    }
+
+    pub fn kernel_version() -> Option<String> {
+        read("/proc/sys/kernel/osrelease")
+    }
+
+    pub fn virtualization() -> Option<String> {
+        if kernel_version().is_some_and(|release| release.to_lowercase().contains("microsoft")) {
+            return Some("wsl".into());
+        }
+        let vendor = read("/sys/class/dmi/id/sys_vendor").unwrap_or_default();
+        let product = read("/sys/class/dmi/id/product_name").unwrap_or_default();
+        super::hypervisor_from_dmi(&vendor, &product).map(Into::into)
+    }
+
+    pub fn container() -> Option<String> {
+        if Path::new("/.dockerenv").exists() {
+            return Some("docker".into());
+        }
+        if Path::new("/run/.containerenv").exists() {
+            return Some("podman".into());
+        }
+        let cgroup = read("/proc/1/cgroup").unwrap_or_default();
+        super::container_from_cgroup(&cgroup).map(Into::into)
+    }
+
+    const CDI_DIRS: [&str; 2] = ["/etc/cdi", "/var/run/cdi"];
+
+    pub fn container_toolkit() -> Option<ContainerToolkit> {
+        let cdi_specs = CDI_DIRS
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                fs::read_to_string(path).is_ok_and(|spec| super::is_nvidia_cdi_spec(&spec))
+            })
+            .map(|path| path.display().to_string())
+            .collect();
+        let docker_config = read("/etc/docker/daemon.json").unwrap_or_default();
+        Some(ContainerToolkit {
+            cli: in_path("nvidia-container-cli"),
+            runtime: in_path("nvidia-container-runtime"),
+            docker_runtime: super::has_nvidia_runtime(&docker_config),
+            cdi_specs,
+        })
+    }
+
+    fn in_path(binary: &str) -> bool {
+        std::env::var_os("PATH").is_some_and(|paths| {
+            std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file())
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use crate::model::ContainerToolkit;
+
+    pub fn kernel_version() -> Option<String> {
+        None
+    }
+
+    pub fn virtualization() -> Option<String> {
+        None
+    }
+
+    pub fn container() -> Option<String> {
+        None
+    }
+
+    pub fn container_toolkit() -> Option<ContainerToolkit> {
+        None
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn hypervisor_from_dmi(vendor: &str, product: &str) -> Option<&'static str> {
+    const HYPERVISORS: [(&str, &str); 8] = [
+        ("qemu", "qemu"),
+        ("kvm", "kvm"),
+        ("vmware", "vmware"),
+        ("virtualbox", "virtualbox"),
+        ("xen", "xen"),
+        ("amazon ec2", "aws"),
+        ("google compute engine", "gce"),
+        ("virtual machine", "hyper-v"),
+    ];
+    let dmi = format!("{vendor} {product}").to_lowercase();
+    HYPERVISORS
+        .iter()
+        .find(|(pattern, _)| dmi.contains(pattern))
+        .map(|(_, name)| *name)
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn container_from_cgroup(cgroup: &str) -> Option<&'static str> {
+    if cgroup.contains("kubepods") {
+        Some("kubernetes")
+    } else if cgroup.contains("docker") {
+        Some("docker")
+    } else if cgroup.contains("lxc") {
+        Some("lxc")
+    } else {
+        None
+    }
+}
+
+// CDI specs are YAML or JSON, NVIDIA ones declare `kind: nvidia.com/gpu`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn is_nvidia_cdi_spec(spec: &str) -> bool {
+    spec.contains("nvidia.com/gpu")
+}
+
+// `nvidia-ctk runtime configure` registers `nvidia-container-runtime` in daemon.json.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn has_nvidia_runtime(docker_config: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(docker_config).is_ok_and(|config| {
+        config["runtimes"].as_object().is_some_and(|runtimes| {
+            runtimes.values().any(|runtime| {
+                runtime["path"]
+                    .as_str()
+                    .is_some_and(|path| path.ends_with("nvidia-container-runtime"))
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{container_from_cgroup, has_nvidia_runtime, hypervisor_from_dmi, persistence_mode};
+    use crate::model::Device;
+
+    #[test]
+    fn test_hypervisor_from_dmi() {
+        assert_eq!(
+            hypervisor_from_dmi("QEMU", "Standard PC (Q35)"),
+            Some("qemu")
+        );
+        assert_eq!(
+            hypervisor_from_dmi("Microsoft Corporation", "Virtual Machine"),
+            Some("hyper-v")
+        );
+        assert_eq!(hypervisor_from_dmi("ASUS", "System Product Name"), None);
+    }
+
+    #[test]
+    fn test_container_from_cgroup() {
+        assert_eq!(
+            container_from_cgroup("0::/kubepods/besteffort/pod1/abc"),
+            Some("kubernetes")
+        );
+        assert_eq!(container_from_cgroup("0::/init.scope"), None);
+    }
+
+    #[test]
+    fn test_has_nvidia_runtime() {
+        assert!(has_nvidia_runtime(
+            r#"{"runtimes": {"nvidia": {"args": [], "path": "nvidia-container-runtime"}}}"#
+        ));
+        assert!(!has_nvidia_runtime(r#"{"log-driver": "json-file"}"#));
+        assert!(!has_nvidia_runtime(""));
+    }
+
+    #[test]
+    fn test_persistence_mode() {
+        let dev = |persistence_mode| Device {
+            persistence_mode,
+            ..Default::default()
+        };
+        assert_eq!(persistence_mode(&[dev(Some(true)), dev(None)]), Some(true));
+        assert_eq!(
+            persistence_mode(&[dev(Some(true)), dev(Some(false))]),
+            Some(false)
+        );
+        assert_eq!(persistence_mode(&[dev(None)]), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Operating system device notifications waking up [`Watcher`](crate::watch::Watcher).
+//!
+//! Listeners only request a rescan, changes are still found by comparing snapshots.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::Thread;
+use std::time::Duration;
+
+/// Time for the driver to set up an added card before rescan.
+const SETTLE: Duration = Duration::from_secs(1);
+
+/// Requests from a listener to the polling thread.
+#[derive(Default)]
+pub(crate) struct Rescan {
+    /// Backends should be re-initialized before the next snapshot.
+    pub requested: AtomicBool,
+    /// Driver was reset, reported even if its version did not change.
+    pub driver_reset: AtomicBool,
+}
+
+impl Rescan {
+    /// Wakes `poller` once the device settles.
+    #[cfg_attr(
+        not(any(
+            all(feature = "udev", target_os = "linux"),
+            all(feature = "wmi", windows)
+        )),
+        allow(dead_code)
+    )]
+    fn request(&self, poller: &Thread, driver_reset: bool) {
+        std::thread::sleep(SETTLE);
+        if driver_reset {
+            self.driver_reset.store(true, Ordering::Relaxed);
+        }
+        self.requested.store(true, Ordering::Relaxed);
+        poller.unpark();
+    }
+}
+
+/// Starts a listener waking `poller` until `stop` is set.
+#[cfg(all(feature = "udev", target_os = "linux"))]
+pub(crate) fn spawn(poller: Thread, rescan: Arc<Rescan>, stop: Arc<AtomicBool>) -> io::Result<()> {
+    std::thread::Builder::new()
+        .name("gpu-watch-udev".into())
+        // without udev (e.g. in containers) polling still works.
+        .spawn(move || drop(udev::listen(&poller, &rescan, &stop)))?;
+    Ok(())
+}
+
+/// Starts a listener waking `poller` until `stop` is set.
+#[cfg(all(feature = "wmi", windows))]
+pub(crate) fn spawn(poller: Thread, rescan: Arc<Rescan>, stop: Arc<AtomicBool>) -> io::Result<()> {
+    std::thread::Builder::new()
+        .name("gpu-watch-wmi".into())
+        // without WMI service polling still works.
+        .spawn(move || drop(wmi::listen(&poller, &rescan, &stop)))?;
+    Ok(())
+}
+
+/// No listener, devices are polled only.
+#[cfg(not(any(
+    all(feature = "udev", target_os = "linux"),
+    all(feature = "wmi", windows)
+)))]
+pub(crate) fn spawn(
+    _poller: Thread,
+    _rescan: Arc<Rescan>,
+    _stop: Arc<AtomicBool>,
+) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(feature = "udev", target_os = "linux"))]
+mod udev {
+    use super::Rescan;
+    use ::udev::{Event, EventType, MonitorBuilder};
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    /// How often the monitor socket and `stop` flag are checked.
+    const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// Requests rescan when a GPU appears, disappears or is re-bound (e.g. to `vfio-pci`).
+    pub(super) fn listen(poller: &Thread, rescan: &Rescan, stop: &AtomicBool) -> io::Result<()> {
+        let socket = MonitorBuilder::new()?
+            .match_subsystem("drm")?
+            .match_subsystem("pci")?
+            .listen()?;
+        while !stop.load(Ordering::Relaxed) {
+            // drains all pending events.
+            if socket.iter().filter(is_gpu_change).count() > 0 {
+                rescan.request(poller, false);
+            }
+            thread::sleep(CHECK_INTERVAL);
+        }
+        Ok(())
+    }
+
+    fn is_gpu_change(event: &Event) -> bool {
+        if !matches!(
+            event.event_type(),
+            EventType::Add | EventType::Remove | EventType::Bind | EventType::Unbind
+        ) {
+            return false;
+        }
+        match event.subsystem().and_then(|subsystem| subsystem.to_str()) {
+            Some("drm") => true,
+            // display controllers are class 0x03, formatted without leading zeros.
+            Some("pci") => event
+                .property_value("PCI_CLASS")
+                .and_then(|class| class.to_str())
+                .is_some_and(|class| class.len() == 5 && class.starts_with('3')),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(all(feature = "wmi", windows))]
+mod wmi {
+    use super::Rescan;
+    use ::wmi::{COMLibrary, Variant, WMIConnection};
+    use futures_core::Stream;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Poll, Waker};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    /// Video controllers added or removed, and changes of their state, which include
+    /// driver resets after a TDR (timeout detection and recovery) and driver updates.
+    /// Other changes (e.g. display resolution) are left out. Instances are checked
+    /// by WMI every 2 seconds.
+    const QUERY: &str = "SELECT * FROM __InstanceOperationEvent WITHIN 2 \
+        WHERE TargetInstance ISA 'Win32_VideoController' \
+        AND (__CLASS <> '__InstanceModificationEvent' \
+        OR TargetInstance.Status <> PreviousInstance.Status \
+        OR TargetInstance.ConfigManagerErrorCode <> PreviousInstance.ConfigManagerErrorCode \
+        OR TargetInstance.DriverVersion <> PreviousInstance.DriverVersion)";
+
+    /// How often delivered events and the `stop` flag are checked.
+    const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// Requests rescan and driver reload when a video controller is added, removed or
+    /// changes state.
+    pub(super) fn listen(
+        poller: &Thread,
+        rescan: &Rescan,
+        stop: &AtomicBool,
+    ) -> Result<(), ::wmi::WMIError> {
+        let connection = WMIConnection::new(COMLibrary::new()?)?;
+        // events are delivered to the stream by WMI, dropping it cancels the query.
+        let mut events =
+            Box::pin(connection.async_raw_notification::<HashMap<String, Variant>>(QUERY)?);
+        let mut cx = Context::from_waker(Waker::noop());
+        while !stop.load(Ordering::Relaxed) {
+            // drains all pending events.
+            let mut changed = false;
+            loop {
+                match events.as_mut().poll_next(&mut cx) {
+                    Poll::Ready(Some(event)) => {
+                        event?;
+                        changed = true;
+                    }
+                    Poll::Ready(None) => return Ok(()),
+                    Poll::Pending => break,
+                }
+            }
+            if changed {
+                rescan.request(poller, true);
+            }
+            thread::sleep(CHECK_INTERVAL);
+        }
+        Ok(())
+    }
+}
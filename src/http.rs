@@ -0,0 +1,132 @@
+//! Minimal HTTP endpoint serving detection results as JSON.
+//!
+//! Routes:
+//! * `GET /gpu` - detected GPUs,
+//! * `GET /gpu/{uuid}` - single device,
+//! * `GET /health` - health of all devices.
+
+use crate::{GpuDetection, GpuDetectionError};
+use serde::Serialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Longest accepted request head.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+/// Clients stalling longer on a read or write are disconnected.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Requests handled at once, further ones are refused with `503`.
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+
+impl GpuDetection {
+    /// Serves detection results over HTTP on `addr`, blocking the current thread.
+    ///
+    /// Every request runs detection anew, at most 16 at a time. The server has
+    /// no authentication, bind it to a loopback or otherwise trusted address.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        serve_on(self, TcpListener::bind(addr)?)
+    }
+}
+
+pub(crate) fn serve_on(detection: &GpuDetection, listener: TcpListener) -> io::Result<()> {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            // e.g. client reset connection before it was accepted.
+            Err(_) => continue,
+        };
+        // slow or silent client must not hold a handler forever.
+        if stream.set_read_timeout(Some(IO_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(IO_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        if active.fetch_add(1, Ordering::AcqRel) >= MAX_CONCURRENT_REQUESTS {
+            active.fetch_sub(1, Ordering::AcqRel);
+            let body = json!({ "error": "too many requests in progress" }).to_string();
+            let _ = write_response(&mut stream, "503 Service Unavailable", &body);
+            continue;
+        }
+        let detection = detection.clone();
+        let slot = Slot(active.clone());
+        thread::spawn(move || {
+            let _slot = slot;
+            // client went away, nothing to report to.
+            let _ = handle(&detection, stream);
+        });
+    }
+    Ok(())
+}
+
+// Frees a handler slot when the request is done, even if it panicked.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle(detection: &GpuDetection, mut stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_HEAD_BYTES as u64);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // skip headers, requests have no body.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => route(detection, path),
+        _ => (
+            "405 Method Not Allowed",
+            json!({ "error": "only GET is supported" }).to_string(),
+        ),
+    };
+    write_response(&mut stream, status, &body)
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn route(detection: &GpuDetection, path: &str) -> (&'static str, String) {
+    let path = path.split('?').next().unwrap_or_default();
+    match path.trim_end_matches('/') {
+        "/gpu" => respond(detection.detect()),
+        "/health" => respond(detection.health_check()),
+        path => match path.strip_prefix("/gpu/") {
+            Some(uuid) if !uuid.contains('/') => respond(detection.search_by_uuid(uuid)),
+            _ => ("404 Not Found", json!({ "error": "not found" }).to_string()),
+        },
+    }
+}
+
+fn respond(result: Result<impl Serialize, GpuDetectionError>) -> (&'static str, String) {
+    match result {
+        // serializing plain data structures does not fail.
+        Ok(value) => ("200 OK", serde_json::to_string(&value).unwrap_or_default()),
+        Err(e @ GpuDetectionError::NotFound { .. }) => (
+            "404 Not Found",
+            json!({ "error": e.to_string(), "code": e.code() }).to_string(),
+        ),
+        Err(e) => (
+            "500 Internal Server Error",
+            json!({ "error": e.to_string(), "code": e.code() }).to_string(),
+        ),
+    }
+}
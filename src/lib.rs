@@ -2,10 +2,27 @@
 #![forbid(unsafe_code)]
 //! GPU Device detection and offer builder.
 
+// macros must be defined before modules using them.
+#[macro_use]
+mod trace;
+
+pub mod aggregation;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "deserialize")]
+pub mod capture;
+pub mod constraints;
 pub mod model;
+pub mod monitor;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod reservation;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod watch;
 
 #[cfg(feature = "amd")]
-mod amd;
+pub mod amd;
 #[cfg(not(feature = "amd"))]
 mod amd {
     #[derive(thiserror::Error, Debug)]
@@ -13,19 +30,67 @@ mod amd {
     pub struct AmdError {
         _inner: (),
     }
+
+    pub(crate) fn error_kind(_e: &AmdError) -> crate::ErrorKind {
+        crate::ErrorKind::Other
+    }
 }
 
+mod backend;
+#[cfg(all(windows, feature = "wmi", feature = "cuda"))]
+mod battery;
+mod compat;
+#[cfg(feature = "config")]
+mod config;
 #[cfg(feature = "cuda")]
-mod cuda;
+pub mod cuda;
+#[cfg(not(feature = "cuda"))]
+mod cuda {
+    #[derive(thiserror::Error, Debug)]
+    #[error("NVML never")]
+    pub enum NvmlError {}
+
+    pub(crate) fn error_kind(e: &NvmlError) -> crate::ErrorKind {
+        match *e {}
+    }
+}
+mod filter;
+mod format;
+mod glob;
+mod health;
+mod host;
+mod hotplug;
+#[cfg(feature = "http")]
+mod http;
+mod pci;
 mod platform;
+mod report;
+mod runtime;
+mod specs;
+#[cfg(all(target_os = "linux", any(feature = "cuda", feature = "amd")))]
+mod sysfs;
+mod timeout;
 
-use crate::model::Device;
+pub use crate::aggregation::{AggregationKey, AggregationPolicy};
+use crate::backend::{Backend, Backends, BackendsState, LAZY_INIT_BACKOFF};
+pub use crate::backend::{BackendInfo, BackendStatus};
+pub use crate::filter::DeviceFilter;
+pub use crate::health::{HealthReport, HealthStatus};
+use crate::model::{Device, GpuApiInfo, GpuProcess, Telemetry};
 use crate::platform::{Detection, Flags, Platform};
+pub use crate::report::{DetectionReport, DetectionWarning};
+pub use crate::runtime::PickCriterion;
 pub use model::Gpu;
+#[cfg(feature = "deserialize")]
+use serde::Deserialize;
+use serde::Serialize;
 use static_assertions::*;
 use std::collections::BTreeSet;
-use std::mem;
+use std::fmt;
+use std::path::PathBuf;
 use std::result::Result as StdResult;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 /// Errors
@@ -47,46 +112,453 @@ pub enum GpuDetectionError {
     #[error("NVML error occurred: {0}")]
     Unknown(String),
 
-    /// Required driver not found.
-    #[error("Driver not found")]
-    NotFound,
+    /// Required driver or device not found.
+    #[error("{}", not_found_message(.reasons))]
+    NotFound {
+        /// Why platforms are unavailable, with suggested fixes. Empty when the driver
+        /// works, but the requested device or platform is not there.
+        reasons: Vec<NotFoundReason>,
+    },
+
+    /// Device has fallen off the bus or requires a reset.
+    #[error("GPU has fallen off the bus or requires a reset")]
+    DeviceLost,
+
+    /// Driver call did not finish in time.
+    #[error("GPU driver call timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// Driver or device does not support the requested query.
+    #[error("{0} is not supported")]
+    NotSupported(String),
+
+    /// NVIDIA Management Library error.
+    #[error("NVML error occurred: {0}")]
+    NvmlError(#[from] cuda::NvmlError),
 
     /// Amd driver error
     #[error(transparent)]
     AmdError(#[from] amd::AmdError),
+
+    /// NVIDIA kernel module and driver library versions differ, usually after a driver
+    /// upgrade without reboot.
+    #[error(
+        "NVIDIA kernel module {kernel} does not match driver library{}, reboot to finish the driver upgrade",
+        .library.as_ref().map(|library| format!(" {library}")).unwrap_or_default()
+    )]
+    DriverVersionMismatch {
+        /// Version of the loaded kernel module.
+        kernel: String,
+        /// Version of the driver library, unknown when it refuses to initialize.
+        library: Option<String>,
+    },
+
+    /// Kernel driver is not loaded, e.g. the `nvidia` module was not rebuilt
+    /// after a kernel upgrade.
+    #[error("{module} kernel module not loaded")]
+    KernelModuleNotLoaded {
+        /// Name of the kernel module.
+        module: String,
+    },
+
+    /// Device node of the kernel driver does not exist, e.g. `/dev/kfd` of a driver
+    /// built without ROCm compute support or not passed to a container.
+    #[error(
+        "{} not found, the kernel driver does not support compute or the device is not passed to the container",
+        .path.display()
+    )]
+    DeviceNodeMissing {
+        /// Missing device node.
+        path: PathBuf,
+    },
+
+    /// Installed driver or runtime is older than the oldest supported one,
+    /// see [`GpuDetection::check_environment`].
+    #[error("{component} {installed} is older than the oldest supported {required}, update it")]
+    UnsupportedDriver {
+        /// Outdated component (e.g. `"NVIDIA driver"`).
+        component: String,
+        /// Installed version.
+        installed: String,
+        /// Oldest supported version.
+        required: String,
+    },
+
+    /// Kernel driver is ready, but its userspace libraries (e.g. ROCm) failed to initialize.
+    #[error("{runtime} userspace is not installed or does not work: {message}")]
+    RuntimeUnavailable {
+        /// Name of the userspace stack.
+        runtime: String,
+        /// Initialization error.
+        message: String,
+    },
+
+    /// Current user can not open a device node the driver needs (e.g. `/dev/kfd`),
+    /// usually because of missing group membership.
+    #[error("{}", permission_denied_message(.path, .groups))]
+    PermissionDenied {
+        /// Device node which could not be opened.
+        path: PathBuf,
+        /// Groups owning the inaccessible device nodes.
+        groups: Vec<String>,
+    },
+
+    /// Configuration file can not be read or is invalid,
+    /// see [`GpuDetectionBuilder::from_config`].
+    #[error("Invalid config {}: {message}", .path.display())]
+    ConfigError {
+        /// Path of the configuration file.
+        path: PathBuf,
+        /// Read or parse error.
+        message: String,
+    },
+
+    /// Benchmark failed, see [`bench`].
+    #[cfg(feature = "bench")]
+    #[error("{benchmark} benchmark failed: {source}")]
+    Benchmark {
+        /// Name of the benchmark (e.g. `"Bandwidth"`).
+        benchmark: String,
+        /// Runtime error.
+        #[source]
+        source: golem_gpu_bench::BenchError,
+    },
+
+    /// Error served from a capture (see [`capture`]), or repeated by queries
+    /// of lazy detection after a failed initialization.
+    #[error("{message}")]
+    Replayed {
+        /// Category of the recorded error.
+        kind: ErrorKind,
+        /// Message of the recorded error.
+        message: String,
+    },
+}
+
+impl GpuDetectionError {
+    /// Stable classification of the error, independent of backend and message.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            GpuDetectionError::LibloadingError(_)
+            | GpuDetectionError::KernelModuleNotLoaded { .. }
+            | GpuDetectionError::DeviceNodeMissing { .. }
+            | GpuDetectionError::RuntimeUnavailable { .. } => ErrorKind::DriverNotFound,
+            GpuDetectionError::NotFound { .. } => ErrorKind::NotFound,
+            GpuDetectionError::DeviceLost => ErrorKind::DeviceLost,
+            GpuDetectionError::Timeout(_) => ErrorKind::Timeout,
+            GpuDetectionError::NotSupported(_) => ErrorKind::NotSupported,
+            GpuDetectionError::NvmlError(e) => cuda::error_kind(e),
+            GpuDetectionError::AmdError(e) => amd::error_kind(e),
+            GpuDetectionError::GpuAccessError(_)
+            | GpuDetectionError::GpuInfoAccessError(_)
+            | GpuDetectionError::Unknown(_)
+            | GpuDetectionError::ConfigError { .. } => ErrorKind::Other,
+            GpuDetectionError::DriverVersionMismatch { .. } => ErrorKind::DriverVersionMismatch,
+            GpuDetectionError::PermissionDenied { .. } => ErrorKind::PermissionDenied,
+            GpuDetectionError::UnsupportedDriver { .. } => ErrorKind::DriverTooOld,
+            #[cfg(feature = "bench")]
+            GpuDetectionError::Benchmark { source, .. } => bench::error_kind(source),
+            GpuDetectionError::Replayed { kind, .. } => *kind,
+        }
+    }
+
+    /// [`NotFound`](Self::NotFound) without reasons.
+    pub(crate) fn not_found() -> Self {
+        GpuDetectionError::NotFound {
+            reasons: Vec::new(),
+        }
+    }
+
+    /// Stable machine readable code of the error (e.g. `"DRIVER_TOO_OLD"`),
+    /// for clients showing tailored instructions, see [`ErrorKind::as_str`].
+    pub fn code(&self) -> &'static str {
+        self.kind().as_str()
+    }
+}
+
+/// Why a platform is unavailable, see [`GpuDetectionError::NotFound`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct NotFoundReason {
+    /// Platform name (e.g. `"cuda"`).
+    pub backend: String,
+    /// Code of the initialization error.
+    pub code: ErrorKind,
+    /// Initialization error message.
+    pub reason: String,
+    /// Suggested fix, unless the message already tells what to do.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl NotFoundReason {
+    /// Reasons of platform `backend` failing with `e`, reasons of nested
+    /// [`GpuDetectionError::NotFound`] are taken as they are.
+    pub(crate) fn from_error(backend: &str, e: &GpuDetectionError) -> Vec<NotFoundReason> {
+        match e {
+            GpuDetectionError::NotFound { reasons } => reasons.clone(),
+            e => vec![NotFoundReason {
+                backend: backend.to_string(),
+                code: e.kind(),
+                reason: e.to_string(),
+                hint: remediation(backend, e),
+            }],
+        }
+    }
+}
+
+// Suggests what the provider should do about a failing platform.
+fn remediation(backend: &str, e: &GpuDetectionError) -> Option<String> {
+    let vendor = match backend {
+        "cuda" => "NVIDIA",
+        "amd" => "AMD",
+        _ => "GPU",
+    };
+    match e {
+        GpuDetectionError::KernelModuleNotLoaded { module } => Some(format!(
+            "load it with `sudo modprobe {module}` or reinstall the {vendor} driver for the running kernel"
+        )),
+        GpuDetectionError::DeviceNodeMissing { path } => Some(format!(
+            "install a {vendor} kernel driver with compute support or pass {} to the container",
+            path.display()
+        )),
+        GpuDetectionError::RuntimeUnavailable { runtime, .. } => {
+            Some(format!("install {runtime} matching the kernel driver"))
+        }
+        // messages already explain what to do.
+        GpuDetectionError::PermissionDenied { .. }
+        | GpuDetectionError::DriverVersionMismatch { .. }
+        | GpuDetectionError::UnsupportedDriver { .. } => None,
+        e => match e.kind() {
+            ErrorKind::DriverNotFound => Some(format!("install the {vendor} driver")),
+            ErrorKind::DriverTooOld => Some(format!("update the {vendor} driver")),
+            ErrorKind::DeviceLost => Some("reset the card or reboot".into()),
+            _ => None,
+        },
+    }
+}
+
+fn not_found_message(reasons: &[NotFoundReason]) -> String {
+    let mut message = String::from("Driver not found");
+    for (i, reason) in reasons.iter().enumerate() {
+        message.push_str(if i == 0 { ": " } else { "; " });
+        message.push_str(&format!("{}: {}", reason.backend, reason.reason));
+        if let Some(hint) = &reason.hint {
+            message.push_str(&format!(", {hint}"));
+        }
+    }
+    message
+}
+
+// Explains how to get access, like rocm-smi does.
+fn permission_denied_message(path: &std::path::Path, groups: &[String]) -> String {
+    let path = path.display();
+    match groups {
+        [] => format!("permission denied to {path}"),
+        groups => format!(
+            "permission denied to {path}, add the user to the {} group{} \
+             (`sudo usermod -aG {} $USER`) and log in again",
+            groups.join(" and "),
+            if groups.len() > 1 { "s" } else { "" },
+            groups.join(","),
+        ),
+    }
+}
+
+// Declares `ErrorKind` together with the code of every kind,
+// so that serialization and `ErrorKind::as_str` can not disagree.
+macro_rules! error_kinds {
+    ($($(#[doc = $doc:literal])* $kind:ident => $code:literal,)*) => {
+        /// Category of [`GpuDetectionError`], see [`GpuDetectionError::kind`].
+        ///
+        /// Serialized as a stable code next to error messages (e.g. `"DRIVER_TOO_OLD"`),
+        /// codes are never renamed.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+        #[cfg_attr(feature = "deserialize", derive(Deserialize))]
+        #[non_exhaustive]
+        pub enum ErrorKind {
+            $($(#[doc = $doc])* #[serde(rename = $code)] $kind,)*
+        }
+
+        impl ErrorKind {
+            /// Code as serialized, e.g. `"NO_PERMISSION"`.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(ErrorKind::$kind => $code,)*
+                }
+            }
+        }
+    };
+}
+
+error_kinds! {
+    /// Driver library is missing or the kernel driver is not loaded.
+    DriverNotFound => "LIB_NOT_FOUND",
+    /// Driver does not provide required functions.
+    DriverTooOld => "DRIVER_TOO_OLD",
+    /// Kernel driver and driver library versions differ, a reboot is needed.
+    DriverVersionMismatch => "DRIVER_VERSION_MISMATCH",
+    /// Current user is not allowed to perform the operation.
+    PermissionDenied => "NO_PERMISSION",
+    /// Operation is not available on the device.
+    NotSupported => "NOT_SUPPORTED",
+    /// Device has fallen off the bus or requires a reset.
+    DeviceLost => "DEVICE_LOST",
+    /// Requested device or driver was not found.
+    NotFound => "NOT_FOUND",
+    /// Driver call did not finish in time.
+    Timeout => "TIMEOUT",
+    /// Any other error.
+    Other => "UNKNOWN",
+}
+
+impl ErrorKind {
+    // Whether the operation may succeed when retried, e.g. driver still starting.
+    fn is_transient(self) -> bool {
+        matches!(self, ErrorKind::Timeout | ErrorKind::Other)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 type Result<T> = StdResult<T, GpuDetectionError>;
 
+// What a single backend detected, see `GpuDetection::detect_backends`.
+struct BackendDetection {
+    // position in `Backends::list`.
+    idx: usize,
+    // failed `Detection::detect_api`, API info of the backend is left out.
+    api_error: Option<GpuDetectionError>,
+    // `Err` when the whole backend failed.
+    devices: Result<Vec<Result<Device>>>,
+}
+
+/// How [`GpuDetection::detect`] handles failing devices and platforms,
+/// see [`GpuDetectionBuilder::policy`].
+///
+/// Properties the driver does not report are not failures under either policy,
+/// they are listed in [`Device::unsupported`](model::Device::unsupported).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DetectionPolicy {
+    /// Any failing device or platform fails detection, including platforms
+    /// which hang (see [`GpuDetectionBuilder::query_timeout`]).
+    #[default]
+    Strict,
+    /// Failing devices and platforms which are not forced are skipped.
+    /// Properties which fail to read are left out, the card is kept with them
+    /// listed in [`Device::unsupported`](model::Device::unsupported).
+    ///
+    /// Use [`GpuDetection::detect_report`] to find out what was skipped.
+    Lenient,
+}
+
+/// Utilization above which [`GpuDetection::is_busy`] reports a device as busy.
+pub const BUSY_UTILIZATION_PCT: u32 = 10;
+
 /// Initialize device discovery backends.
+#[derive(Clone)]
 pub struct GpuDetectionBuilder {
-    force: BTreeSet<&'static str>,
+    force: BTreeSet<String>,
     unstable: bool,
+    dynamic: bool,
+    host_info: bool,
+    aggregation: AggregationPolicy,
+    visible: Option<BTreeSet<String>>,
+    excluded: BTreeSet<String>,
+    nvml_lib_path: Option<PathBuf>,
+    rocm_lib_path: Option<PathBuf>,
+    sysfs_root: PathBuf,
+    init_timeout: Option<Duration>,
+    query_timeout: Option<Duration>,
+    enrich_specs: bool,
+    normalized_names: bool,
+    runtime_resume: bool,
+    threads: usize,
+    policy: DetectionPolicy,
+    lazy: bool,
 
-    platforms: Vec<&'static dyn Platform>,
+    platforms: Vec<Arc<dyn Platform>>,
+    // names of platforms left out, listed by `GpuDetection::backends`.
+    disabled: Vec<String>,
+    // failure of the recorded initialization, see `replay_capture`.
+    #[cfg(feature = "deserialize")]
+    replayed_init_error: Option<capture::RecordedError>,
 }
 
 impl Default for GpuDetectionBuilder {
     fn default() -> Self {
         let force = Default::default();
         let unstable = false;
+        let dynamic = false;
+        let host_info = false;
+        let aggregation = Default::default();
+        let visible = None;
+        let excluded = Default::default();
+        let nvml_lib_path = None;
+        let rocm_lib_path = None;
+        let sysfs_root = PathBuf::from("/sys");
+        let init_timeout = None;
+        let query_timeout = None;
+        let enrich_specs = false;
+        let normalized_names = false;
+        let runtime_resume = false;
+        let threads = 1;
+        let policy = Default::default();
+        let lazy = false;
         let platforms = vec![
             #[cfg(feature = "cuda")]
             cuda::platform(),
             #[cfg(feature = "amd")]
             amd::platform(),
         ];
+        let disabled = Vec::new();
+        #[cfg(feature = "deserialize")]
+        let replayed_init_error = None;
         Self {
             force,
             unstable,
+            dynamic,
+            host_info,
+            aggregation,
+            visible,
+            excluded,
+            nvml_lib_path,
+            rocm_lib_path,
+            sysfs_root,
+            init_timeout,
+            query_timeout,
+            enrich_specs,
+            normalized_names,
+            runtime_resume,
+            threads,
+            policy,
+            lazy,
             platforms,
+            disabled,
+            #[cfg(feature = "deserialize")]
+            replayed_init_error,
         }
     }
 }
 
 /// Device detection service.
+///
+/// Cloning is cheap, clones share initialized backends.
+#[derive(Clone)]
 pub struct GpuDetection {
-    detections: Vec<Box<dyn Detection>>,
+    backends: Arc<Mutex<BackendsState>>,
+    query_timeout: Option<Duration>,
+    host_info: bool,
+    aggregation: AggregationPolicy,
+    policy: DetectionPolicy,
+    visible: Option<BTreeSet<String>>,
+    excluded: BTreeSet<String>,
+    builder: GpuDetectionBuilder,
 }
 
 assert_impl_all!(GpuDetection: Send, Sync);
@@ -94,8 +566,72 @@ assert_impl_all!(GpuDetection: Send, Sync);
 impl GpuDetectionBuilder {
     /// Queries about devices will result in an error if
     /// NVIDIA Management Library is not available in the current environment.
-    pub fn force_cuda(mut self) -> Self {
-        self.force.insert("cuda");
+    pub fn force_cuda(self) -> Self {
+        self.force("cuda")
+    }
+
+    /// Queries about devices will result in an error if
+    /// ROCm SMI library is not available in the current environment.
+    pub fn force_amd(self) -> Self {
+        self.force("amd")
+    }
+
+    /// Initialization will result in an error if platform with given name
+    /// (`cuda`, `amd`) fails to initialize or is not compiled in.
+    pub fn force(mut self, name: &str) -> Self {
+        self.force.insert(name.to_string());
+        self
+    }
+
+    /// Probes only platforms with given names (e.g. `&["cuda"]`),
+    /// regardless of compiled features.
+    pub fn enable_only(self, names: &[&str]) -> Self {
+        self.retain_platforms(|name| names.contains(&name))
+    }
+
+    /// Adds mock platform, see [`testing::MockPlatform`].
+    ///
+    /// Built-in platforms are still probed, use [`enable_only`](Self::enable_only) to skip them.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn mock(mut self, platform: testing::MockPlatform) -> Self {
+        self.platforms.push(Arc::new(platform));
+        self
+    }
+
+    /// Serves recorded backend in place of the platform with the same name,
+    /// see [`capture`].
+    #[cfg(feature = "deserialize")]
+    pub fn replay(mut self, platform: capture::ReplayPlatform) -> Self {
+        // replaced, not disabled platform.
+        (self.platforms).retain(|other| other.name() != Platform::name(&platform));
+        self.platforms.push(Arc::new(platform));
+        self
+    }
+
+    /// Replaces all platforms with the ones recorded in `capture`, replaying
+    /// their initialization errors and the platforms left out as well.
+    #[cfg(feature = "deserialize")]
+    pub fn replay_capture(mut self, capture: capture::Capture) -> Self {
+        self.platforms.clear();
+        self.disabled = capture.disabled.clone();
+        self.replayed_init_error = capture.init_error.clone();
+        for platform in capture.into_platforms() {
+            self.platforms.push(Arc::new(platform));
+        }
+        self
+    }
+
+    /// Skips probing of platform with given name (e.g. `"amd"`).
+    pub fn disable(self, name: &str) -> Self {
+        self.retain_platforms(|platform| platform != name)
+    }
+
+    // Drops platforms whose name does not pass `keep`, remembering them as disabled.
+    fn retain_platforms(mut self, keep: impl Fn(&str) -> bool) -> Self {
+        let (enabled, disabled): (Vec<_>, Vec<_>) =
+            (self.platforms.into_iter()).partition(|platform| keep(platform.name()));
+        self.platforms = enabled;
+        (self.disabled).extend(disabled.iter().map(|platform| platform.name().to_string()));
         self
     }
 
@@ -105,79 +641,818 @@ impl GpuDetectionBuilder {
         self
     }
 
+    /// Queries will include volatile device state (fan speeds, utilization etc.).
+    pub fn dynamic_props(mut self) -> Self {
+        self.dynamic = true;
+        self
+    }
+
+    /// Detection results will include host environment (OS, kernel, virtualization).
+    pub fn host_info(mut self) -> Self {
+        self.host_info = true;
+        self
+    }
+
+    /// Sets rules for grouping identical cards in [`GpuDetection::detect`].
+    pub fn aggregation(mut self, policy: AggregationPolicy) -> Self {
+        self.aggregation = policy;
+        self
+    }
+
+    /// Restricts detection and searches to devices with given uuids,
+    /// like `CUDA_VISIBLE_DEVICES` does.
+    pub fn visible_devices(mut self, uuids: &[&str]) -> Self {
+        self.visible = Some(uuids.iter().map(|uuid| uuid.to_string()).collect());
+        self
+    }
+
+    /// Hides device with given uuid from detection and searches
+    /// (e.g. the GPU driving the desktop).
+    pub fn exclude_device(mut self, uuid: &str) -> Self {
+        self.excluded.insert(uuid.to_string());
+        self
+    }
+
+    /// Loads NVIDIA Management Library from given path
+    /// instead of the default library search.
+    ///
+    /// When not set, `GOLEM_GPU_NVML_LIB` environment variable is used.
+    pub fn nvml_lib_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.nvml_lib_path = Some(path.into());
+        self
+    }
+
+    /// Loads ROCm SMI (`librocm_smi64.so`) from given path
+    /// instead of the default library search.
+    ///
+    /// When not set, `GOLEM_GPU_ROCM_LIB` environment variable is used.
+    pub fn rocm_lib_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rocm_lib_path = Some(path.into());
+        self
+    }
+
+    /// Reads Linux sysfs from given root instead of `/sys`
+    /// (e.g. host `/sys` mounted read-only elsewhere in a container).
+    ///
+    /// Only reads of this crate are redirected, ROCm SMI itself ignores it and
+    /// always reads `/sys`.
+    pub fn sysfs_root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sysfs_root = path.into();
+        self
+    }
+
+    /// Gives up on a platform whose initialization takes longer than `timeout`.
+    ///
+    /// Such platform is skipped, or results in [`GpuDetectionError::Timeout`] if forced.
+    pub fn init_timeout(mut self, timeout: Duration) -> Self {
+        self.init_timeout = Some(timeout);
+        self
+    }
+
+    /// Gives up on a platform whose query takes longer than `timeout`
+    /// (e.g. GPU fell off the bus).
+    ///
+    /// Detection results in [`GpuDetectionError::Timeout`], unless the platform is
+    /// skipped under [`DetectionPolicy::Lenient`]. [`GpuDetection::detect_report`]
+    /// lists the other platforms' devices either way.
+    pub fn query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
+    /// Fills properties drivers can not report (peak memory bandwidth, default power limit)
+    /// of known card models from a built-in spec table.
+    ///
+    /// Filled properties are listed in [`Device::enriched`](model::Device::enriched).
+    pub fn enrich_specs(mut self) -> Self {
+        self.enrich_specs = true;
+        self
+    }
+
+    /// Devices will include normalized model name (`model.normalized` offer property),
+    /// see [`model::normalize_name`].
+    pub fn normalized_names(mut self) -> Self {
+        self.normalized_names = true;
+        self
+    }
+
+    /// Wakes runtime suspended NVIDIA cards (`power/control` set to `on`) while they are
+    /// listed, so that dormant discrete GPUs of hybrid laptops report their properties.
+    ///
+    /// Linux only and requires root, automatic power management is restored afterwards.
+    pub fn runtime_resume(mut self) -> Self {
+        self.runtime_resume = true;
+        self
+    }
+
+    /// Reads properties of up to `threads` cards at once, one at a time by default.
+    ///
+    /// Each card takes dozens of driver calls, rigs with many cards are listed faster
+    /// in parallel. Used by the `cuda` platform, NVML is thread-safe.
+    pub fn enumeration_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Sets how detection handles failing devices and platforms,
+    /// [`DetectionPolicy::Strict`] by default.
+    pub fn policy(mut self, policy: DetectionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Defers backend initialization from [`init`](Self::init) to the first query,
+    /// so that applications can construct [`GpuDetection`] at startup and load drivers
+    /// only when GPU info is requested.
+    ///
+    /// Initialization errors (e.g. of forced platforms) are returned by queries,
+    /// which retry the initialization until it succeeds, at most every 10 seconds.
+    /// Queries wait for the initialization at most for the
+    /// [`init_timeout`](Self::init_timeout).
+    pub fn lazy(mut self) -> Self {
+        self.lazy = true;
+        self
+    }
+
     /// Initializes backends.
-    pub fn init(mut self) -> Result<GpuDetection> {
-        let detections = self
+    ///
+    /// Platforms are probed concurrently, so a slow one does not delay the others.
+    ///
+    /// When `GOLEM_GPU_FAKE` environment variable points to a JSON file with detection
+    /// output (`Gpu`), all platforms are replaced with a `fake` backend serving its devices
+    /// and forced platforms are not required. The file is read once per process.
+    pub fn init(self) -> Result<GpuDetection> {
+        let backends = if self.lazy {
+            BackendsState::Pending
+        } else {
+            BackendsState::Ready(Arc::new(self.clone().init_backends()?))
+        };
+        Ok(GpuDetection {
+            backends: Arc::new(Mutex::new(backends)),
+            query_timeout: self.query_timeout,
+            host_info: self.host_info,
+            aggregation: self.aggregation.clone(),
+            policy: self.policy,
+            visible: self.visible.clone(),
+            excluded: self.excluded.clone(),
+            builder: self,
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn init_backends(mut self) -> Result<Backends> {
+        #[cfg(feature = "deserialize")]
+        if let Some(e) = self.replayed_init_error.take() {
+            return Err(e.into());
+        }
+        #[cfg(feature = "deserialize")]
+        if let Some(path) = std::env::var_os("GOLEM_GPU_FAKE") {
+            debug!(path = ?path, "serving fake devices");
+            let platform = capture::ReplayPlatform::fake_cached(std::path::Path::new(&path))?;
+            self.platforms = vec![platform];
+            self.force.clear();
+            self.disabled.clear();
+        }
+        let nvml_lib_path = self
+            .nvml_lib_path
+            .or_else(|| std::env::var_os("GOLEM_GPU_NVML_LIB").map(PathBuf::from));
+        let rocm_lib_path = self
+            .rocm_lib_path
+            .or_else(|| std::env::var_os("GOLEM_GPU_ROCM_LIB").map(PathBuf::from));
+        let probes: Vec<_> = self
             .platforms
-            .into_iter()
-            .filter_map(|platform| {
+            .iter()
+            .map(|platform| {
                 let force = self.force.remove(platform.name());
-                match platform.init(Flags {
+                let flags = Flags {
                     unstable: self.unstable,
+                    dynamic: self.dynamic,
                     force,
-                }) {
-                    Ok(v) => Some(Ok(v)),
-                    Err(e) if force => Some(Err(e)),
-                    // skip error if not forced.
-                    _ => None,
-                }
+                    nvml_lib_path: nvml_lib_path.clone(),
+                    rocm_lib_path: rocm_lib_path.clone(),
+                    sysfs_root: self.sysfs_root.clone(),
+                    enrich_specs: self.enrich_specs,
+                    normalized_names: self.normalized_names,
+                    runtime_resume: self.runtime_resume,
+                    lenient: self.policy == DetectionPolicy::Lenient,
+                    threads: self.threads,
+                };
+                (Arc::clone(platform), flags)
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect();
+        // slow or hung platform must not delay the others, each one has its own timeout.
+        let init_timeout = self.init_timeout;
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = probes
+                .into_iter()
+                .map(|(platform, flags)| {
+                    let force = flags.force;
+                    debug!(backend = platform.name(), force, "initializing backend");
+                    let probed = Arc::clone(&platform);
+                    let handle =
+                        scope.spawn(move || timeout::run(init_timeout, move || probed.init(flags)));
+                    (platform, force, handle)
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|(platform, force, handle)| {
+                    let result = handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                    (platform, force, result)
+                })
+                .collect()
+        });
+
+        let mut backends = Backends::default();
+        for (platform, force, result) in results {
+            match result {
+                Ok(detection) => {
+                    debug!(backend = platform.name(), version = ?detection.version(), "backend ready");
+                    backends.info.push(BackendInfo {
+                        name: platform.name().to_string(),
+                        version: detection.version(),
+                        status: BackendStatus::Ready,
+                    });
+                    backends.list.push(Backend {
+                        name: platform.name().to_string(),
+                        forced: force,
+                        detection,
+                    });
+                }
+                Err(e) if force => return Err(e),
+                // skip error if not forced.
+                Err(e) => {
+                    debug!(backend = platform.name(), reason = %e, "backend skipped");
+                    backends
+                        .skipped
+                        .extend(NotFoundReason::from_error(platform.name(), &e));
+                    backends.info.push(BackendInfo {
+                        name: platform.name().to_string(),
+                        version: None,
+                        status: BackendStatus::Skipped {
+                            reason: e.to_string(),
+                        },
+                    })
+                }
+            }
+        }
+
+        backends
+            .info
+            .extend(self.disabled.into_iter().map(|name| BackendInfo {
+                name,
+                version: None,
+                status: BackendStatus::Disabled,
+            }));
 
         if !self.force.is_empty() {
-            return Err(GpuDetectionError::GpuAccessError(format!(
-                "missing forced platforms: {:?}",
-                self.force
-            )));
+            // a misconfiguration, retrying does not help.
+            let reasons = self
+                .force
+                .into_iter()
+                .map(|backend| NotFoundReason {
+                    backend,
+                    code: ErrorKind::NotFound,
+                    reason: "forced platform is unknown or not compiled in".into(),
+                    hint: None,
+                })
+                .collect();
+            return Err(GpuDetectionError::NotFound { reasons });
+        }
+        Ok(backends)
+    }
+
+    /// Initializes backends, retrying up to `attempts` times while it fails or no backend comes up.
+    ///
+    /// Waits `backoff` before the second attempt and doubles the delay after each one.
+    /// Useful right after boot or driver reload, when NVML init can fail transiently.
+    /// Gives up early on errors retrying does not fix, e.g. no driver installed.
+    pub fn init_with_retry(self, attempts: u32, backoff: Duration) -> Result<GpuDetection> {
+        let mut delay = backoff;
+        for _ in 1..attempts {
+            let transient = match self.clone().init() {
+                Ok(detection) => match detection.ready() {
+                    Ok(backends) if !backends.list.is_empty() => return Ok(detection),
+                    Ok(backends) => backends
+                        .skipped
+                        .iter()
+                        .any(|reason| reason.code.is_transient()),
+                    Err(e) => e.kind().is_transient(),
+                },
+                Err(e) => e.kind().is_transient(),
+            };
+            if !transient {
+                break;
+            }
+            debug!(?delay, "no backend initialized, retrying");
+            std::thread::sleep(delay);
+            delay = delay.saturating_mul(2);
         }
-        Ok(GpuDetection { detections })
+        self.init()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl GpuDetectionBuilder {
+    /// Initializes backends on the tokio blocking pool.
+    pub async fn init_async(self) -> Result<GpuDetection> {
+        tokio::task::spawn_blocking(move || self.init())
+            .await
+            .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl GpuDetection {
+    /// Detects all available GPUs on the tokio blocking pool.
+    pub async fn detect_async(&self) -> Result<Gpu> {
+        let detection = self.clone();
+        tokio::task::spawn_blocking(move || detection.detect())
+            .await
+            .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?
     }
 }
 
 impl GpuDetection {
     /// Detects all available GPUs..
     pub fn detect(&self) -> Result<Gpu> {
-        let mut api = Default::default();
-        let mut devices = Vec::new();
+        self.detect_filtered(&DeviceFilter::new())
+    }
+
+    /// Re-initializes backends with the original configuration, so newly
+    /// installed drivers and re-seated cards are picked up.
+    ///
+    /// On error the current backends are kept. Existing clones, monitors
+    /// and watchers keep using the old backends.
+    pub fn refresh(&mut self) -> Result<()> {
+        *self = self.builder.clone().init()?;
+        Ok(())
+    }
+
+    /// Lists probed platforms with their initialization status.
+    ///
+    /// Initializes backends of lazy detection, lists nothing if that fails.
+    pub fn backends(&self) -> Vec<BackendInfo> {
+        self.ready()
+            .map(|backends| backends.info.clone())
+            .unwrap_or_default()
+    }
 
-        for detector in &self.detections {
-            detector.detect_api(&mut api)?;
-
-            let mut it = detector.devices()?.into_iter();
-            if let Some(mut dev) = it.next() {
-                for next_dev in it {
-                    //while let Some(next_dev) = it.next() {
-                    if next_dev.model == dev.model
-                        && next_dev.cuda == dev.cuda
-                        && next_dev.clocks == dev.clocks
-                        && next_dev.memory == dev.memory
-                    {
-                        dev.quantity += 1;
-                    } else {
-                        devices.push(mem::replace(&mut dev, next_dev));
+    // Initialized backends, initializing them on first use in lazy mode.
+    pub(crate) fn ready(&self) -> Result<Arc<Backends>> {
+        // concurrent first queries wait for a single initialization.
+        let mut state = self.backends.lock().unwrap();
+        match &*state {
+            BackendsState::Ready(backends) => return Ok(backends.clone()),
+            // queries must not hang on a failing driver one after another.
+            BackendsState::Failed { at, kind, message } if at.elapsed() < LAZY_INIT_BACKOFF => {
+                return Err(GpuDetectionError::Replayed {
+                    kind: *kind,
+                    message: message.clone(),
+                })
+            }
+            _ => (),
+        }
+        let builder = self.builder.clone();
+        let init_timeout = builder.init_timeout;
+        match timeout::run(init_timeout, move || builder.init_backends().map(Arc::new)) {
+            Ok(ready) => {
+                *state = BackendsState::Ready(ready.clone());
+                Ok(ready)
+            }
+            Err(e) => {
+                *state = BackendsState::Failed {
+                    at: Instant::now(),
+                    kind: e.kind(),
+                    message: e.to_string(),
+                };
+                Err(e)
+            }
+        }
+    }
+
+    /// Detects available GPUs meeting `filter` thresholds.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn detect_filtered(&self, filter: &DeviceFilter) -> Result<Gpu> {
+        let backends = self.ready()?;
+        let (api, results) = self.detect_backends(&backends);
+        let mut by_backend = Vec::new();
+        for result in results {
+            let backend = &backends.list[result.idx];
+            let detected = match (result.devices, result.api_error) {
+                (Ok(detected), None) => detected,
+                (Err(e), _) | (Ok(_), Some(e)) => {
+                    if !self.is_skippable(backend) {
+                        return Err(e);
                     }
+                    warn!(backend = backend.name, error = %e, "backend failed, skipped");
+                    continue;
                 }
-                devices.push(dev);
+            };
+            by_backend.push(self.collect_devices(backend, detected)?);
+        }
+        let devices = self.aggregate_backends(by_backend, filter);
+
+        let host = self.host_info.then(|| host::detect(&devices));
+
+        Ok(Gpu {
+            schema_version: model::SCHEMA_VERSION,
+            api,
+            devices,
+            host,
+        })
+    }
+
+    /// Detects available GPUs, skipping devices and platforms which fail.
+    ///
+    /// Unlike [`detect`](Self::detect) never fails, problems are listed in the report.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn detect_report(&self) -> DetectionReport {
+        let mut by_backend = Vec::new();
+        let mut warnings = Vec::new();
+        let mut failed_backends = Vec::new();
+        let warning = |backend: &Backend, index, e: GpuDetectionError| DetectionWarning {
+            backend: backend.name.to_string(),
+            index,
+            code: e.kind(),
+            message: e.to_string(),
+        };
+        let backends = match self.ready() {
+            Ok(backends) => backends,
+            Err(e) => {
+                warnings.push(DetectionWarning {
+                    backend: String::new(),
+                    index: None,
+                    code: e.kind(),
+                    message: e.to_string(),
+                });
+                Arc::default()
             }
+        };
+
+        let (api, results) = self.detect_backends(&backends);
+        for result in results {
+            let backend = &backends.list[result.idx];
+            let detected = match result.devices {
+                Ok(detected) => detected,
+                Err(e) => {
+                    warnings.push(warning(backend, None, e));
+                    failed_backends.push(backend.name.to_string());
+                    continue;
+                }
+            };
+            if let Some(e) = result.api_error {
+                warnings.push(warning(backend, None, e));
+            }
+            let mut backend_devices = Vec::new();
+            for (index, dev) in (0..).zip(detected) {
+                match dev {
+                    Ok(dev) => {
+                        if !dev.status.is_available() {
+                            warnings.push(warning(
+                                backend,
+                                Some(index),
+                                GpuDetectionError::DeviceLost,
+                            ));
+                        }
+                        if !dev.unsupported.is_empty() {
+                            warnings.push(DetectionWarning {
+                                backend: backend.name.to_string(),
+                                index: Some(index),
+                                code: ErrorKind::NotSupported,
+                                message: format!(
+                                    "not reported by driver: {}",
+                                    dev.unsupported.join(", ")
+                                ),
+                            });
+                        }
+                        backend_devices.push(dev);
+                    }
+                    Err(e) => warnings.push(warning(backend, Some(index), e)),
+                }
+            }
+            by_backend.push(backend_devices);
         }
+        let devices = self.aggregate_backends(by_backend, &DeviceFilter::new());
+
+        let host = self.host_info.then(|| host::detect(&devices));
 
-        Ok(Gpu { api, devices })
+        DetectionReport {
+            gpu: Gpu {
+                schema_version: model::SCHEMA_VERSION,
+                api,
+                devices,
+                host,
+            },
+            warnings,
+            failed_backends,
+        }
+    }
+
+    // Queries every backend in turn, keeping failures for the caller to judge.
+    //
+    // API info is merged from backends which report it.
+    fn detect_backends(&self, backends: &Backends) -> (GpuApiInfo, Vec<BackendDetection>) {
+        let mut api = GpuApiInfo::default();
+        let mut results = Vec::new();
+        for idx in 0..backends.list.len() {
+            let mut backend_api = api.clone();
+            let detected = self.call(idx, move |detection| {
+                let api_result = detection.detect_api(&mut backend_api);
+                Ok((backend_api, api_result, detection.devices_partial()?))
+            });
+            results.push(match detected {
+                Ok((backend_api, api_result, detected)) => BackendDetection {
+                    idx,
+                    api_error: match api_result {
+                        Ok(()) => {
+                            api = backend_api;
+                            None
+                        }
+                        Err(e) => Some(e),
+                    },
+                    devices: Ok(detected),
+                },
+                Err(e) => BackendDetection {
+                    idx,
+                    api_error: None,
+                    devices: Err(e),
+                },
+            });
+        }
+        (api, results)
+    }
+
+    /// Detects all available GPUs without aggregation.
+    ///
+    /// Returns one entry per physical card, see [`aggregate`] to group them.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn detect_all(&self) -> Result<Vec<Device>> {
+        let mut by_backend = Vec::new();
+        for (idx, backend) in self.ready()?.list.iter().enumerate() {
+            match self.call(idx, |detection| detection.devices_partial()) {
+                Ok(detected) => by_backend.push(self.collect_devices(backend, detected)?),
+                Err(e) => {
+                    if !self.is_skippable(backend) {
+                        return Err(e);
+                    }
+                    warn!(backend = backend.name, error = %e, "backend failed, skipped");
+                }
+            }
+        }
+        aggregation::dedup_by_bus_id(&mut by_backend);
+        let mut devices: Vec<Device> = by_backend.into_iter().flatten().collect();
+        devices.retain(|dev| self.is_visible(dev));
+        devices.sort_by(Device::location_cmp);
+        Ok(devices)
+    }
+
+    // Whether detection goes on without a failing backend.
+    fn is_skippable(&self, backend: &Backend) -> bool {
+        !backend.forced && self.policy == DetectionPolicy::Lenient
+    }
+
+    // Drops failing devices under lenient policy, fails on them otherwise.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn collect_devices(
+        &self,
+        backend: &Backend,
+        detected: Vec<Result<Device>>,
+    ) -> Result<Vec<Device>> {
+        let mut devices = Vec::new();
+        for dev in detected {
+            match (dev, self.policy) {
+                (Ok(dev), _) => devices.push(dev),
+                (Err(e), DetectionPolicy::Strict) => return Err(e),
+                (Err(e), DetectionPolicy::Lenient) => {
+                    warn!(backend = backend.name, error = %e, "device failed, skipped");
+                }
+            }
+        }
+        Ok(devices)
+    }
+
+    // Merges cards listed by several backends, then groups cards of each backend.
+    fn aggregate_backends(
+        &self,
+        mut by_backend: Vec<Vec<Device>>,
+        filter: &DeviceFilter,
+    ) -> Vec<Device> {
+        aggregation::dedup_by_bus_id(&mut by_backend);
+        by_backend
+            .into_iter()
+            .flat_map(|mut detected| {
+                detected.retain(|dev| self.is_visible(dev) && filter.matches(dev));
+                detected.sort_by(Device::location_cmp);
+                self.aggregation.aggregate(detected)
+            })
+            .collect()
+    }
+
+    // Runs query on backend `idx` with configured timeout.
+    fn call<T, F>(&self, idx: usize, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn Detection) -> Result<T> + Send + 'static,
+    {
+        let backends = self.ready()?;
+        timeout::run(self.query_timeout, move || {
+            f(backends.list[idx].detection.as_ref())
+        })
+    }
+
+    fn is_visible(&self, dev: &Device) -> bool {
+        if dev.uuids.iter().any(|uuid| self.excluded.contains(uuid)) {
+            return false;
+        }
+        match &self.visible {
+            Some(visible) => dev.uuids.iter().any(|uuid| visible.contains(uuid)),
+            None => true,
+        }
+    }
+
+    fn is_visible_uuid(&self, uuid: &str) -> bool {
+        if self.excluded.contains(uuid) {
+            return false;
+        }
+        match &self.visible {
+            Some(visible) => visible.contains(uuid),
+            None => true,
+        }
+    }
+
+    /// Samples live state (utilization, temperature, power draw etc.) of device with given uuid.
+    ///
+    /// Cheaper than [`detect`](Self::detect), meant to be polled while a task is running.
+    pub fn telemetry(&self, uuid: &str) -> Result<Telemetry> {
+        if !self.is_visible_uuid(uuid) {
+            return Err(GpuDetectionError::not_found());
+        }
+        let uuid = uuid.to_string();
+        self.search(move |detection| detection.telemetry(&uuid), |_| true)
+    }
+
+    /// Checks if device with given uuid is in use and should not be allocated
+    /// (e.g. the owner is gaming on it).
+    ///
+    /// Device is busy when compute is prohibited, any process uses it, or its utilization
+    /// is at least [`BUSY_UTILIZATION_PCT`] (processes in other containers may not be listed).
+    /// Inputs the platform does not support are skipped, fails only when none is supported.
+    pub fn is_busy(&self, uuid: &str) -> Result<bool> {
+        let telemetry = match self.telemetry(uuid) {
+            Err(e) if e.kind() == ErrorKind::NotSupported => None,
+            telemetry => Some(telemetry?),
+        };
+        if let Some(telemetry) = &telemetry {
+            if telemetry.compute_mode == Some(model::ComputeMode::Prohibited) {
+                return Ok(true);
+            }
+        }
+        let processes = match self.processes(uuid) {
+            // without telemetry busy state is unknown.
+            Err(e) if e.kind() == ErrorKind::NotSupported && telemetry.is_some() => None,
+            processes => Some(processes?),
+        };
+        if processes.is_some_and(|processes| !processes.is_empty()) {
+            return Ok(true);
+        }
+        Ok(telemetry
+            .and_then(|telemetry| telemetry.utilization)
+            .is_some_and(|utilization| utilization.gpu_pct >= BUSY_UTILIZATION_PCT))
+    }
+
+    /// Watches for added and removed devices and driver reloads, polling every `interval`.
+    ///
+    /// Long-running agents should rebuild offers on every event.
+    pub fn watch(&self, interval: Duration) -> Result<watch::Watcher> {
+        watch::Watcher::start(self, interval)
+    }
+
+    /// Lists processes using device with given uuid.
+    ///
+    /// Lets providers verify the card is idle before accepting a job.
+    pub fn processes(&self, uuid: &str) -> Result<Vec<GpuProcess>> {
+        if !self.is_visible_uuid(uuid) {
+            return Err(GpuDetectionError::not_found());
+        }
+        let uuid = uuid.to_string();
+        self.search(move |detection| detection.processes(&uuid), |_| true)
+    }
+
+    /// Streams critical driver errors (XID, ECC) and clock changes of all devices.
+    ///
+    /// Lets operators react to failures like XID 79 ("GPU has fallen off the bus")
+    /// before they surface as failed tasks.
+    pub fn watch_errors(&self) -> Result<watch::ErrorWatcher> {
+        watch::ErrorWatcher::start(self)
+    }
+
+    /// Checks health of all visible devices.
+    ///
+    /// Unlike [`detect`](Self::detect), failing or hanging backends are reported as errors.
+    pub fn health_check(&self) -> Result<Vec<HealthReport>> {
+        let mut reports = Vec::new();
+        for idx in 0..self.ready()?.list.len() {
+            reports.extend(self.call(idx, |detection| detection.health_check())?);
+        }
+        // lost devices may not report uuid, those are kept.
+        reports.retain(|report| match &report.uuid {
+            Some(uuid) => self.is_visible_uuid(uuid),
+            None => true,
+        });
+        Ok(reports)
+    }
+
+    /// Checks installed drivers and runtimes of initialized platforms against the oldest
+    /// supported versions (e.g. [`cuda::MIN_SUPPORTED_DRIVER`]).
+    ///
+    /// Detection on older drivers may fail later with errors about missing functions,
+    /// this reports [`GpuDetectionError::UnsupportedDriver`] up front instead.
+    pub fn check_environment(&self) -> Result<()> {
+        for idx in 0..self.ready()?.list.len() {
+            self.call(idx, |detection| detection.check_environment())?;
+        }
+        Ok(())
     }
 
     /// Finds single device by uuid.
     pub fn search_by_uuid(&self, uuid: &str) -> Result<Device> {
+        let uuid = uuid.to_string();
+        self.search(
+            move |detection| detection.device_by_uuid(&uuid),
+            |dev| self.is_visible(dev),
+        )
+    }
+
+    /// Finds single device by PCI bus id (e.g. `0000:01:00.0`).
+    pub fn search_by_pci_bus_id(&self, bus_id: &str) -> Result<Device> {
+        let bus_id = pci::normalize_bus_id(bus_id);
+        self.search(
+            move |detection| detection.device_by_pci_bus_id(&bus_id),
+            |dev| self.is_visible(dev),
+        )
+    }
+
+    /// Finds all devices whose model matches a case-insensitive glob pattern (e.g. `*RTX 40*`).
+    ///
+    /// Returns one entry per physical card.
+    pub fn search_by_model(&self, pattern: &str) -> Result<Vec<Device>> {
+        Ok(self
+            .detect_all()?
+            .into_iter()
+            .filter(|dev| glob::matches(pattern, &dev.model))
+            .collect())
+    }
+
+    // Returns first accepted result of `f` among backends.
+    fn search<T, F>(&self, f: F, accept: impl Fn(&T) -> bool) -> Result<T>
+    where
+        T: Send + 'static,
+        F: Fn(&dyn Detection) -> Result<Option<T>> + Clone + Send + 'static,
+    {
         let mut last_err = None;
-        for detector in &self.detections {
-            match detector.device_by_uuid(uuid) {
-                Ok(Some(device)) => return Ok(device),
+        let backends = self.ready()?;
+        for idx in 0..backends.list.len() {
+            match self.call(idx, f.clone()) {
+                Ok(Some(found)) if accept(&found) => return Ok(found),
                 Err(e) => {
                     last_err = Some(e);
                 }
                 _ => (),
             }
         }
-        Err(last_err.unwrap_or(GpuDetectionError::NotFound))
+        // skipped platforms may explain the missing device.
+        Err(last_err.unwrap_or_else(|| GpuDetectionError::NotFound {
+            reasons: backends.skipped.clone(),
+        }))
     }
+
+    /// Finds single device by backend native index (e.g. `("cuda", 0)`).
+    pub fn search_by_index(&self, platform: &str, index: u32) -> Result<Device> {
+        let backends = self.ready()?;
+        let idx = backends
+            .list
+            .iter()
+            .position(|backend| backend.name == platform)
+            .ok_or_else(|| GpuDetectionError::NotFound {
+                reasons: (backends.skipped.iter())
+                    .filter(|reason| reason.backend == platform)
+                    .cloned()
+                    .collect(),
+            })?;
+        self.call(idx, move |detection| detection.device_by_index(index))?
+            .filter(|dev| self.is_visible(dev))
+            .ok_or_else(GpuDetectionError::not_found)
+    }
+}
+
+/// Groups identical cards into a single [`Device`] with summed `quantity`.
+///
+/// Uses default [`AggregationPolicy`].
+pub fn aggregate(devices: impl IntoIterator<Item = Device>) -> Vec<Device> {
+    AggregationPolicy::default().aggregate(devices)
 }
 
 #[cfg(any(feature = "cuda", feature = "amd"))]
@@ -188,50 +1463,44 @@ fn bytes_to_gib(memory: u64) -> f32 {
 #[cfg(test)]
 mod test {
     use crate::model;
-    use crate::model::{Device, GpuApiInfo};
-    use crate::platform::{Detection, Flags, Platform};
+    use crate::model::Device;
+    use crate::testing::MockPlatform;
+    use std::time::Duration;
 
-    #[derive(Clone)]
-    struct TestPlatformDetection {
-        devices: Vec<Device>,
+    fn mock(devices: Vec<Device>) -> MockPlatform {
+        MockPlatform::new(devices)
+            .name("test")
+            .cuda(model::Cuda {
+                version: "12.2".into(),
+                driver_version: Some("535.146.02".parse().unwrap()),
+                driver_version_raw: None,
+            })
+            .telemetry(model::Telemetry {
+                temperature_c: Some(45),
+                ..Default::default()
+            })
+            .version("1.0")
     }
 
-    impl Platform for TestPlatformDetection {
-        fn name(&self) -> &str {
-            "test"
-        }
-
-        fn init(&self, _flags: Flags) -> crate::Result<Box<dyn Detection>> {
-            Ok(Box::new(self.clone()))
-        }
+    fn builder(devices: Vec<Device>) -> super::GpuDetectionBuilder {
+        builder_with(mock(devices))
     }
 
-    impl Detection for TestPlatformDetection {
-        fn detect_api(&self, api: &mut GpuApiInfo) -> crate::Result<()> {
-            api.cuda = model::Cuda {
-                version: "12.2".into(),
-                driver_version: Some("535.146.02".into()),
-            }
-            .into();
-            Ok(())
-        }
-
-        fn devices(&self) -> crate::Result<Vec<Device>> {
-            Ok(self.devices.clone())
-        }
-
-        fn device_by_uuid(&self, _uuid: &str) -> crate::Result<Option<Device>> {
-            Ok(None)
-        }
+    fn builder_with(platform: MockPlatform) -> super::GpuDetectionBuilder {
+        super::GpuDetectionBuilder::default()
+            .mock(platform)
+            .enable_only(&["test"])
     }
 
-    fn gen_rtx_3090() -> Device {
+    fn gen_rtx_3090(uuid: &str, index: u32) -> Device {
+        let bus_id = format!("0000:{:02x}:00.0", index + 1);
         Device {
             model: "NVIDIA GeForce RTX 3090".to_string(),
+            model_normalized: None,
             cuda: model::DeviceCuda {
                 enabled: true,
                 cores: 10496,
-                caps: "8.6".to_string(),
+                caps: model::ComputeCapability::new(8, 6),
             }
             .into(),
             clocks: model::DeviceClocks {
@@ -239,23 +1508,62 @@ mod test {
                 memory_mhz: 9751,
                 sm_mhz: 2100,
                 video_mhz: 1950.into(),
+                power_source: None,
             },
             memory: model::DeviceMemory {
                 bandwidth_gib: 936.into(),
+                measured_bandwidth_gib: None,
                 total_gib: 24.0,
             },
+            power: model::DevicePower {
+                min_limit_w: 100,
+                max_limit_w: 390,
+                limit_w: 350.into(),
+                default_limit_w: 350.into(),
+            }
+            .into(),
+            encoder: model::DeviceEncoder {
+                enabled: true,
+                sessions_max: 8.into(),
+            }
+            .into(),
+            persistence_mode: true.into(),
+            pci: model::DevicePci {
+                vendor_id: 0x10de,
+                device_id: 0x2204,
+                subsystem_vendor_id: 0x1043,
+                subsystem_device_id: 0x87b3,
+                partner: Some("ASUS".into()),
+            }
+            .into(),
+            kernel_driver: model::KernelDriver {
+                name: "nvidia".into(),
+                version: Some("535.146.02".into()),
+            }
+            .into(),
+            external: false.into(),
+            laptop: None,
+            driver_model: None,
+            mig: None,
+            status: model::DeviceStatus::Available,
             quantity: 1,
+            uuids: vec![uuid.to_string()],
+            indices: vec![index],
+            pci_bus_ids: vec![bus_id],
+            dynamic: None,
+            compute: None,
+            enriched: Vec::new(),
+            estimated: Vec::new(),
+            unsupported: Vec::new(),
         }
     }
 
     #[test]
     fn test_aggregation() {
-        let platform: Box<dyn Platform> = Box::new(TestPlatformDetection {
-            devices: vec![gen_rtx_3090(), gen_rtx_3090()],
-        });
-
-        let mut b = super::GpuDetectionBuilder::default();
-        b.platforms = vec![Box::leak(platform)];
+        let b = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ]);
         let gpu = b
             .init()
             .expect("failed to initialize")
@@ -265,7 +1573,705 @@ mod test {
         assert_eq!(gpu.devices.len(), 1);
         let dev = gpu.devices.first().unwrap();
         assert_eq!(dev.quantity, 2);
+        assert_eq!(
+            dev.uuids,
+            vec![
+                "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+                "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02"
+            ]
+        );
+        assert_eq!(dev.indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_detect_all() {
+        let b = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ]);
+        let devices = b
+            .init()
+            .expect("failed to initialize")
+            .detect_all()
+            .expect("mock detection");
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[1].indices, vec![1]);
+        assert_eq!(super::aggregate(devices).len(), 1);
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let mut other = gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a00", 0);
+        other.model = "NVIDIA GeForce RTX 4090".into();
+        let b = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 2),
+            other,
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 1),
+        ]);
+        let gpu = b
+            .init()
+            .expect("failed to initialize")
+            .detect()
+            .expect("mock detection");
+
+        assert_eq!(gpu.devices.len(), 2);
+        assert_eq!(gpu.devices[0].model, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpu.devices[1].indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_by_pci_bus_id() {
+        let b = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ]);
+        let detection = b.init().expect("failed to initialize");
+        let dev = detection
+            .search_by_pci_bus_id("00000000:02:00.0")
+            .expect("device");
+        assert_eq!(dev.indices, vec![1]);
+        assert!(detection.search_by_pci_bus_id("0000:03:00.0").is_err());
+    }
+
+    #[test]
+    fn test_visible_devices() {
+        let b = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ])
+        .visible_devices(&["GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02"]);
+        let detection = b.init().expect("failed to initialize");
+
+        let gpu = detection.detect().expect("mock detection");
+        assert_eq!(gpu.devices.len(), 1);
+        assert_eq!(gpu.devices[0].quantity, 1);
+        assert_eq!(gpu.devices[0].indices, vec![1]);
+        assert!(detection.search_by_index("test", 0).is_err());
+        assert!(detection.search_by_index("test", 1).is_ok());
+    }
+
+    #[test]
+    fn test_disable() {
+        let gpu = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .disable("test")
+        .init()
+        .expect("failed to initialize")
+        .detect()
+        .expect("mock detection");
+        assert!(gpu.devices.is_empty());
+    }
+
+    #[test]
+    fn test_force_missing_platform() {
+        let result = builder(vec![]).force("missing").init();
+        let Err(super::GpuDetectionError::NotFound { reasons }) = result else {
+            panic!("expected NotFound");
+        };
+        assert_eq!(reasons.len(), 1);
+        assert_eq!(reasons[0].backend, "missing");
+        assert_eq!(reasons[0].code, super::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_exclude_device() {
+        let detection = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ])
+        .exclude_device("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+        .init()
+        .expect("failed to initialize");
+
+        let devices = detection.detect_all().expect("mock detection");
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].indices, vec![1]);
+        assert!(detection.search_by_index("test", 0).is_err());
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let hanging = mock(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .delay(Duration::from_secs(5));
+        let builder = || builder_with(hanging.clone());
+
+        let detection = |policy| {
+            builder()
+                .policy(policy)
+                .query_timeout(Duration::from_millis(50))
+                .init()
+                .expect("failed to initialize")
+        };
+        assert!(matches!(
+            detection(super::DetectionPolicy::Strict).detect(),
+            Err(super::GpuDetectionError::Timeout(_))
+        ));
+        let gpu = detection(super::DetectionPolicy::Lenient)
+            .detect()
+            .expect("hung backend should be skipped");
+        assert!(gpu.devices.is_empty());
+        let report = detection(super::DetectionPolicy::Strict).detect_report();
+        assert_eq!(report.failed_backends, vec!["test".to_string()]);
+        assert_eq!(report.warnings[0].code, super::ErrorKind::Timeout);
+
+        let result = builder()
+            .force("test")
+            .query_timeout(Duration::from_millis(50))
+            .init()
+            .expect("failed to initialize")
+            .detect_all();
+        assert!(matches!(result, Err(super::GpuDetectionError::Timeout(_))));
+    }
+
+    #[test]
+    fn test_parallel_init() {
+        let slow = |name: &str, delay| mock(vec![]).name(name).init_delay(delay);
+        let start = std::time::Instant::now();
+        let detection = super::GpuDetectionBuilder::default()
+            .mock(slow("hung", Duration::from_secs(5)))
+            .mock(slow("slow", Duration::from_millis(200)))
+            .mock(slow("other", Duration::from_millis(200)))
+            .enable_only(&["hung", "slow", "other"])
+            .init_timeout(Duration::from_millis(400))
+            .init()
+            .expect("failed to initialize");
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let statuses: Vec<_> = detection
+            .backends()
+            .into_iter()
+            .filter(|info| info.status != super::BackendStatus::Disabled)
+            .map(|info| (info.name, info.status == super::BackendStatus::Ready))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("hung".into(), false),
+                ("slow".into(), true),
+                ("other".into(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lazy() {
+        let detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .lazy()
+        .init()
+        .expect("lazy init should not probe platforms");
+        assert!(matches!(
+            *detection.backends.lock().unwrap(),
+            super::BackendsState::Pending
+        ));
+        assert_eq!(detection.clone().detect().unwrap().devices.len(), 1);
+        assert!(matches!(
+            *detection.backends.lock().unwrap(),
+            super::BackendsState::Ready(_)
+        ));
+
+        let detection = builder(vec![])
+            .force("missing")
+            .lazy()
+            .init()
+            .expect("lazy init should not probe platforms");
+        assert_eq!(
+            detection.detect().err().map(|e| e.kind()),
+            Some(super::ErrorKind::NotFound)
+        );
+        assert!(detection.backends().is_empty());
+        assert_eq!(detection.detect_report().warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_lazy_init_timeout() {
+        let detection = super::GpuDetectionBuilder::default()
+            .mock(mock(vec![]).name("hung").init_delay(Duration::from_secs(5)))
+            .force("hung")
+            .init_timeout(Duration::from_millis(100))
+            .lazy()
+            .init()
+            .expect("lazy init should not probe platforms");
+        let start = std::time::Instant::now();
+        assert!(matches!(
+            detection.detect(),
+            Err(super::GpuDetectionError::Timeout(_))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // the failure is repeated without waiting again.
+        let start = std::time::Instant::now();
+        let err = detection.detect().unwrap_err();
+        assert!(matches!(err, super::GpuDetectionError::Replayed { .. }));
+        assert_eq!(err.kind(), super::ErrorKind::Timeout);
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_init_with_retry() {
+        let detection = builder(vec![])
+            .disable("test")
+            .init_with_retry(3, Duration::from_millis(1))
+            .expect("failed to initialize");
+        assert!(detection.ready().unwrap().list.is_empty());
+        let backends = detection.backends();
+        assert!(backends
+            .iter()
+            .all(|info| info.status == super::BackendStatus::Disabled));
+        assert!(backends.iter().any(|info| info.name == "test"));
+
+        // a missing forced platform is a misconfiguration and fails without waiting.
+        let start = std::time::Instant::now();
+        let result = builder(vec![])
+            .force("missing")
+            .init_with_retry(3, Duration::from_millis(100));
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        let failing = |error: fn() -> super::GpuDetectionError| {
+            let start = std::time::Instant::now();
+            builder_with(mock(vec![]).init_error(error))
+                .init_with_retry(3, Duration::from_millis(100))
+                .expect("failed to initialize");
+            start.elapsed()
+        };
+        // missing driver does not show up by waiting.
+        assert!(
+            failing(|| super::GpuDetectionError::KernelModuleNotLoaded {
+                module: "nvidia".into(),
+            }) < Duration::from_millis(100)
+        );
+        assert!(
+            failing(|| super::GpuDetectionError::Unknown("driver busy".into()))
+                >= Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn test_backends() {
+        let detection = builder(vec![]).init().expect("failed to initialize");
+        let backends = detection.backends();
+        assert_eq!(
+            backends[0],
+            super::BackendInfo {
+                name: "test".into(),
+                version: Some("1.0".into()),
+                status: super::BackendStatus::Ready,
+            }
+        );
+        // built-in platforms are left out by `enable_only`.
+        assert!(backends[1..]
+            .iter()
+            .all(|info| info.status == super::BackendStatus::Disabled));
+        #[cfg(feature = "cuda")]
+        assert!(backends.iter().any(|info| info.name == "cuda"));
+        assert!(detection.check_environment().is_ok());
+    }
+
+    #[test]
+    fn test_not_found_reasons() {
+        let detection = builder_with(mock(vec![]).init_error(|| {
+            super::GpuDetectionError::KernelModuleNotLoaded {
+                module: "nvidia".into(),
+            }
+        }))
+        .init()
+        .expect("failed to initialize");
+
+        let e = detection.search_by_index("test", 0).unwrap_err();
+        let super::GpuDetectionError::NotFound { reasons } = &e else {
+            panic!("unexpected error {e}");
+        };
+        assert_eq!(
+            reasons,
+            &[super::NotFoundReason {
+                backend: "test".into(),
+                code: super::ErrorKind::DriverNotFound,
+                reason: "nvidia kernel module not loaded".into(),
+                hint: Some(
+                    "load it with `sudo modprobe nvidia` or reinstall the GPU driver \
+                     for the running kernel"
+                        .into()
+                ),
+            }]
+        );
+        assert!(e
+            .to_string()
+            .starts_with("Driver not found: test: nvidia kernel module not loaded, load it"));
+        assert!(matches!(
+            detection.telemetry("GPU-missing"),
+            Err(super::GpuDetectionError::NotFound { reasons }) if reasons.len() == 1
+        ));
+        assert_eq!(
+            super::GpuDetectionError::not_found().to_string(),
+            "Driver not found"
+        );
+    }
+
+    #[test]
+    fn test_lost_device() {
+        let lost = Device {
+            model: "NVIDIA GeForce RTX 3090".into(),
+            status: model::DeviceStatus::Lost,
+            quantity: 1,
+            uuids: vec!["GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02".into()],
+            indices: vec![1],
+            ..Default::default()
+        };
+        let detection = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            lost,
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a03", 2),
+        ])
+        .init()
+        .expect("failed to initialize");
+
+        let gpu = detection.detect().unwrap();
+        assert_eq!(gpu.devices.len(), 2);
+        assert_eq!(gpu.devices[0].quantity, 2);
+        assert_eq!(gpu.devices[1].status, model::DeviceStatus::Lost);
+        assert_eq!(gpu.devices[1].to_string(), "RTX 3090 (lost)");
+
+        let report = detection.detect_report();
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].index, Some(1));
+    }
+
+    #[test]
+    fn test_detect_report() {
+        let report = builder_with(mock(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            Device {
+                status: model::DeviceStatus::Lost,
+                ..gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1)
+            },
+            Device {
+                unsupported: vec!["clock.video.mhz".into()],
+                ..gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a03", 2)
+            },
+        ]))
+        .init()
+        .expect("failed to initialize")
+        .detect_report();
+        // lost card is listed apart from the working ones.
+        let status = |dev: &Device| (dev.status, dev.quantity);
+        assert_eq!(
+            report.gpu.devices.iter().map(status).collect::<Vec<_>>(),
+            vec![
+                (model::DeviceStatus::Available, 2),
+                (model::DeviceStatus::Lost, 1)
+            ]
+        );
+        assert!(report.failed_backends.is_empty());
+        assert_eq!(
+            report.warnings,
+            vec![
+                super::DetectionWarning {
+                    backend: "test".into(),
+                    index: Some(1),
+                    code: super::ErrorKind::DeviceLost,
+                    message: "GPU has fallen off the bus or requires a reset".into(),
+                },
+                super::DetectionWarning {
+                    backend: "test".into(),
+                    index: Some(2),
+                    code: super::ErrorKind::NotSupported,
+                    message: "not reported by driver: clock.video.mhz".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        use super::DetectionPolicy;
+
+        let failing = mock(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ])
+        .device_error(1, || {
+            super::GpuDetectionError::GpuAccessError("GPU is lost".into())
+        });
+        let detection = |policy| {
+            builder_with(failing.clone())
+                .policy(policy)
+                .init()
+                .expect("failed to initialize")
+        };
+
+        assert!(detection(DetectionPolicy::Strict).detect().is_err());
+        assert!(detection(DetectionPolicy::Strict).detect_all().is_err());
+
+        let lenient = detection(DetectionPolicy::Lenient);
+        let gpu = lenient.detect().expect("failing device should be skipped");
+        assert_eq!(gpu.devices.len(), 1);
+        assert_eq!(gpu.devices[0].quantity, 1);
+        assert_eq!(lenient.detect_all().unwrap().len(), 1);
+
+        let result = builder_with(failing.clone().delay(Duration::from_secs(5)))
+            .force("test")
+            .policy(DetectionPolicy::Lenient)
+            .query_timeout(Duration::from_millis(50))
+            .init()
+            .expect("failed to initialize")
+            .detect();
+        assert!(matches!(result, Err(super::GpuDetectionError::Timeout(_))));
+    }
+
+    #[test]
+    fn test_error_kind() {
+        use super::{ErrorKind, GpuDetectionError};
+
+        assert_eq!(GpuDetectionError::not_found().kind(), ErrorKind::NotFound);
+        assert_eq!(
+            GpuDetectionError::Timeout(Duration::from_secs(1)).kind(),
+            ErrorKind::Timeout
+        );
+        let e = GpuDetectionError::PermissionDenied {
+            path: "/dev/kfd".into(),
+            groups: vec!["render".into(), "video".into()],
+        };
+        assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(e.code(), "NO_PERMISSION");
+        let missing = GpuDetectionError::DeviceNodeMissing {
+            path: "/dev/kfd".into(),
+        };
+        assert_eq!(missing.kind(), ErrorKind::DriverNotFound);
+        assert!(missing.to_string().starts_with("/dev/kfd not found"));
+        assert_eq!(
+            serde_json::to_value(e.kind()).unwrap(),
+            serde_json::json!("NO_PERMISSION")
+        );
+        assert_eq!(
+            e.to_string(),
+            "permission denied to /dev/kfd, add the user to the render and video groups \
+             (`sudo usermod -aG render,video $USER`) and log in again"
+        );
+        #[cfg(feature = "cuda")]
+        {
+            use nvml_wrapper::error::NvmlError;
+            let e = GpuDetectionError::from(NvmlError::FunctionNotFound);
+            assert_eq!(e.kind(), ErrorKind::DriverTooOld);
+            assert_eq!(e.kind().to_string(), "DRIVER_TOO_OLD");
+            assert!(std::error::Error::source(&e)
+                .and_then(|source| source.downcast_ref::<NvmlError>())
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn test_is_busy() {
+        let detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .init()
+        .expect("failed to initialize");
+        assert!(!detection
+            .is_busy("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+            .expect("mock telemetry"));
+        assert!(matches!(
+            detection.is_busy("GPU-00000000-0000-0000-0000-000000000000"),
+            Err(super::GpuDetectionError::NotFound { .. })
+        ));
+
+        // without process listing utilization decides.
+        let busy = |gpu_pct| {
+            let platform = mock(vec![gen_rtx_3090(
+                "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+                0,
+            )])
+            .telemetry(model::Telemetry {
+                utilization: Some(model::DeviceUtilization {
+                    gpu_pct,
+                    memory_pct: 0,
+                }),
+                ..Default::default()
+            })
+            .processes_error(|| super::GpuDetectionError::NotSupported("listing processes".into()));
+            builder_with(platform)
+                .init()
+                .expect("failed to initialize")
+                .is_busy("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+                .expect("busy from utilization")
+        };
+        assert!(busy(super::BUSY_UTILIZATION_PCT));
+        assert!(!busy(0));
+    }
+
+    #[test]
+    fn test_refresh() {
+        let mut detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .init()
+        .expect("failed to initialize");
+        let before = detection.ready().unwrap();
+        detection.refresh().expect("failed to refresh");
+        assert!(!std::sync::Arc::ptr_eq(
+            &before,
+            &detection.ready().unwrap()
+        ));
+        assert_eq!(detection.detect_all().expect("mock detection").len(), 1);
+    }
+
+    #[test]
+    fn test_health_check() {
+        let reports = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ])
+        .exclude_device("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+        .init()
+        .expect("failed to initialize")
+        .health_check()
+        .expect("mock health check");
+        assert_eq!(
+            reports,
+            vec![super::HealthReport {
+                uuid: Some("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02".into()),
+                index: 1,
+                status: super::HealthStatus::Healthy,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_telemetry() {
+        let detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .init()
+        .expect("failed to initialize");
+        let telemetry = detection
+            .telemetry("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+            .expect("mock telemetry");
+        assert_eq!(telemetry.temperature_c, Some(45));
+        assert!(matches!(
+            detection.telemetry("GPU-missing"),
+            Err(super::GpuDetectionError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_monitor() {
+        let detection = builder(vec![
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01", 0),
+            gen_rtx_3090("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02", 1),
+        ])
+        .init()
+        .expect("failed to initialize");
+        let mut monitor = crate::monitor::Monitor::start(&detection, Duration::from_millis(10))
+            .expect("failed to start monitor");
+        assert_eq!(monitor.uuids().count(), 2);
+
+        let frames = monitor
+            .take_receiver("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02")
+            .expect("monitored device");
+        for _ in 0..2 {
+            let frame = frames
+                .recv_timeout(Duration::from_secs(5))
+                .expect("telemetry frame");
+            assert_eq!(frame.uuid, "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02");
+            assert_eq!(
+                frame.telemetry.expect("mock telemetry").temperature_c,
+                Some(45)
+            );
+        }
+        assert_eq!(monitor.uuids().count(), 1);
+    }
+
+    #[test]
+    fn test_watch_errors() {
+        let mut watcher = builder_with(
+            mock(vec![gen_rtx_3090(
+                "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+                0,
+            )])
+            .error_events(vec![
+                // hidden device.
+                crate::watch::DeviceErrorEvent {
+                    uuid: Some("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02".into()),
+                    kind: crate::watch::DeviceErrorKind::DoubleBitEcc,
+                },
+                crate::watch::DeviceErrorEvent {
+                    uuid: Some("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01".into()),
+                    kind: crate::watch::DeviceErrorKind::Xid(Some(79)),
+                },
+            ]),
+        )
+        .exclude_device("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02")
+        .init()
+        .expect("failed to initialize")
+        .watch_errors()
+        .expect("failed to watch errors");
+        let event = watcher
+            .next()
+            .expect("error event")
+            .expect("mock error event");
+        assert_eq!(
+            event,
+            crate::watch::DeviceErrorEvent {
+                uuid: Some("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01".into()),
+                kind: crate::watch::DeviceErrorKind::Xid(Some(79)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_processes() {
+        let detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .exclude_device("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02")
+        .init()
+        .expect("failed to initialize");
+        let processes = detection
+            .processes("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01")
+            .expect("mock processes");
+        assert!(processes.is_empty());
+        assert!(detection
+            .processes("GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a02")
+            .is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_serve() {
+        use std::io::{Read, Write};
+
+        let detection = builder(vec![gen_rtx_3090(
+            "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01",
+            0,
+        )])
+        .init()
+        .expect("failed to initialize");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("local address");
+        std::thread::spawn(move || crate::http::serve_on(&detection, listener));
 
-        //eprintln!("{}", serde_json::to_string_pretty(&gpu).unwrap());
+        let get = |path: &str| {
+            let mut stream = std::net::TcpStream::connect(addr).expect("connect");
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("request");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).expect("response");
+            response
+        };
+        let response = get("/gpu/GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\"model\":\"NVIDIA GeForce RTX 3090\""));
+        assert!(get("/gpu/GPU-missing").starts_with("HTTP/1.1 404"));
+        assert!(get("/health").contains("\"status\":\"healthy\""));
+        assert!(get("/gpu").starts_with("HTTP/1.1 200 OK"));
     }
 }
@@ -3,22 +3,399 @@
 //! This module provides structures to define basic information about
 //! provider GPUs.
 
+use crate::aggregation::{self, AggregationPolicy};
+use crate::pci::normalize_bus_id;
 use serde::ser::SerializeMap;
+#[cfg(feature = "deserialize")]
+use serde::{Deserialize, Deserializer};
 use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+pub mod v1;
+
+/// Current offer format, `schema-version` 2.
+pub mod v2 {
+    pub use super::{Cuda, Device, Gpu, GpuApiInfo, Host};
+}
+
+/// Version of the serialized offer format produced by this crate.
+///
+/// Bumped when fields older consumers can not ignore are added or changed,
+/// see [`v1`] for the previous format.
+pub const SCHEMA_VERSION: u32 = 2;
 
 /// General information about all gpus.
-#[derive(Clone, Debug, Serialize, Default)]
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
 pub struct Gpu {
+    /// Offer format version, see [`SCHEMA_VERSION`].
+    ///
+    /// Documents without it are in [`v1`] format.
+    #[cfg_attr(feature = "deserialize", serde(default = "v1::schema_version"))]
+    #[cfg_attr(feature = "arbitrary", arbitrary(value = SCHEMA_VERSION))]
+    pub schema_version: u32,
     /// Available SDKs & device drivers.
     #[serde(flatten)]
     pub api: GpuApiInfo,
     /// Lists of devices.
     #[serde(flatten, serialize_with = "ser_devices")]
+    #[cfg_attr(feature = "deserialize", serde(deserialize_with = "de_devices"))]
     pub devices: Vec<Device>,
+    /// Host environment.
+    ///
+    /// Collected only when enabled with `GpuDetectionBuilder::host_info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<Host>,
+}
+
+impl Default for Gpu {
+    fn default() -> Self {
+        Gpu {
+            schema_version: SCHEMA_VERSION,
+            api: GpuApiInfo::default(),
+            devices: Vec::new(),
+            host: None,
+        }
+    }
+}
+
+impl Gpu {
+    /// Checks requirements against every device group.
+    ///
+    /// Returns one report per entry in `devices`.
+    pub fn satisfies(&self, requirements: &Requirements) -> Vec<MatchReport> {
+        let cuda_version = self.api.cuda.as_ref().map(|cuda| cuda.version.clone());
+        self.devices
+            .iter()
+            .map(|dev| {
+                let mut report = dev.satisfies(requirements);
+                if let Some(required) = &requirements.min_cuda_version {
+                    if !version_at_least(cuda_version.as_deref(), required) {
+                        report.unmet.push(Unmet::CudaVersion {
+                            required: required.clone(),
+                            available: cuda_version.clone(),
+                        });
+                    }
+                }
+                report
+            })
+            .collect()
+    }
+
+    /// Flattens into offer properties with dotted keys, e.g.
+    /// `to_offer_properties("golem.inf.gpu")` produces `golem.inf.gpu.d0.model`.
+    ///
+    /// Arrays are kept as single property values.
+    pub fn to_offer_properties(&self, prefix: &str) -> BTreeMap<String, Value> {
+        let mut properties = BTreeMap::new();
+        // serializing plain data structures into `Value` does not fail.
+        let value = serde_json::to_value(self).unwrap_or_default();
+        flatten_value(prefix.trim_end_matches('.'), value, &mut properties);
+        properties
+    }
+
+    /// Describes what changed between `self` and newer snapshot `other`.
+    ///
+    /// Cards are matched by uuid. Volatile state (`dynamic`) is not compared,
+    /// so an empty diff means the offer does not need to be republished.
+    pub fn diff(&self, other: &Gpu) -> GpuDiff {
+        let old_devices = devices_by_uuid(&self.devices);
+        let new_devices = devices_by_uuid(&other.devices);
+
+        let added = new_devices
+            .keys()
+            .filter(|uuid| !old_devices.contains_key(*uuid))
+            .map(|uuid| uuid.to_string())
+            .collect();
+        let removed = old_devices
+            .keys()
+            .filter(|uuid| !new_devices.contains_key(*uuid))
+            .map(|uuid| uuid.to_string())
+            .collect();
+        let devices = old_devices
+            .iter()
+            .filter_map(|(uuid, old)| {
+                let new = new_devices.get(uuid)?;
+                let changes = property_changes(&device_properties(old), &device_properties(new));
+                (!changes.is_empty()).then(|| DeviceDiff {
+                    uuid: uuid.to_string(),
+                    changes,
+                })
+            })
+            .collect();
+
+        GpuDiff {
+            added,
+            removed,
+            api: property_changes(&properties(&self.api), &properties(&other.api)),
+            host: property_changes(&properties(&self.host), &properties(&other.host)),
+            devices,
+        }
+    }
+
+    /// Combines detections from several hosts into one inventory.
+    ///
+    /// Identical device groups are merged with summed `quantity`, regardless
+    /// of their position. CUDA info is kept when all hosts report the same,
+    /// otherwise the lowest CUDA version is reported without driver version.
+    /// Host environment is dropped.
+    pub fn merge(gpus: impl IntoIterator<Item = Gpu>) -> Gpu {
+        let policy = AggregationPolicy::default();
+        let mut merged = Gpu::default();
+        for gpu in gpus {
+            merged.api.cuda = merge_cuda(merged.api.cuda.take(), gpu.api.cuda);
+            for dev in gpu.devices {
+                match merged
+                    .devices
+                    .iter_mut()
+                    .find(|group| policy.matches(group, &dev))
+                {
+                    Some(group) => aggregation::absorb(group, dev),
+                    None => merged.devices.push(dev),
+                }
+            }
+        }
+        merged
+    }
+}
+
+fn merge_cuda(a: Option<Cuda>, b: Option<Cuda>) -> Option<Cuda> {
+    match (a, b) {
+        (Some(a), Some(b)) if a.version == b.version => Some(Cuda {
+            driver_version: a
+                .driver_version
+                .filter(|v| b.driver_version.as_ref() == Some(v)),
+            driver_version_raw: a
+                .driver_version_raw
+                .filter(|v| b.driver_version_raw.as_ref() == Some(v)),
+            ..a
+        }),
+        (Some(a), Some(b)) => {
+            let lower = parse_version(&b.version)
+                .is_some_and(|b| parse_version(&a.version).is_none_or(|a| b < a));
+            let lowest = if lower { b } else { a };
+            Some(Cuda {
+                version: lowest.version,
+                driver_version: None,
+                driver_version_raw: None,
+            })
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+/// Difference between two detections, see [`Gpu::diff`].
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct GpuDiff {
+    /// Uuids of cards present only in the newer snapshot.
+    pub added: Vec<String>,
+    /// Uuids of cards missing from the newer snapshot.
+    pub removed: Vec<String>,
+    /// Changed SDK and driver properties (e.g. `cuda.driver.version`).
+    pub api: Vec<PropertyChange>,
+    /// Changed host properties (e.g. `kernel-version`).
+    pub host: Vec<PropertyChange>,
+    /// Changed properties of cards present in both snapshots.
+    pub devices: Vec<DeviceDiff>,
+}
+
+impl GpuDiff {
+    /// True if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.api.is_empty()
+            && self.host.is_empty()
+            && self.devices.is_empty()
+    }
+}
+
+/// Changed properties of a single card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceDiff {
+    /// Card uuid.
+    pub uuid: String,
+    /// Changed properties.
+    pub changes: Vec<PropertyChange>,
+}
+
+/// Single changed property, keyed like [`Gpu::to_offer_properties`] without prefix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyChange {
+    /// Dotted property key, e.g. `memory.total.gib`.
+    pub key: String,
+    /// Previous value, `None` if property was added.
+    pub old: Option<Value>,
+    /// New value, `None` if property was removed.
+    pub new: Option<Value>,
+}
+
+// Per card identity and volatile state, not compared by `Gpu::diff`.
+const DIFF_IGNORED: &[&str] = &["quantity", "uuids", "indices", "pci-bus-ids", "dynamic"];
+
+fn devices_by_uuid(devices: &[Device]) -> BTreeMap<&str, &Device> {
+    devices
+        .iter()
+        .flat_map(|dev| dev.uuids.iter().map(move |uuid| (uuid.as_str(), dev)))
+        .collect()
+}
+
+fn properties(value: &impl Serialize) -> BTreeMap<String, Value> {
+    let mut properties = BTreeMap::new();
+    match serde_json::to_value(value).unwrap_or_default() {
+        // e.g. host info not collected.
+        Value::Null => (),
+        value => flatten_value("", value, &mut properties),
+    }
+    properties
+}
+
+fn device_properties(device: &Device) -> BTreeMap<String, Value> {
+    let mut value = serde_json::to_value(device).unwrap_or_default();
+    if let Value::Object(object) = &mut value {
+        object.retain(|key, _| !DIFF_IGNORED.contains(&key.as_str()));
+    }
+    let mut properties = BTreeMap::new();
+    flatten_value("", value, &mut properties);
+    properties
+}
+
+fn property_changes(
+    old: &BTreeMap<String, Value>,
+    new: &BTreeMap<String, Value>,
+) -> Vec<PropertyChange> {
+    let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| PropertyChange {
+            key: key.clone(),
+            old: old.get(key).cloned(),
+            new: new.get(key).cloned(),
+        })
+        .collect()
+}
+
+fn flatten_value(key: &str, value: Value, properties: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(object) => {
+            for (name, value) in object {
+                let key = if key.is_empty() {
+                    name
+                } else {
+                    format!("{key}.{name}")
+                };
+                flatten_value(&key, value, properties);
+            }
+        }
+        value => {
+            properties.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Serializes the wrapped value with camelCase keys without dots,
+/// e.g. `clock.graphics.mhz` becomes `clock.graphicsMhz`.
+///
+/// For consumers which can not handle the default kebab-case dotted keys.
+/// Serialization only, the default format is the one accepted when deserializing.
+#[derive(Clone, Copy, Debug)]
+pub struct CamelCase<T>(pub T);
+
+impl<T: Serialize> Serialize for CamelCase<T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        serde_json::to_value(&self.0)
+            .map_err(serde::ser::Error::custom)
+            .map(camel_case_keys)?
+            .serialize(s)
+    }
+}
+
+fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => object
+            .into_iter()
+            .map(|(key, value)| (camel_case(&key), camel_case_keys(value)))
+            .collect(),
+        Value::Array(items) => items.into_iter().map(camel_case_keys).collect(),
+        value => value,
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut words = key.split(['.', '-', '_']).filter(|word| !word.is_empty());
+    let mut result = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        result.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        result.push_str(chars.as_str());
+    }
+    result
+}
+
+/// Host environment.
+#[derive(Clone, Debug, Serialize, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct Host {
+    /// Operating system, e.g. linux, windows.
+    pub os: String,
+    /// CPU architecture, e.g. x86_64.
+    pub arch: String,
+    /// Kernel release (Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel_version: Option<String>,
+    /// Hypervisor name if running in a virtual machine, e.g. kvm, hyper-v, wsl.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<String>,
+    /// Container engine if running in a container, e.g. docker, podman.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// NVIDIA Container Toolkit installation (Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_toolkit: Option<ContainerToolkit>,
+    /// Whether all NVIDIA cards are in persistence mode (Linux only), see
+    /// [`Device::persistence_mode`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistence_mode: Option<bool>,
+}
+
+/// NVIDIA Container Toolkit installation, needed to expose GPUs to containers.
+#[derive(Clone, Debug, Serialize, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct ContainerToolkit {
+    /// `nvidia-container-cli` is installed.
+    pub cli: bool,
+    /// `nvidia-container-runtime` is installed.
+    pub runtime: bool,
+    /// Docker daemon is configured with the NVIDIA runtime.
+    pub docker_runtime: bool,
+    /// CDI specifications describing NVIDIA devices.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cdi_specs: Vec<String>,
+}
+
+impl ContainerToolkit {
+    /// Should be true if containers can be given GPU access,
+    /// either through the docker runtime hook or CDI.
+    pub fn is_functional(&self) -> bool {
+        (self.cli && (self.runtime || self.docker_runtime)) || !self.cdi_specs.is_empty()
+    }
 }
 
 /// Available SDKs & device drivers.
-#[derive(Clone, Debug, Serialize, Default)]
+#[derive(Clone, Debug, Serialize, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 pub struct GpuApiInfo {
     /// Optional information about installed CUDA API & Drivers.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -26,25 +403,118 @@ pub struct GpuApiInfo {
 }
 
 /// information about installed CUDA.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 pub struct Cuda {
     /// CUDA version
     pub version: String,
     /// Installed driver version.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "driver.version")]
-    pub driver_version: Option<String>,
+    pub driver_version: Option<DriverVersion>,
+    /// Driver version as reported, when it is not in a format
+    /// [`driver_version`](Self::driver_version) can hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "driver.version.raw")]
+    pub driver_version_raw: Option<String>,
+}
+
+/// NVIDIA driver version, e.g. `535.161.07` on Linux or `555.85` on Windows.
+///
+/// Serialized as a string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DriverVersion {
+    /// Driver branch, e.g. `535`.
+    pub major: u32,
+    /// Release within the branch.
+    pub minor: u32,
+    /// Patch level, reported only by Linux drivers.
+    pub patch: Option<u32>,
+}
+
+impl DriverVersion {
+    /// Creates version without patch level, e.g. `DriverVersion::new(510, 0)`.
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch: None,
+        }
+    }
+}
+
+impl fmt::Display for DriverVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, ".{patch:02}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DriverVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.to_string());
+        let mut parts = s
+            .trim()
+            .split('.')
+            .map(|part| part.parse().map_err(|_| err()));
+        let major = parts.next().ok_or_else(err)??;
+        let minor = parts.next().ok_or_else(err)??;
+        let patch = parts.next().transpose()?;
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl Serialize for DriverVersion {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+#[cfg(feature = "deserialize")]
+impl<'de> Deserialize<'de> for DriverVersion {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        String::deserialize(d)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
 }
 
+/// Version string could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("invalid version: {0:?}")]
+pub struct ParseVersionError(String);
+
 /// GPU device group information.
 ///
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 #[serde(rename_all = "kebab-case")]
 pub struct Device {
     /// Name of this device.
     ///
     /// alphanumeric string that denotes a particular product, e.g. Tesla C2070
     pub model: String,
+    /// Model name without vendor and brand prefixes, see [`normalize_name`].
+    ///
+    /// Filled only when enabled with `GpuDetectionBuilder::normalized_names`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "model.normalized")]
+    pub model_normalized: Option<String>,
 
     /// CUDA specific attributes for this device
     pub cuda: Option<DeviceCuda>,
@@ -53,13 +523,348 @@ pub struct Device {
     pub clocks: DeviceClocks,
     /// Memory information.
     pub memory: DeviceMemory,
+    /// Power limits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power: Option<DevicePower>,
+    /// Hardware video encoder (nVidia NVENC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoder: Option<DeviceEncoder>,
+    /// Should be true if driver persistence mode is enabled (Linux only).
+    ///
+    /// Without persistence mode the driver is unloaded when no client is attached,
+    /// which makes first access to the device slow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persistence_mode: Option<bool>,
+    /// PCI identification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pci: Option<DevicePci>,
+    /// Kernel driver bound to the device (Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel_driver: Option<KernelDriver>,
+    /// Should be true if device is an external GPU (Thunderbolt / USB4 enclosure).
+    ///
+    /// External GPUs may be hot-removed and have limited host bandwidth.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+    /// Role of the device in a laptop, `None` on other computers or when unknown
+    /// (Linux only).
+    ///
+    /// Laptop cards run at lower power limits and throttle sooner than desktop cards
+    /// of the same model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub laptop: Option<LaptopGraphics>,
+    /// Windows driver model, `None` on other systems or when unknown (nVidia only).
+    ///
+    /// Compute runs slower and allocations are limited in WDDM mode, see
+    /// [`DeviceDriverModel::recommendation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver_model: Option<DeviceDriverModel>,
+    /// Multi-Instance GPU partitioning, set when the card is split into instances
+    /// (nVidia, Linux only).
+    ///
+    /// Cores and memory still describe the whole card, while a job gets a single instance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mig: Option<DeviceMig>,
+    /// Whether the device responds, lost devices are listed so that the others can still
+    /// be offered.
+    #[serde(default, skip_serializing_if = "DeviceStatus::is_available")]
+    pub status: DeviceStatus,
 
     /// Number of cards.
     pub quantity: usize,
+
+    /// Identifiers of the cards in this group.
+    ///
+    /// nVidia: device UUID (`GPU-...`)
+    /// AMD: unique id (as in rocm-smi, without `0x`), PCI id on cards older than Vega
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uuids: Vec<String>,
+
+    /// Backend native indices of the cards in this group, in the same order as `uuids`.
+    ///
+    /// nVidia: NVML index (as used by `CUDA_VISIBLE_DEVICES`)
+    /// AMD: ROCm SMI `dv_ind`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indices: Vec<u32>,
+
+    /// PCI bus ids (`0000:01:00.0`) of the cards in this group, in the same order as `uuids`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pci_bus_ids: Vec<String>,
+
+    /// Volatile device state.
+    ///
+    /// Collected only when enabled with `GpuDetectionBuilder::dynamic_props`.
+    /// For aggregated groups it describes the first card.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamic: Option<DeviceDynamic>,
+
+    /// Measured matrix multiplication throughput.
+    ///
+    /// Filled only by `bench::measure_compute` (`bench` feature).
+    /// For aggregated groups it describes the first card.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute: Option<DeviceCompute>,
+
+    /// Keys of properties filled from the built-in spec table instead of the driver
+    /// (e.g. `memory.bandwidth.gib`).
+    ///
+    /// Filled only when enabled with `GpuDetectionBuilder::enrich_specs`, except for
+    /// `cuda.cores` of older cards whose driver does not report it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enriched: Vec<String>,
+
+    /// Keys of properties computed from other driver reported values instead of read
+    /// from the driver (e.g. `memory.bandwidth.gib` from memory clock and bus width).
+    ///
+    /// Values from the spec table replace the estimates and are listed in `enriched` instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub estimated: Vec<String>,
+
+    /// Keys of properties the driver does not report for this card (e.g. `cuda.cores`),
+    /// left out or zero. Under `DetectionPolicy::Lenient` also property groups which
+    /// failed to read (e.g. `power`).
+    ///
+    /// Listed as warnings by `GpuDetection::detect_report`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsupported: Vec<String>,
 }
 
-/// CUDA specific attributes for single device
+impl Device {
+    /// Orders cards by PCI bus id, then uuid, for output independent of
+    /// enumeration order. Devices without bus id go last.
+    ///
+    /// Groups are compared by their first card.
+    pub fn location_cmp(&self, other: &Device) -> Ordering {
+        let key = |dev: &Device| {
+            let bus_id = dev.pci_bus_ids.first().map(|id| normalize_bus_id(id));
+            (bus_id.is_none(), bus_id, dev.uuids.first().cloned())
+        };
+        key(self).cmp(&key(other))
+    }
+
+    /// Checks device level requirements.
+    ///
+    /// CUDA version is a property of the installed driver,
+    /// use [`Gpu::satisfies`] to check it as well.
+    pub fn satisfies(&self, requirements: &Requirements) -> MatchReport {
+        let mut unmet = Vec::new();
+        if let Some(required_gib) = requirements.min_memory_gib {
+            if self.memory.total_gib < required_gib {
+                unmet.push(Unmet::Memory {
+                    required_gib,
+                    available_gib: self.memory.total_gib,
+                });
+            }
+        }
+        if let Some(required) = requirements.min_cuda_caps {
+            let available = self.cuda.as_ref().map(|cuda| cuda.caps);
+            if available.is_none_or(|available| available < required) {
+                unmet.push(Unmet::ComputeCapability {
+                    required,
+                    available,
+                });
+            }
+        }
+        if let Some(required) = requirements.vendor {
+            let available = self.vendor();
+            if available != Some(required) {
+                unmet.push(Unmet::Vendor {
+                    required,
+                    available,
+                });
+            }
+        }
+        if requirements.encoder && !self.encoder.as_ref().is_some_and(|encoder| encoder.enabled) {
+            unmet.push(Unmet::Encoder);
+        }
+        MatchReport { unmet }
+    }
+
+    /// Device vendor, derived from CUDA support or PCI vendor id.
+    pub fn vendor(&self) -> Option<Vendor> {
+        if self.cuda.is_some() {
+            return Some(Vendor::Nvidia);
+        }
+        match self.pci.as_ref()?.vendor_id {
+            0x10de => Some(Vendor::Nvidia),
+            0x1002 => Some(Vendor::Amd),
+            _ => None,
+        }
+    }
+}
+
+/// Hardware requirements of a workload.
+#[derive(Clone, Debug, Serialize, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct Requirements {
+    /// Minimal total device memory in GiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_memory_gib: Option<f32>,
+    /// Minimal CUDA compute capability, e.g. "8.0".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cuda_caps: Option<ComputeCapability>,
+    /// Minimal CUDA version supported by the driver, e.g. "12.1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cuda_version: Option<String>,
+    /// Required vendor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<Vendor>,
+    /// Hardware video encoder is required.
+    #[serde(default)]
+    pub encoder: bool,
+}
+
+/// Result of matching a device against [`Requirements`].
+#[derive(Clone, Debug, Serialize, PartialEq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MatchReport {
+    /// Requirements the device does not meet.
+    pub unmet: Vec<Unmet>,
+}
+
+impl MatchReport {
+    /// Should be true if all requirements are met.
+    pub fn is_satisfied(&self) -> bool {
+        self.unmet.is_empty()
+    }
+}
+
+/// Requirement not met by a device.
 #[derive(Clone, Debug, Serialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "kebab-case")]
+pub enum Unmet {
+    /// Not enough device memory.
+    Memory {
+        /// Required memory in GiB.
+        required_gib: f32,
+        /// Device memory in GiB.
+        available_gib: f32,
+    },
+    /// Compute capability too low or device does not support CUDA.
+    ComputeCapability {
+        /// Required capability.
+        required: ComputeCapability,
+        /// Device capability.
+        available: Option<ComputeCapability>,
+    },
+    /// Driver supports older CUDA version.
+    CudaVersion {
+        /// Required CUDA version.
+        required: String,
+        /// Driver CUDA version.
+        available: Option<String>,
+    },
+    /// Device of other vendor.
+    Vendor {
+        /// Required vendor.
+        required: Vendor,
+        /// Device vendor.
+        available: Option<Vendor>,
+    },
+    /// Device has no hardware video encoder.
+    Encoder,
+}
+
+impl fmt::Display for Unmet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unmet::Memory {
+                required_gib,
+                available_gib,
+            } => write!(
+                f,
+                "GPU too small: {available_gib:.1} GiB memory, {required_gib:.1} GiB required"
+            ),
+            Unmet::ComputeCapability {
+                required,
+                available: Some(available),
+            } => write!(
+                f,
+                "compute capability {available} too low, {required} required"
+            ),
+            Unmet::ComputeCapability { required, .. } => {
+                write!(
+                    f,
+                    "CUDA not supported, compute capability {required} required"
+                )
+            }
+            Unmet::CudaVersion {
+                required,
+                available: Some(available),
+            } => write!(
+                f,
+                "driver supports CUDA {available}, {required} required, upgrade the driver"
+            ),
+            Unmet::CudaVersion { required, .. } => {
+                write!(f, "CUDA driver not found, CUDA {required} required")
+            }
+            Unmet::Vendor {
+                required,
+                available: Some(available),
+            } => write!(f, "{available:?} GPU, {required:?} required"),
+            Unmet::Vendor { required, .. } => {
+                write!(f, "unknown GPU vendor, {required:?} required")
+            }
+            Unmet::Encoder => f.write_str("no hardware video encoder"),
+        }
+    }
+}
+
+/// Canonical model name, e.g. `"NVIDIA GeForce RTX 3090"` becomes `"RTX 3090"`.
+///
+/// Strips vendor (NVIDIA, AMD) and brand (GeForce, Tesla, Radeon) prefixes,
+/// collapses whitespace and upper-cases model designations, so the same card
+/// gets the same name regardless of driver naming quirks.
+pub fn normalize_name(name: &str) -> String {
+    const PREFIXES: &[&str] = &["nvidia", "amd", "ati", "geforce", "tesla", "radeon"];
+    const WORDS: &[&str] = &["Ti", "Super", "Pro", "Laptop", "Max-Q", "Mobile"];
+
+    name.split_whitespace()
+        .skip_while(|word| PREFIXES.contains(&word.to_lowercase().as_str()))
+        .map(|word| {
+            WORDS
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(word))
+                .map_or_else(|| word.to_uppercase(), |known| known.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// compares `major.minor` numerically, "10.0" > "8.9".
+pub(crate) fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+    Some((major, minor))
+}
+
+fn version_at_least(available: Option<&str>, required: &str) -> bool {
+    match (available.and_then(parse_version), parse_version(required)) {
+        (Some(available), Some(required)) => available >= required,
+        _ => false,
+    }
+}
+
+/// GPU vendor.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum Vendor {
+    /// NVIDIA
+    Nvidia,
+    /// AMD
+    Amd,
+}
+
+/// CUDA specific attributes for single device
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 #[serde(rename_all = "kebab-case")]
 pub struct DeviceCuda {
     /// should be true if given device is supported.
@@ -68,58 +873,958 @@ pub struct DeviceCuda {
     /// The cores represented in the count here are commonly referred to as "CUDA core
     pub cores: u32,
     /// CUDA compute capability of this Device
-    pub caps: String,
+    pub caps: ComputeCapability,
+}
+
+/// CUDA compute capability, e.g. `8.6`.
+///
+/// Compared numerically, so `10.0` is above `8.9`. Serialized as a string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ComputeCapability {
+    /// Major revision (architecture generation).
+    pub major: u32,
+    /// Minor revision.
+    pub minor: u32,
+}
+
+impl ComputeCapability {
+    /// Creates capability `major.minor`.
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl fmt::Display for ComputeCapability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for ComputeCapability {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = parse_version(s).ok_or_else(|| ParseVersionError(s.to_string()))?;
+        Ok(Self { major, minor })
+    }
+}
+
+impl Serialize for ComputeCapability {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+#[cfg(feature = "deserialize")]
+impl<'de> Deserialize<'de> for ComputeCapability {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        String::deserialize(d)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 /// Device clocks.
-#[derive(Clone, Debug, Serialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 #[serde(rename_all = "kebab-case")]
 pub struct DeviceClocks {
     /// Graphics clock in MHz.
     ///
     /// For AMD: RSMI_CLK_TYPE_DCEF (Display Controller Engine Clock)
     /// For nVidia: NVML_CLOCK_GRAPHICS (Graphics clock domain)
-    #[serde(rename(serialize = "graphics.mhz"))]
+    #[serde(rename = "graphics.mhz")]
     pub graphics_mhz: u32,
     /// Memory clock in MHz.
-    #[serde(rename(serialize = "memory.mhz"))]
+    #[serde(rename = "memory.mhz")]
     pub memory_mhz: u32,
     /// SM clock
     ///
     /// nVidia: NVML_CLOCK_SM (Streaming Multiprocessor)
     /// AMD: RSMI_FREQ_TYPE_SYS (
-    #[serde(rename(serialize = "sm.mhz"))]
+    #[serde(rename = "sm.mhz")]
     pub sm_mhz: u32,
     /// Video encoder/decoder clock
     ///
     /// nVidia: NVML_CLOCK_VIDEO
-    #[serde(rename(serialize = "video.mhz"))]
+    /// AMD: RSMI_CLK_TYPE_VCLK0 (video core clock), RSMI_CLK_TYPE_DCLK0 (decoder clock) if missing
+    #[serde(rename = "video.mhz")]
     pub video_mhz: Option<u32>,
+    /// Power source of a laptop when clocks were read, `None` on other computers
+    /// or when unknown (Linux, and Windows with the `wmi` feature).
+    ///
+    /// On battery the driver may report maximum clocks far below those the card
+    /// reaches plugged in. Offers built on battery differ from those built plugged in,
+    /// and cards on different power sources are not aggregated into one group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_source: Option<PowerSource>,
+}
+
+/// Power source of a laptop, see [`DeviceClocks::power_source`].
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerSource {
+    /// Plugged in.
+    Ac,
+    /// Running on battery.
+    Battery,
 }
 
 /// Memory.
-#[derive(Clone, Debug, Serialize, PartialEq)]
+///
+/// `total_gib` is compared and hashed by its bit pattern, values are equal only when
+/// identical (`NaN` equals itself, `0.0` differs from `-0.0`). Use
+/// [`AggregationPolicy::memory_tolerance_gib`] for approximate grouping.
+#[derive(Clone, Debug, Serialize, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
 #[serde(rename_all = "kebab-case")]
 pub struct DeviceMemory {
-    /// Peak Memory Bandwidth.
+    /// Peak Memory Bandwidth in GiB/s.
     ///
-    /// unstable option.
+    /// unstable option, estimated from memory clock and bus width unless filled
+    /// from the spec table (see `Device::estimated`).
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(rename(serialize = "bandwidth.gib"))]
+    #[serde(rename = "bandwidth.gib")]
     pub bandwidth_gib: Option<u32>,
+    /// Measured device-to-device copy bandwidth in GiB/s.
+    ///
+    /// Filled only by `bench::measure_memory_bandwidth` (`bench` feature).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bandwidth.measured.gib")]
+    pub measured_bandwidth_gib: Option<u32>,
     /// Total physical device memory on device in GiB,
-    #[serde(rename(serialize = "total.gib"))]
+    #[serde(rename = "total.gib")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_gib))]
     pub total_gib: f32,
 }
 
-fn ser_devices<S>(devices: &Vec<Device>, s: S) -> Result<S::Ok, S::Error>
+// finite and non-negative, JSON has no representation for NaN and infinity.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_gib(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+    Ok(u.int_in_range(0..=u16::MAX as u32 * 1024)? as f32 / 1024.0)
+}
+
+impl PartialEq for DeviceMemory {
+    fn eq(&self, other: &Self) -> bool {
+        self.bandwidth_gib == other.bandwidth_gib
+            && self.measured_bandwidth_gib == other.measured_bandwidth_gib
+            && self.total_gib.to_bits() == other.total_gib.to_bits()
+    }
+}
+
+impl Eq for DeviceMemory {}
+
+impl Hash for DeviceMemory {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bandwidth_gib.hash(state);
+        self.measured_bandwidth_gib.hash(state);
+        self.total_gib.to_bits().hash(state);
+    }
+}
+
+/// Measured matrix multiplication throughput of square matrices, see
+/// `bench::measure_compute`.
+///
+/// Values are compared and hashed by their bit pattern, like [`DeviceMemory`].
+#[derive(Clone, Debug, Serialize, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceCompute {
+    /// Single precision throughput in TFLOPS.
+    #[serde(rename = "fp32.tflops")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_tflops))]
+    pub fp32_tflops: f32,
+    /// Half precision throughput in TFLOPS, `None` when the BLAS library of the card
+    /// does not multiply half precision matrices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "fp16.tflops")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_optional_tflops))]
+    pub fp16_tflops: Option<f32>,
+}
+
+// finite and non-negative, JSON has no representation for NaN and infinity.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_tflops(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+    Ok(u.int_in_range(0..=u16::MAX as u32)? as f32 / 16.0)
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_optional_tflops(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Option<f32>> {
+    u.arbitrary::<bool>()?
+        .then(|| arbitrary_tflops(u))
+        .transpose()
+}
+
+impl PartialEq for DeviceCompute {
+    fn eq(&self, other: &Self) -> bool {
+        self.fp32_tflops.to_bits() == other.fp32_tflops.to_bits()
+            && self.fp16_tflops.map(f32::to_bits) == other.fp16_tflops.map(f32::to_bits)
+    }
+}
+
+impl Eq for DeviceCompute {}
+
+impl Hash for DeviceCompute {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fp32_tflops.to_bits().hash(state);
+        self.fp16_tflops.map(f32::to_bits).hash(state);
+    }
+}
+
+/// Power limits.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DevicePower {
+    /// Minimal configurable power limit in watts.
+    #[serde(rename = "limit.min.w")]
+    pub min_limit_w: u32,
+    /// Maximal configurable power limit in watts.
+    #[serde(rename = "limit.max.w")]
+    pub max_limit_w: u32,
+    /// Power limit currently enforced by the driver in watts.
+    ///
+    /// Values lower than the default limit indicate a power capped card.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "limit.w")]
+    pub limit_w: Option<u32>,
+    /// Default power limit in watts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "limit.default.w")]
+    pub default_limit_w: Option<u32>,
+}
+
+/// Hardware video encoder.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceEncoder {
+    /// should be true if device has a hardware encoder.
+    pub enabled: bool,
+    /// Maximum number of concurrent encoding sessions.
+    ///
+    /// Consumer cards are limited by the driver, `None` means no driver limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "sessions.max")]
+    pub sessions_max: Option<u32>,
+}
+
+/// Role of a laptop GPU, see [`Device::laptop`].
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum LaptopGraphics {
+    /// Renders for an integrated GPU driving the display (NVIDIA Optimus,
+    /// AMD Switchable Graphics), powered down when idle.
+    Hybrid,
+    /// The only GPU, e.g. with the MUX switch set to discrete graphics.
+    Discrete,
+}
+
+/// Windows driver model of a card, see [`Device::driver_model`].
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceDriverModel {
+    /// Driver model in use.
+    pub current: DriverModel,
+    /// Driver model set to apply after reboot, when it differs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<DriverModel>,
+    /// Suggested change for compute workloads, `None` when nothing can be improved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<DriverModelRecommendation>,
+}
+
+/// Windows driver model.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum DriverModel {
+    /// Card is a display device, shared with the desktop compositor.
+    Wddm,
+    /// Tesla Compute Cluster, card is a compute only device.
+    Tcc,
+}
+
+/// Driver model change suggested for compute workloads, see [`DeviceDriverModel`].
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum DriverModelRecommendation {
+    /// Datacenter card runs in WDDM mode, switch it to TCC
+    /// (`nvidia-smi -i <index> -dm TCC`, then reboot).
+    SwitchToTcc,
+    /// Card can not run in TCC mode (consumer GeForce cards), WDDM limits
+    /// allocations and adds scheduling overhead.
+    TccUnsupported,
+}
+
+/// Device availability.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceStatus {
+    /// Device responds to the driver.
+    #[default]
+    Available,
+    /// Device fell off the bus and needs a reset, only its identifiers are known.
+    Lost,
+}
+
+impl DeviceStatus {
+    /// Checks if the device responds to the driver.
+    pub fn is_available(&self) -> bool {
+        *self == DeviceStatus::Available
+    }
+}
+
+/// Multi-Instance GPU partitioning.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceMig {
+    /// Number of GPU instances (memory and SM slices) the card is split into.
+    pub gpu_instances: u32,
+    /// Number of compute instances across all GPU instances.
+    pub compute_instances: u32,
+}
+
+/// PCI identification.
+///
+/// Subsystem ids distinguish board variants (vendor cards) of the same GPU.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DevicePci {
+    /// PCI vendor id (chip vendor).
+    pub vendor_id: u16,
+    /// PCI device id (chip model).
+    pub device_id: u16,
+    /// PCI subsystem vendor id (board partner).
+    #[serde(rename = "subsystem.vendor-id")]
+    pub subsystem_vendor_id: u16,
+    /// PCI subsystem device id (board model).
+    #[serde(rename = "subsystem.device-id")]
+    pub subsystem_device_id: u16,
+    /// Board partner name decoded from subsystem vendor id, e.g. ASUS, MSI, PNY.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partner: Option<String>,
+}
+
+/// Kernel driver.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct KernelDriver {
+    /// Driver name, e.g. nvidia, nouveau, amdgpu, vfio-pci.
+    pub name: String,
+    /// Driver module version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Volatile device state.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceDynamic {
+    /// Fan configuration and speeds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fans: Option<DeviceFans>,
+    /// Memory health counters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<DeviceHealth>,
+    /// Reasons why device clocks are currently reduced.
+    ///
+    /// Empty if the device runs at full clocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle_reasons: Option<Vec<ThrottleReason>>,
+    /// Instantaneous utilization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<DeviceUtilization>,
+}
+
+/// Live device state, see [`GpuDetection::telemetry`](crate::GpuDetection::telemetry).
+///
+/// Fields are `None` when not supported by the device or backend.
+#[derive(Clone, Debug, Serialize, PartialEq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct Telemetry {
+    /// Instantaneous utilization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<DeviceUtilization>,
+    /// Allocated device memory in GiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "memory.used.gib")]
+    pub memory_used_gib: Option<f32>,
+    /// GPU core temperature in degrees Celsius.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "temperature.c")]
+    pub temperature_c: Option<u32>,
+    /// Current power draw in watts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "power.draw.w")]
+    pub power_draw_w: Option<u32>,
+    /// Current (not max) clocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "clock")]
+    pub clocks: Option<DeviceClocks>,
+    /// Which processes may create contexts on the device.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_mode: Option<ComputeMode>,
+}
+
+/// Device compute mode, set by the owner e.g. with `nvidia-smi -c`.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum ComputeMode {
+    /// Any number of processes may use the device.
+    Default,
+    /// Only one process may use the device at a time.
+    ExclusiveProcess,
+    /// No process may use the device.
+    Prohibited,
+}
+
+/// Process using a device, see [`GpuDetection::processes`](crate::GpuDetection::processes).
+#[derive(Clone, Debug, Serialize, PartialEq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct GpuProcess {
+    /// Process id.
+    pub pid: u32,
+    /// Process name, if it could be resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Device memory used by the process in GiB.
+    ///
+    /// Not available on Windows WDDM driver model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "memory.used.gib")]
+    pub used_memory_gib: Option<f32>,
+}
+
+/// Utilization over the last sample period of the driver.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceUtilization {
+    /// Percent of time the device was executing kernels.
+    #[serde(rename = "gpu.pct")]
+    pub gpu_pct: u32,
+    /// Percent of time device memory was being read or written.
+    #[serde(rename = "memory.pct")]
+    pub memory_pct: u32,
+}
+
+/// Reason of reduced device clocks.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum ThrottleReason {
+    /// Nothing is running on the device.
+    GpuIdle,
+    /// Clocks were limited by application clocks setting.
+    ApplicationsClocksSetting,
+    /// Software power scaling algorithm reduces clocks below requested.
+    SwPowerCap,
+    /// Hardware slowdown (high temperature, power brake or power supply issues).
+    HwSlowdown,
+    /// Clocks are synchronized with other devices in the sync boost group.
+    SyncBoost,
+    /// Software thermal slowdown.
+    SwThermalSlowdown,
+    /// Hardware thermal slowdown, temperature is too high.
+    HwThermalSlowdown,
+    /// Power brake asserted by external power supply.
+    HwPowerBrakeSlowdown,
+    /// Clocks were limited by display clock setting.
+    DisplayClockSetting,
+}
+
+/// Fans.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceFans {
+    /// Number of fans on device.
+    pub count: u32,
+    /// Current speed of each fan as a percent of its maximum speed.
+    ///
+    /// Fans whose speed is not reported by the driver are left out.
+    #[serde(rename = "speed.pct")]
+    pub speeds_pct: Vec<u32>,
+}
+
+/// Memory health counters.
+///
+/// ECC counters are available only on devices with ECC mode enabled.
+#[derive(Clone, Debug, Serialize, PartialEq, Default, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceHealth {
+    /// Lifetime count of corrected (single bit) ECC errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "ecc.corrected")]
+    pub ecc_corrected: Option<u64>,
+    /// Lifetime count of uncorrected (double bit) ECC errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "ecc.uncorrected")]
+    pub ecc_uncorrected: Option<u64>,
+    /// Number of memory pages retired due to ECC errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "retired-pages")]
+    pub retired_pages: Option<u32>,
+    /// Should be true if there are pages waiting for retirement on next reboot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "retired-pages.pending")]
+    pub retired_pages_pending: Option<bool>,
+}
+
+fn ser_devices<S, T>(devices: &[T], s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
+    T: Serialize,
 {
     let mut m = s.serialize_map(Some(devices.len()))?;
-    for (idx, dev) in devices.into_iter().enumerate() {
+    for (idx, dev) in devices.iter().enumerate() {
         m.serialize_key(&format!("d{idx}"))?;
         m.serialize_value(dev)?;
     }
     m.end()
 }
+
+#[cfg(feature = "deserialize")]
+fn de_devices<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    use serde::de::{IgnoredAny, MapAccess, Visitor};
+    use std::marker::PhantomData;
+
+    struct DevicesVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for DevicesVisitor<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("map with d<idx> device entries")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut devices = BTreeMap::new();
+            while let Some(key) = map.next_key::<String>()? {
+                // other keys belong to flattened sibling fields.
+                match key
+                    .strip_prefix('d')
+                    .and_then(|idx| idx.parse::<usize>().ok())
+                {
+                    Some(idx) => {
+                        devices.insert(idx, map.next_value::<T>()?);
+                    }
+                    None => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+            Ok(devices.into_values().collect())
+        }
+    }
+
+    d.deserialize_map(DevicesVisitor(PhantomData))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_offer_properties() {
+        let gpu = Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.3".into(),
+                    driver_version: None,
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![Device {
+                model: "NVIDIA A30".into(),
+                memory: DeviceMemory {
+                    bandwidth_gib: None,
+                    measured_bandwidth_gib: None,
+                    total_gib: 24.0,
+                },
+                quantity: 2,
+                uuids: vec!["GPU-a".into(), "GPU-b".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let properties = gpu.to_offer_properties("golem.!exp.gap-35.v1.inf.gpu.");
+        assert_eq!(
+            properties["golem.!exp.gap-35.v1.inf.gpu.cuda.version"],
+            json!("12.3")
+        );
+        assert_eq!(
+            properties["golem.!exp.gap-35.v1.inf.gpu.d0.memory.total.gib"],
+            json!(24.0)
+        );
+        assert_eq!(
+            properties["golem.!exp.gap-35.v1.inf.gpu.d0.uuids"],
+            json!(["GPU-a", "GPU-b"])
+        );
+        assert_eq!(
+            properties["golem.!exp.gap-35.v1.inf.gpu.d0.quantity"],
+            json!(2)
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let device = |uuid: &str, total_gib| Device {
+            model: "NVIDIA A30".into(),
+            memory: DeviceMemory {
+                total_gib,
+                ..Default::default()
+            },
+            quantity: 1,
+            uuids: vec![uuid.into()],
+            ..Default::default()
+        };
+        let gpu = |driver_version: &str, devices| Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.3".into(),
+                    driver_version: Some(driver_version.parse().unwrap()),
+                    driver_version_raw: None,
+                }),
+            },
+            devices,
+            ..Default::default()
+        };
+        let old = gpu(
+            "545.23.08",
+            vec![device("GPU-a", 24.0), device("GPU-b", 24.0)],
+        );
+        let new = gpu(
+            "550.54.14",
+            vec![device("GPU-a", 20.0), device("GPU-c", 24.0)],
+        );
+
+        assert!(old.diff(&old).is_empty());
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec!["GPU-c"]);
+        assert_eq!(diff.removed, vec!["GPU-b"]);
+        assert_eq!(
+            diff.api,
+            vec![PropertyChange {
+                key: "cuda.driver.version".into(),
+                old: Some(json!("545.23.08")),
+                new: Some(json!("550.54.14")),
+            }]
+        );
+        assert!(diff.host.is_empty());
+        assert_eq!(
+            diff.devices,
+            vec![DeviceDiff {
+                uuid: "GPU-a".into(),
+                changes: vec![PropertyChange {
+                    key: "memory.total.gib".into(),
+                    old: Some(json!(24.0)),
+                    new: Some(json!(20.0)),
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_driver_version() {
+        let linux: DriverVersion = "535.161.07".parse().unwrap();
+        let windows: DriverVersion = "555.85".parse().unwrap();
+        assert_eq!(linux.to_string(), "535.161.07");
+        assert_eq!(windows.to_string(), "555.85");
+        assert!(linux >= DriverVersion::new(510, 0));
+        assert!(linux < windows);
+        assert!("546.01".parse::<DriverVersion>().unwrap() < windows);
+        assert!("555".parse::<DriverVersion>().is_err());
+        assert!("555.85.1.2".parse::<DriverVersion>().is_err());
+        assert_eq!(serde_json::to_value(linux).unwrap(), json!("535.161.07"));
+    }
+
+    #[test]
+    fn test_compute_capability() {
+        let caps: ComputeCapability = "10.0".parse().unwrap();
+        assert!(caps > "8.9".parse().unwrap());
+        assert_eq!(caps.to_string(), "10.0");
+        assert_eq!("9".parse(), Ok(ComputeCapability::new(9, 0)));
+        assert!("sm_86".parse::<ComputeCapability>().is_err());
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("NVIDIA GeForce RTX 3090"), "RTX 3090");
+        assert_eq!(
+            normalize_name("GeForce  RTX 4070 ti SUPER"),
+            "RTX 4070 Ti Super"
+        );
+        assert_eq!(normalize_name("Tesla T4"), "T4");
+        assert_eq!(normalize_name("NVIDIA A100-PCIE-40GB"), "A100-PCIE-40GB");
+        assert_eq!(normalize_name("AMD Radeon RX 7900 XTX"), "RX 7900 XTX");
+        assert_eq!(normalize_name("NVIDIA RTX A6000"), "RTX A6000");
+    }
+
+    #[test]
+    fn test_merge() {
+        let device = |model: &str, uuid: &str| Device {
+            model: model.into(),
+            quantity: 1,
+            uuids: vec![uuid.into()],
+            ..Default::default()
+        };
+        let gpu = |version: &str, devices| Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: version.into(),
+                    driver_version: Some(DriverVersion::new(550, 54)),
+                    driver_version_raw: None,
+                }),
+            },
+            devices,
+            ..Default::default()
+        };
+        let merged = Gpu::merge([
+            gpu(
+                "12.4",
+                vec![device("NVIDIA A30", "GPU-a"), device("NVIDIA L4", "GPU-b")],
+            ),
+            gpu("12.2", vec![device("NVIDIA A30", "GPU-c")]),
+        ]);
+
+        assert_eq!(merged.devices.len(), 2);
+        assert_eq!(merged.devices[0].quantity, 2);
+        assert_eq!(merged.devices[0].uuids, vec!["GPU-a", "GPU-c"]);
+        assert_eq!(merged.devices[1].model, "NVIDIA L4");
+        let cuda = merged.api.cuda.unwrap();
+        assert_eq!(cuda.version, "12.2");
+        assert_eq!(cuda.driver_version, None);
+    }
+
+    #[test]
+    fn test_requirements() {
+        let dev = Device {
+            model: "NVIDIA GeForce RTX 3060".into(),
+            cuda: Some(DeviceCuda {
+                enabled: true,
+                cores: 3584,
+                caps: ComputeCapability::new(8, 6),
+            }),
+            memory: DeviceMemory {
+                bandwidth_gib: None,
+                measured_bandwidth_gib: None,
+                total_gib: 12.0,
+            },
+            quantity: 1,
+            ..Default::default()
+        };
+        let requirements = Requirements {
+            min_memory_gib: Some(16.0),
+            min_cuda_caps: Some(ComputeCapability::new(8, 0)),
+            min_cuda_version: Some("12.1".into()),
+            ..Default::default()
+        };
+
+        let report = dev.satisfies(&requirements);
+        assert_eq!(
+            report.unmet,
+            vec![Unmet::Memory {
+                required_gib: 16.0,
+                available_gib: 12.0
+            }]
+        );
+        assert_eq!(
+            report.unmet[0].to_string(),
+            "GPU too small: 12.0 GiB memory, 16.0 GiB required"
+        );
+
+        let gpu = Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "11.8".into(),
+                    driver_version: None,
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![dev],
+            ..Default::default()
+        };
+        let reports = gpu.satisfies(&requirements);
+        assert_eq!(reports[0].unmet.len(), 2);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_camel_case() {
+        let gpu = Gpu {
+            devices: vec![Device {
+                model: "NVIDIA A30".into(),
+                clocks: DeviceClocks {
+                    graphics_mhz: 1440,
+                    ..Default::default()
+                },
+                model_normalized: Some("A30".into()),
+                quantity: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(CamelCase(&gpu)).unwrap();
+        assert_eq!(json["d0"]["clock"]["graphicsMhz"], json!(1440));
+        assert_eq!(json["d0"]["memory"]["totalGib"], json!(0.0));
+        assert_eq!(json["d0"]["modelNormalized"], json!("A30"));
+        assert_eq!(json["d0"]["model"], json!("NVIDIA A30"));
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_schema_version() {
+        let gpu = Gpu {
+            devices: vec![Device {
+                model: "NVIDIA A30".into(),
+                cuda: Some(DeviceCuda {
+                    enabled: true,
+                    cores: 3584,
+                    caps: ComputeCapability::new(8, 0),
+                }),
+                quantity: 2,
+                uuids: vec!["GPU-a".into(), "GPU-b".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&gpu).unwrap();
+        assert_eq!(json["schema-version"], json!(SCHEMA_VERSION));
+
+        let old: v1::Gpu = serde_json::from_value(json).unwrap();
+        assert_eq!(old.devices[0].quantity, 2);
+        assert_eq!(old.devices[0].cuda.as_ref().unwrap().caps, "8.0");
+
+        let json = serde_json::to_value(v1::Gpu::from(gpu)).unwrap();
+        assert!(json.get("schema-version").is_none());
+        assert!(json["d0"].get("uuids").is_none());
+        let parsed: Gpu = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.schema_version, 1);
+        assert_eq!(parsed.devices[0].model, "NVIDIA A30");
+    }
+
+    #[test]
+    fn test_eq_hash() {
+        use std::collections::HashSet;
+
+        let device = |total_gib| Device {
+            model: "NVIDIA A30".into(),
+            memory: DeviceMemory {
+                total_gib,
+                ..Default::default()
+            },
+            quantity: 1,
+            ..Default::default()
+        };
+        assert_eq!(device(24.0), device(24.0));
+        assert_ne!(device(24.0), device(23.9));
+        assert_eq!(device(f32::NAN), device(f32::NAN));
+        let gpus = HashSet::from([
+            Gpu {
+                devices: vec![device(24.0)],
+                ..Default::default()
+            },
+            Gpu {
+                devices: vec![device(24.0)],
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(gpus.len(), 1);
+    }
+
+    #[cfg(all(feature = "arbitrary", feature = "deserialize"))]
+    #[test]
+    fn test_arbitrary_round_trip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let data = (0..4096u32)
+            .map(|i| (i * 7919 % 251) as u8)
+            .collect::<Vec<_>>();
+        let gpu = Gpu::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(gpu.schema_version, SCHEMA_VERSION);
+        let json = serde_json::to_value(&gpu).unwrap();
+        let parsed: Gpu = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_round_trip() {
+        let gpu = Gpu {
+            api: GpuApiInfo {
+                cuda: Some(Cuda {
+                    version: "12.3".into(),
+                    driver_version: Some("545.23.08".parse().unwrap()),
+                    driver_version_raw: None,
+                }),
+            },
+            devices: vec![
+                Device {
+                    model: "NVIDIA A30".into(),
+                    quantity: 2,
+                    uuids: vec!["GPU-a".into(), "GPU-b".into()],
+                    ..Default::default()
+                },
+                Device {
+                    model: "NVIDIA GeForce RTX 3090".into(),
+                    quantity: 1,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&gpu).unwrap();
+        let parsed: Gpu = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed.devices.len(), 2);
+        assert_eq!(parsed.devices[0].uuids, gpu.devices[0].uuids);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+}
@@ -0,0 +1,151 @@
+//! Offer format before `schema-version` was introduced.
+//!
+//! Frozen, it is not extended when the current model changes. Documents in the
+//! current format parse as v1 with newer fields ignored, and v1 documents parse as
+//! the current [`Gpu`](super::Gpu) with `schema_version` 1.
+
+#[cfg(feature = "deserialize")]
+use super::de_devices;
+use super::ser_devices;
+#[cfg(feature = "deserialize")]
+use serde::Deserialize;
+use serde::Serialize;
+
+/// General information about all gpus.
+#[derive(Clone, Debug, Serialize, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub struct Gpu {
+    /// Optional information about installed CUDA API & Drivers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cuda: Option<Cuda>,
+    /// Lists of devices.
+    #[serde(flatten, serialize_with = "ser_devices")]
+    #[cfg_attr(feature = "deserialize", serde(deserialize_with = "de_devices"))]
+    pub devices: Vec<Device>,
+}
+
+/// information about installed CUDA.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub struct Cuda {
+    /// CUDA version
+    pub version: String,
+    /// Installed driver version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "driver.version")]
+    pub driver_version: Option<String>,
+}
+
+/// GPU device group information.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct Device {
+    /// Name of this device.
+    pub model: String,
+    /// CUDA specific attributes for this device
+    pub cuda: Option<DeviceCuda>,
+    /// Device clocks.
+    #[serde(rename = "clock")]
+    pub clocks: DeviceClocks,
+    /// Memory information.
+    pub memory: DeviceMemory,
+    /// Number of cards.
+    pub quantity: usize,
+}
+
+/// CUDA specific attributes for single device
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct DeviceCuda {
+    /// should be true if given device is supported.
+    pub enabled: bool,
+    /// Core count for this device.
+    pub cores: u32,
+    /// CUDA compute capability of this Device
+    pub caps: String,
+}
+
+/// Device clocks.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub struct DeviceClocks {
+    /// Graphics clock in MHz.
+    #[serde(rename = "graphics.mhz")]
+    pub graphics_mhz: u32,
+    /// Memory clock in MHz.
+    #[serde(rename = "memory.mhz")]
+    pub memory_mhz: u32,
+    /// SM clock
+    #[serde(rename = "sm.mhz")]
+    pub sm_mhz: u32,
+    /// Video encoder/decoder clock
+    #[serde(rename = "video.mhz")]
+    pub video_mhz: Option<u32>,
+}
+
+/// Memory.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub struct DeviceMemory {
+    /// Peak Memory Bandwidth.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bandwidth.gib")]
+    pub bandwidth_gib: Option<u32>,
+    /// Total physical device memory on device in GiB,
+    #[serde(rename = "total.gib")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = super::arbitrary_gib))]
+    pub total_gib: f32,
+}
+
+/// Assumed for documents without `schema-version`.
+#[cfg(feature = "deserialize")]
+pub(super) fn schema_version() -> u32 {
+    1
+}
+
+/// Drops fields added after v1.
+impl From<super::Gpu> for Gpu {
+    fn from(gpu: super::Gpu) -> Self {
+        Gpu {
+            cuda: gpu.api.cuda.map(|cuda| Cuda {
+                version: cuda.version,
+                driver_version: (cuda.driver_version)
+                    .map(|version| version.to_string())
+                    .or(cuda.driver_version_raw),
+            }),
+            devices: gpu.devices.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<super::Device> for Device {
+    fn from(dev: super::Device) -> Self {
+        Device {
+            model: dev.model,
+            cuda: dev.cuda.map(|cuda| DeviceCuda {
+                enabled: cuda.enabled,
+                cores: cuda.cores,
+                caps: cuda.caps.to_string(),
+            }),
+            clocks: DeviceClocks {
+                graphics_mhz: dev.clocks.graphics_mhz,
+                memory_mhz: dev.clocks.memory_mhz,
+                sm_mhz: dev.clocks.sm_mhz,
+                video_mhz: dev.clocks.video_mhz,
+            },
+            memory: DeviceMemory {
+                bandwidth_gib: dev.memory.bandwidth_gib,
+                total_gib: dev.memory.total_gib,
+            },
+            quantity: dev.quantity,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Background telemetry sampling.
+//!
+//! ```no_run
+//! use golem_gpu_info::monitor::Monitor;
+//! use std::time::Duration;
+//!
+//! let detection = golem_gpu_info::GpuDetectionBuilder::default().init()?;
+//! let mut monitor = Monitor::start(&detection, Duration::from_secs(1))?;
+//! for uuid in monitor.uuids() {
+//!     println!("monitoring {uuid}");
+//! }
+//! # Ok::<(), golem_gpu_info::GpuDetectionError>(())
+//! ```
+
+use crate::model::Telemetry;
+use crate::{GpuDetection, GpuDetectionError, Result};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Frames are dropped when a subscriber lags more than this many samples behind.
+const FRAME_BUFFER: usize = 64;
+
+/// How long dropping a monitor waits for a sample in progress.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Single telemetry sample of a device.
+#[derive(Debug)]
+pub struct TelemetryFrame {
+    /// Device uuid.
+    pub uuid: String,
+    /// Sampling time.
+    pub timestamp: SystemTime,
+    /// Sampled state, or error if the device could not be queried.
+    pub telemetry: std::result::Result<Telemetry, GpuDetectionError>,
+}
+
+/// Samples telemetry of all visible devices on a background thread.
+///
+/// Each device gets its own channel. Sampling stops when the monitor is dropped
+/// or all receivers are gone. Dropping waits for a sample in progress at most a second.
+pub struct Monitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    // disconnected when the sampling thread exits.
+    done: Receiver<()>,
+    receivers: BTreeMap<String, Receiver<TelemetryFrame>>,
+}
+
+impl Monitor {
+    /// Starts sampling every `interval`.
+    pub fn start(detection: &GpuDetection, interval: Duration) -> Result<Monitor> {
+        let mut senders = Vec::new();
+        let mut receivers = BTreeMap::new();
+        for device in detection.detect_all()? {
+            for uuid in device.uuids {
+                let (tx, rx) = mpsc::sync_channel(FRAME_BUFFER);
+                senders.push((uuid.clone(), tx));
+                receivers.insert(uuid, rx);
+            }
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (done_tx, done) = mpsc::sync_channel(0);
+        let handle = {
+            let detection = detection.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("gpu-monitor".into())
+                .spawn(move || {
+                    let _done = done_tx;
+                    sample(detection, senders, interval, stop)
+                })
+                .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?
+        };
+
+        Ok(Monitor {
+            stop,
+            handle: Some(handle),
+            done,
+            receivers,
+        })
+    }
+
+    /// Uuids of monitored devices whose receivers were not taken yet.
+    pub fn uuids(&self) -> impl Iterator<Item = &str> {
+        self.receivers.keys().map(String::as_str)
+    }
+
+    /// Takes frames receiver of device with given uuid.
+    ///
+    /// Returns `None` if device is not monitored or receiver was already taken.
+    pub fn take_receiver(&mut self, uuid: &str) -> Option<Receiver<TelemetryFrame>> {
+        self.receivers.remove(uuid)
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            // a query hung in the driver must not block the owner, the thread
+            // is detached and exits once the query returns.
+            if let Err(RecvTimeoutError::Disconnected) = self.done.recv_timeout(STOP_TIMEOUT) {
+                // sampling thread errors are already delivered in frames.
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+fn sample(
+    detection: GpuDetection,
+    mut senders: Vec<(String, SyncSender<TelemetryFrame>)>,
+    interval: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::Relaxed) && !senders.is_empty() {
+        senders.retain(|(uuid, tx)| {
+            let frame = TelemetryFrame {
+                uuid: uuid.clone(),
+                timestamp: SystemTime::now(),
+                telemetry: detection.telemetry(uuid),
+            };
+            // lagging subscriber only loses frames.
+            !matches!(tx.try_send(frame), Err(TrySendError::Disconnected(_)))
+        });
+        thread::park_timeout(interval);
+    }
+}
@@ -0,0 +1,57 @@
+//! PCI vendor identification.
+
+/// Normalizes PCI bus id to the `domain:bus:device.function` form used by sysfs.
+///
+/// NVML reports 8 digit domain (`00000000:01:00.0`), sysfs and lspci use 4 digits.
+pub(crate) fn normalize_bus_id(bus_id: &str) -> String {
+    let bus_id = bus_id.trim().to_ascii_lowercase();
+    match bus_id.split_once(':') {
+        Some((domain, rest)) if rest.contains(':') => match u32::from_str_radix(domain, 16) {
+            Ok(domain) => format!("{domain:04x}:{rest}"),
+            Err(_) => bus_id,
+        },
+        // domain omitted (`01:00.0`)
+        _ => format!("0000:{bus_id}"),
+    }
+}
+
+/// Decodes PCI (sub)vendor id into a board partner name.
+#[cfg_attr(not(any(feature = "cuda", feature = "amd")), allow(dead_code))]
+pub(crate) fn vendor_name(vendor_id: u16) -> Option<&'static str> {
+    Some(match vendor_id {
+        0x1002 => "AMD",
+        0x1028 => "Dell",
+        0x103c => "HP",
+        0x1043 => "ASUS",
+        0x10b0 => "Gainward",
+        0x10de => "NVIDIA",
+        0x1458 => "Gigabyte",
+        0x1462 => "MSI",
+        0x148c => "PowerColor",
+        0x1569 => "Palit",
+        0x1682 => "XFX",
+        0x174b => "PC Partner",
+        0x17aa => "Lenovo",
+        0x1849 => "ASRock",
+        0x196e => "PNY",
+        0x19da => "Zotac",
+        0x1acc => "Point of View",
+        0x1b4c => "Galax",
+        0x1da2 => "Sapphire",
+        0x3842 => "EVGA",
+        0x7377 => "Colorful",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::normalize_bus_id;
+
+    #[test]
+    fn test_normalize_bus_id() {
+        assert_eq!(normalize_bus_id("00000000:01:00.0"), "0000:01:00.0");
+        assert_eq!(normalize_bus_id("0000:0A:00.0"), "0000:0a:00.0");
+        assert_eq!(normalize_bus_id("01:00.0"), "0000:01:00.0");
+    }
+}
@@ -1,12 +1,63 @@
 use super::Result;
-use crate::model::{Device, GpuApiInfo};
+use crate::health::HealthReport;
+use crate::model::{normalize_name, Device, GpuApiInfo, GpuProcess, Telemetry};
+use crate::watch::DeviceErrorEvent;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 
 pub struct Flags {
     pub unstable: bool,
+    pub dynamic: bool,
     pub force: bool,
+    pub nvml_lib_path: Option<PathBuf>,
+    #[cfg_attr(not(feature = "amd"), allow(dead_code))]
+    pub rocm_lib_path: Option<PathBuf>,
+    pub sysfs_root: PathBuf,
+    pub enrich_specs: bool,
+    pub normalized_names: bool,
+    /// Keep dormant cards resumed while they are listed.
+    #[cfg_attr(not(all(target_os = "linux", feature = "cuda")), allow(dead_code))]
+    pub runtime_resume: bool,
+    /// Leave out properties which fail to read, see `DetectionPolicy::Lenient`.
+    pub lenient: bool,
+    /// Number of cards whose properties may be read at once.
+    pub threads: usize,
 }
 
-pub trait Platform {
+impl Flags {
+    /// Post-processes device built by a backend.
+    #[cfg_attr(not(any(feature = "cuda", feature = "amd")), allow(dead_code))]
+    pub fn apply(&self, dev: &mut Device) {
+        if self.enrich_specs {
+            crate::specs::enrich(dev);
+        }
+        if self.normalized_names {
+            dev.model_normalized = Some(normalize_name(&dev.model));
+        }
+    }
+
+    /// Leaves out property `key` which failed to read under lenient policy,
+    /// listing it in `unsupported` instead of failing the whole card.
+    #[cfg_attr(not(any(feature = "cuda", feature = "amd")), allow(dead_code))]
+    pub fn tolerate<T, E: std::fmt::Display>(
+        &self,
+        result: std::result::Result<T, E>,
+        key: &str,
+        unsupported: &mut Vec<String>,
+    ) -> std::result::Result<Option<T>, E> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if !self.lenient => Err(e),
+            Err(_e) => {
+                warn!(property = key, error = %_e, "failed to read device property, left out");
+                unsupported.push(key.into());
+                Ok(None)
+            }
+        }
+    }
+}
+
+pub trait Platform: Send + Sync {
     fn name(&self) -> &str;
 
     fn init(&self, flags: Flags) -> Result<Box<dyn Detection>>;
@@ -17,5 +68,43 @@ pub trait Detection: Sync + Send {
 
     fn devices(&self) -> Result<Vec<Device>>;
 
+    /// Lists devices, one entry per backend native index, so that a single
+    /// unreadable device does not hide the others.
+    fn devices_partial(&self) -> Result<Vec<Result<Device>>> {
+        Ok(self.devices()?.into_iter().map(Ok).collect())
+    }
+
     fn device_by_uuid(&self, uuid: &str) -> Result<Option<Device>>;
+
+    /// `bus_id` is normalized to `0000:01:00.0` form.
+    fn device_by_pci_bus_id(&self, bus_id: &str) -> Result<Option<Device>>;
+
+    fn device_by_index(&self, index: u32) -> Result<Option<Device>>;
+
+    /// Samples live state of device with given uuid.
+    fn telemetry(&self, uuid: &str) -> Result<Option<Telemetry>>;
+
+    /// Lists processes using device with given uuid.
+    fn processes(&self, uuid: &str) -> Result<Option<Vec<GpuProcess>>>;
+
+    /// Reports health of every device known to the backend.
+    fn health_check(&self) -> Result<Vec<HealthReport>>;
+
+    /// Blocks delivering driver error events to `on_event` until `stop` is set
+    /// or `on_event` returns `false`.
+    ///
+    /// Backends without event support return immediately.
+    fn watch_errors(
+        &self,
+        on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        stop: &AtomicBool,
+    ) -> Result<()>;
+
+    /// Version of the underlying management library, if known.
+    fn version(&self) -> Option<String>;
+
+    /// Fails when the installed driver or runtime is older than the supported minimum.
+    fn check_environment(&self) -> Result<()> {
+        Ok(())
+    }
 }
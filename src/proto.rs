@@ -0,0 +1,262 @@
+//! Protobuf messages generated from `proto/gpu_info.proto`, with conversions
+//! from [`model`](crate::model).
+//!
+//! Services exchanging inventory over gRPC can map the `golem.gpu_info.v1` messages
+//! to these types (`extern_path` in `prost-build`) instead of generating their own.
+
+use crate::model;
+
+#[allow(missing_docs, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/golem.gpu_info.v1.rs"));
+}
+
+pub use generated::*;
+
+impl From<model::Gpu> for Gpu {
+    fn from(gpu: model::Gpu) -> Self {
+        Gpu {
+            schema_version: gpu.schema_version,
+            cuda: gpu.api.cuda.map(Into::into),
+            devices: gpu.devices.into_iter().map(Into::into).collect(),
+            host: gpu.host.map(Into::into),
+        }
+    }
+}
+
+impl From<model::Cuda> for Cuda {
+    fn from(cuda: model::Cuda) -> Self {
+        Cuda {
+            version: cuda.version,
+            driver_version: (cuda.driver_version)
+                .map(|version| version.to_string())
+                .or(cuda.driver_version_raw),
+        }
+    }
+}
+
+impl From<model::Host> for Host {
+    fn from(host: model::Host) -> Self {
+        Host {
+            os: host.os,
+            arch: host.arch,
+            kernel_version: host.kernel_version,
+            virtualization: host.virtualization,
+            container: host.container,
+            container_toolkit: host.container_toolkit.map(|toolkit| ContainerToolkit {
+                cli: toolkit.cli,
+                runtime: toolkit.runtime,
+                docker_runtime: toolkit.docker_runtime,
+                cdi_specs: toolkit.cdi_specs,
+            }),
+            persistence_mode: host.persistence_mode,
+        }
+    }
+}
+
+impl From<model::Device> for Device {
+    fn from(dev: model::Device) -> Self {
+        Device {
+            model: dev.model,
+            cuda: dev.cuda.map(|cuda| DeviceCuda {
+                enabled: cuda.enabled,
+                cores: cuda.cores,
+                caps: cuda.caps.to_string(),
+            }),
+            clocks: Some(dev.clocks.into()),
+            memory: Some(DeviceMemory {
+                bandwidth_gib: dev.memory.bandwidth_gib,
+                measured_bandwidth_gib: dev.memory.measured_bandwidth_gib,
+                total_gib: dev.memory.total_gib,
+            }),
+            power: dev.power.map(|power| DevicePower {
+                min_limit_w: power.min_limit_w,
+                max_limit_w: power.max_limit_w,
+                limit_w: power.limit_w,
+                default_limit_w: power.default_limit_w,
+            }),
+            encoder: dev.encoder.map(|encoder| DeviceEncoder {
+                enabled: encoder.enabled,
+                sessions_max: encoder.sessions_max,
+            }),
+            persistence_mode: dev.persistence_mode,
+            pci: dev.pci.map(|pci| DevicePci {
+                vendor_id: pci.vendor_id.into(),
+                device_id: pci.device_id.into(),
+                subsystem_vendor_id: pci.subsystem_vendor_id.into(),
+                subsystem_device_id: pci.subsystem_device_id.into(),
+                partner: pci.partner,
+            }),
+            kernel_driver: dev.kernel_driver.map(|driver| KernelDriver {
+                name: driver.name,
+                version: driver.version,
+            }),
+            external: dev.external,
+            laptop: dev.laptop.map(|laptop| LaptopGraphics::from(laptop) as i32),
+            driver_model: dev.driver_model.map(|model| DeviceDriverModel {
+                current: DriverModel::from(model.current) as i32,
+                pending: model
+                    .pending
+                    .map(|pending| DriverModel::from(pending) as i32),
+                recommendation: model
+                    .recommendation
+                    .map(|advice| DriverModelRecommendation::from(advice) as i32),
+            }),
+            status: DeviceStatus::from(dev.status) as i32,
+            mig: dev.mig.map(|mig| DeviceMig {
+                gpu_instances: mig.gpu_instances,
+                compute_instances: mig.compute_instances,
+            }),
+            quantity: dev.quantity as u64,
+            uuids: dev.uuids,
+            indices: dev.indices,
+            pci_bus_ids: dev.pci_bus_ids,
+            dynamic: dev.dynamic.map(Into::into),
+            compute: dev.compute.map(|compute| DeviceCompute {
+                fp32_tflops: compute.fp32_tflops,
+                fp16_tflops: compute.fp16_tflops,
+            }),
+            enriched: dev.enriched,
+            estimated: dev.estimated,
+            model_normalized: dev.model_normalized,
+            unsupported: dev.unsupported,
+        }
+    }
+}
+
+impl From<model::DeviceClocks> for DeviceClocks {
+    fn from(clocks: model::DeviceClocks) -> Self {
+        DeviceClocks {
+            graphics_mhz: clocks.graphics_mhz,
+            memory_mhz: clocks.memory_mhz,
+            sm_mhz: clocks.sm_mhz,
+            video_mhz: clocks.video_mhz,
+            power_source: clocks
+                .power_source
+                .map(|source| PowerSource::from(source) as i32),
+        }
+    }
+}
+
+impl From<model::PowerSource> for PowerSource {
+    fn from(source: model::PowerSource) -> Self {
+        match source {
+            model::PowerSource::Ac => PowerSource::Ac,
+            model::PowerSource::Battery => PowerSource::Battery,
+        }
+    }
+}
+
+impl From<model::DeviceDynamic> for DeviceDynamic {
+    fn from(dynamic: model::DeviceDynamic) -> Self {
+        DeviceDynamic {
+            fans: dynamic.fans.map(|fans| DeviceFans {
+                count: fans.count,
+                speeds_pct: fans.speeds_pct,
+            }),
+            health: dynamic.health.map(|health| DeviceHealth {
+                ecc_corrected: health.ecc_corrected,
+                ecc_uncorrected: health.ecc_uncorrected,
+                retired_pages: health.retired_pages,
+                retired_pages_pending: health.retired_pages_pending,
+            }),
+            throttle_reasons: dynamic
+                .throttle_reasons
+                .unwrap_or_default()
+                .into_iter()
+                .map(|reason| ThrottleReason::from(reason) as i32)
+                .collect(),
+            utilization: dynamic.utilization.map(Into::into),
+        }
+    }
+}
+
+impl From<model::ThrottleReason> for ThrottleReason {
+    fn from(reason: model::ThrottleReason) -> Self {
+        match reason {
+            model::ThrottleReason::GpuIdle => ThrottleReason::GpuIdle,
+            model::ThrottleReason::ApplicationsClocksSetting => {
+                ThrottleReason::ApplicationsClocksSetting
+            }
+            model::ThrottleReason::SwPowerCap => ThrottleReason::SwPowerCap,
+            model::ThrottleReason::HwSlowdown => ThrottleReason::HwSlowdown,
+            model::ThrottleReason::SyncBoost => ThrottleReason::SyncBoost,
+            model::ThrottleReason::SwThermalSlowdown => ThrottleReason::SwThermalSlowdown,
+            model::ThrottleReason::HwThermalSlowdown => ThrottleReason::HwThermalSlowdown,
+            model::ThrottleReason::HwPowerBrakeSlowdown => ThrottleReason::HwPowerBrakeSlowdown,
+            model::ThrottleReason::DisplayClockSetting => ThrottleReason::DisplayClockSetting,
+        }
+    }
+}
+
+impl From<model::DeviceUtilization> for DeviceUtilization {
+    fn from(utilization: model::DeviceUtilization) -> Self {
+        DeviceUtilization {
+            gpu_pct: utilization.gpu_pct,
+            memory_pct: utilization.memory_pct,
+        }
+    }
+}
+
+impl From<model::Telemetry> for Telemetry {
+    fn from(telemetry: model::Telemetry) -> Self {
+        Telemetry {
+            utilization: telemetry.utilization.map(Into::into),
+            memory_used_gib: telemetry.memory_used_gib,
+            temperature_c: telemetry.temperature_c,
+            power_draw_w: telemetry.power_draw_w,
+            clocks: telemetry.clocks.map(Into::into),
+            compute_mode: telemetry
+                .compute_mode
+                .map(|mode| ComputeMode::from(mode) as i32),
+        }
+    }
+}
+
+impl From<model::DeviceStatus> for DeviceStatus {
+    fn from(status: model::DeviceStatus) -> Self {
+        match status {
+            model::DeviceStatus::Available => DeviceStatus::Available,
+            model::DeviceStatus::Lost => DeviceStatus::Lost,
+        }
+    }
+}
+
+impl From<model::LaptopGraphics> for LaptopGraphics {
+    fn from(laptop: model::LaptopGraphics) -> Self {
+        match laptop {
+            model::LaptopGraphics::Hybrid => LaptopGraphics::Hybrid,
+            model::LaptopGraphics::Discrete => LaptopGraphics::Discrete,
+        }
+    }
+}
+
+impl From<model::DriverModel> for DriverModel {
+    fn from(model: model::DriverModel) -> Self {
+        match model {
+            model::DriverModel::Wddm => DriverModel::Wddm,
+            model::DriverModel::Tcc => DriverModel::Tcc,
+        }
+    }
+}
+
+impl From<model::DriverModelRecommendation> for DriverModelRecommendation {
+    fn from(advice: model::DriverModelRecommendation) -> Self {
+        match advice {
+            model::DriverModelRecommendation::SwitchToTcc => DriverModelRecommendation::SwitchToTcc,
+            model::DriverModelRecommendation::TccUnsupported => {
+                DriverModelRecommendation::TccUnsupported
+            }
+        }
+    }
+}
+
+impl From<model::ComputeMode> for ComputeMode {
+    fn from(mode: model::ComputeMode) -> Self {
+        match mode {
+            model::ComputeMode::Default => ComputeMode::Default,
+            model::ComputeMode::ExclusiveProcess => ComputeMode::ExclusiveProcess,
+            model::ComputeMode::Prohibited => ComputeMode::Prohibited,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Detection results with partial failures.
+
+use crate::model::Gpu;
+use crate::ErrorKind;
+use serde::Serialize;
+
+/// Outcome of [`GpuDetection::detect_report`](crate::GpuDetection::detect_report).
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DetectionReport {
+    /// Devices which were detected successfully.
+    pub gpu: Gpu,
+    /// Problems which did not prevent detection.
+    pub warnings: Vec<DetectionWarning>,
+    /// Names of platforms which failed to list devices.
+    pub failed_backends: Vec<String>,
+}
+
+/// Problem encountered during detection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DetectionWarning {
+    /// Platform name (e.g. `"cuda"`), empty when initialization of lazy detection failed.
+    pub backend: String,
+    /// Backend native index of the skipped device, `None` for backend wide problems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    /// Code of the error, `NOT_SUPPORTED` for properties the driver does not report.
+    pub code: ErrorKind,
+    /// Error message.
+    pub message: String,
+}
@@ -0,0 +1,120 @@
+//! Advisory per-device reservations shared by runtimes on the same host.
+//!
+//! A reservation is an OS file lock (`flock` / `LockFileEx`) on a file named after
+//! the device uuid, which also records the owner PID. The operating system releases
+//! the lock when its owner exits, so reservations of crashed processes are free again.
+//! Nothing stops a process ignoring reservations from using the device.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Exclusive claim on a device, released when dropped.
+#[derive(Debug)]
+pub struct Reservation {
+    file: File,
+}
+
+impl Reservation {
+    /// Reserves device with given uuid in the default lock directory
+    /// (`golem-gpu` in the system temporary directory).
+    ///
+    /// Returns `None` if the device is reserved by another running process.
+    pub fn acquire(uuid: &str) -> io::Result<Option<Reservation>> {
+        Self::acquire_in(&default_dir(), uuid)
+    }
+
+    /// Reserves device with given uuid, keeping lock files in `dir`.
+    ///
+    /// All cooperating runtimes have to use the same directory. Lock files are kept
+    /// when reservations are released.
+    pub fn acquire_in(dir: &Path, uuid: &str) -> io::Result<Option<Reservation>> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path(dir, uuid))?;
+        match file.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        // previous owner may have died before clearing its PID.
+        file.set_len(0)?;
+        write!(&file, "{}", std::process::id())?;
+        Ok(Some(Reservation { file }))
+    }
+
+    /// PID of the process holding reservation of device with given uuid
+    /// in the default lock directory, if it is still running.
+    pub fn owner(uuid: &str) -> io::Result<Option<u32>> {
+        Self::owner_in(&default_dir(), uuid)
+    }
+
+    /// PID of the process holding reservation of device with given uuid
+    /// in `dir`, if it is still running.
+    pub fn owner_in(dir: &Path, uuid: &str) -> io::Result<Option<u32>> {
+        let mut file = match File::open(lock_path(dir, uuid)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // a shared lock is granted only while nobody holds the reservation.
+        match file.try_lock_shared() {
+            Ok(()) => return Ok(None),
+            Err(TryLockError::WouldBlock) => (),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        let mut pid = String::new();
+        file.read_to_string(&mut pid)?;
+        Ok(pid.trim().parse().ok())
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        // the lock itself is released when the file is closed.
+        let _ = self.file.set_len(0);
+    }
+}
+
+fn default_dir() -> PathBuf {
+    std::env::temp_dir().join("golem-gpu")
+}
+
+// legacy MIG uuids contain slashes (`MIG-GPU-<uuid>/1/0`).
+fn lock_path(dir: &Path, uuid: &str) -> PathBuf {
+    dir.join(format!("{}.lock", uuid.replace(['/', '\\'], "_")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reservation() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let uuid = "GPU-5c0a6b8e-1f3e-4f1c-9c1b-2f6f4d2b8a01";
+
+        let reservation = Reservation::acquire_in(dir, uuid)
+            .unwrap()
+            .expect("device is free");
+        assert!(Reservation::acquire_in(dir, uuid).unwrap().is_none());
+        assert_eq!(
+            Reservation::owner_in(dir, uuid).unwrap(),
+            Some(std::process::id())
+        );
+        drop(reservation);
+        assert_eq!(Reservation::owner_in(dir, uuid).unwrap(), None);
+
+        // left by a process which died before releasing it, or before writing its PID.
+        for stale in [u32::MAX.to_string(), String::new()] {
+            std::fs::write(lock_path(dir, uuid), stale).unwrap();
+            assert_eq!(Reservation::owner_in(dir, uuid).unwrap(), None);
+            assert!(Reservation::acquire_in(dir, uuid).unwrap().is_some());
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Helpers for running workloads on selected devices.
+
+use crate::model::{Device, Gpu};
+use crate::pci::normalize_bus_id;
+use serde_json::{json, Value};
+
+/// Ordering used by [`Gpu::pick_best_devices`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickCriterion {
+    /// Most total memory.
+    Memory,
+    /// Highest compute score, CUDA cores times SM clock.
+    Score,
+    /// Cards closest to each other, for jobs exchanging data between cards.
+    ///
+    /// Interconnect topology is not detected, cards on the nearest PCI buses of the
+    /// same domain are picked, preferring more memory among equally close sets.
+    Locality,
+}
+
+/// Card uuid, its device group and PCI `(domain, bus)`.
+type Card<'a> = (&'a str, &'a Device, Option<(u32, u32)>);
+
+impl Gpu {
+    /// Builds `CUDA_VISIBLE_DEVICES` value exposing cards with given uuids.
+    ///
+    /// MIG instance uuids (`MIG-...`) are passed as is. Other uuids not present
+    /// in this detection are skipped, because CUDA ignores all entries
+    /// following an invalid one.
+    pub fn cuda_visible_devices(&self, selected: &[&str]) -> String {
+        self.known_uuids(selected).join(",")
+    }
+
+    /// Builds `CUDA_VISIBLE_DEVICES` value exposing cards with given indices.
+    ///
+    /// Indices are in PCI bus order, as reported by the driver, while CUDA by default
+    /// orders cards fastest first. The spawned process needs `CUDA_DEVICE_ORDER=PCI_BUS_ID`
+    /// as well, prefer [`cuda_visible_devices`](Self::cuda_visible_devices) otherwise.
+    pub fn cuda_visible_devices_by_index(&self, selected: &[u32]) -> String {
+        selected
+            .iter()
+            .filter(|index| self.devices.iter().any(|dev| dev.indices.contains(index)))
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Builds value of `docker run --gpus` exposing cards with given uuids,
+    /// e.g. `"device=GPU-a,GPU-b"`.
+    ///
+    /// Double quotes are part of the value (docker parses it as CSV), so pass it
+    /// with [`Command::arg`](std::process::Command::arg) or single-quote it in a shell.
+    /// Unknown uuids are skipped like in [`cuda_visible_devices`](Self::cuda_visible_devices).
+    pub fn docker_gpus(&self, selected: &[&str]) -> String {
+        format!("\"device={}\"", self.known_uuids(selected).join(","))
+    }
+
+    /// Builds Docker Engine API `DeviceRequest` (`HostConfig.DeviceRequests` entry)
+    /// exposing cards with given uuids.
+    pub fn docker_device_request(&self, selected: &[&str]) -> Value {
+        json!({
+            "Driver": "nvidia",
+            "DeviceIDs": self.known_uuids(selected),
+            "Capabilities": [["gpu"]],
+        })
+    }
+
+    /// Selects `n` cards best according to `criterion` and returns their uuids.
+    ///
+    /// Lost cards are skipped, returns `None` when fewer than `n` cards remain.
+    pub fn pick_best_devices(&self, n: usize, criterion: PickCriterion) -> Option<Vec<String>> {
+        let mut cards: Vec<Card> = self
+            .devices
+            .iter()
+            .filter(|dev| dev.status.is_available())
+            .flat_map(|dev| {
+                dev.uuids.iter().enumerate().map(move |(i, uuid)| {
+                    let bus = dev.pci_bus_ids.get(i).and_then(|id| parse_bus(id));
+                    (uuid.as_str(), dev, bus)
+                })
+            })
+            .collect();
+        if cards.len() < n {
+            return None;
+        }
+        let by_memory = |a: &Device, b: &Device| b.memory.total_gib.total_cmp(&a.memory.total_gib);
+        match criterion {
+            PickCriterion::Memory => cards.sort_by(|a, b| by_memory(a.1, b.1)),
+            PickCriterion::Score => cards.sort_by(|a, b| {
+                score(b.1)
+                    .cmp(&score(a.1))
+                    .then_with(|| by_memory(a.1, b.1))
+            }),
+            PickCriterion::Locality if n > 0 => {
+                // unknown bus ids last.
+                cards.sort_by_key(|card| card.2.unwrap_or((u32::MAX, u32::MAX)));
+                let start = (0..=cards.len() - n)
+                    .min_by(|&a, &b| {
+                        let window = |start: usize| &cards[start..start + n];
+                        span(window(a))
+                            .cmp(&span(window(b)))
+                            .then_with(|| memory(window(b)).total_cmp(&memory(window(a))))
+                    })
+                    .unwrap_or_default();
+                cards.drain(..start);
+            }
+            PickCriterion::Locality => (),
+        }
+        Some(
+            cards
+                .into_iter()
+                .take(n)
+                .map(|(uuid, _, _)| uuid.to_string())
+                .collect(),
+        )
+    }
+
+    fn known_uuids<'a>(&self, selected: &[&'a str]) -> Vec<&'a str> {
+        selected
+            .iter()
+            .filter(|uuid| uuid.starts_with("MIG-") || self.has_uuid(uuid))
+            .copied()
+            .collect()
+    }
+
+    fn has_uuid(&self, uuid: &str) -> bool {
+        self.devices
+            .iter()
+            .any(|dev| dev.uuids.iter().any(|id| id == uuid))
+    }
+}
+
+fn score(dev: &Device) -> u64 {
+    let cores = dev.cuda.as_ref().map_or(0, |cuda| cuda.cores);
+    cores as u64 * dev.clocks.sm_mhz as u64
+}
+
+/// `(domain, bus)` of a PCI bus id.
+fn parse_bus(bus_id: &str) -> Option<(u32, u32)> {
+    let bus_id = normalize_bus_id(bus_id);
+    let mut parts = bus_id.split(':');
+    let domain = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let bus = u32::from_str_radix(parts.next()?, 16).ok()?;
+    Some((domain, bus))
+}
+
+fn span(cards: &[Card]) -> u32 {
+    match (
+        cards.first().and_then(|c| c.2),
+        cards.last().and_then(|c| c.2),
+    ) {
+        (Some((first_domain, first)), Some((last_domain, last))) if first_domain == last_domain => {
+            last - first
+        }
+        _ => u32::MAX,
+    }
+}
+
+fn memory(cards: &[Card]) -> f32 {
+    cards.iter().map(|card| card.1.memory.total_gib).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{ComputeCapability, DeviceClocks, DeviceCuda, DeviceMemory};
+    use serde_json::json;
+
+    fn gen_gpu() -> Gpu {
+        Gpu {
+            devices: vec![Device {
+                model: "NVIDIA A30".into(),
+                quantity: 2,
+                uuids: vec!["GPU-a".into(), "GPU-b".into()],
+                indices: vec![0, 1],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cuda_visible_devices() {
+        let gpu = gen_gpu();
+        assert_eq!(
+            gpu.cuda_visible_devices(&["GPU-b", "GPU-x", "MIG-c", "GPU-a"]),
+            "GPU-b,MIG-c,GPU-a"
+        );
+        assert_eq!(gpu.cuda_visible_devices(&[]), "");
+        assert_eq!(gpu.cuda_visible_devices_by_index(&[1, 2, 0]), "1,0");
+    }
+
+    #[test]
+    fn test_pick_best_devices() {
+        let device = |model: &str, total_gib, sm_mhz, bus_ids: &[&str]| Device {
+            model: model.into(),
+            memory: DeviceMemory {
+                total_gib,
+                ..Default::default()
+            },
+            clocks: DeviceClocks {
+                sm_mhz,
+                ..Default::default()
+            },
+            cuda: Some(DeviceCuda {
+                enabled: true,
+                cores: 10496,
+                caps: ComputeCapability::new(8, 6),
+            }),
+            quantity: bus_ids.len(),
+            uuids: bus_ids.iter().map(|id| format!("GPU-{id}")).collect(),
+            pci_bus_ids: bus_ids.iter().map(|id| id.to_string()).collect(),
+            ..Default::default()
+        };
+        let gpu = Gpu {
+            devices: vec![
+                device(
+                    "NVIDIA GeForce RTX 3090",
+                    24.0,
+                    1695,
+                    &["0000:01:00.0", "0000:81:00.0"],
+                ),
+                device("NVIDIA GeForce RTX 3090", 24.0, 1860, &["0000:41:00.0"]),
+                device("NVIDIA A30", 24.0, 1440, &["0000:02:00.0"]),
+                device("NVIDIA RTX A6000", 48.0, 1800, &["0000:c1:00.0"]),
+            ],
+            ..Default::default()
+        };
+        let pick = |n, criterion| gpu.pick_best_devices(n, criterion);
+        assert_eq!(
+            pick(2, PickCriterion::Memory).unwrap(),
+            ["GPU-0000:c1:00.0", "GPU-0000:01:00.0"]
+        );
+        assert_eq!(
+            pick(2, PickCriterion::Score).unwrap(),
+            ["GPU-0000:41:00.0", "GPU-0000:c1:00.0"]
+        );
+        assert_eq!(
+            pick(2, PickCriterion::Locality).unwrap(),
+            ["GPU-0000:01:00.0", "GPU-0000:02:00.0"]
+        );
+        assert_eq!(
+            pick(1, PickCriterion::Locality).unwrap(),
+            ["GPU-0000:c1:00.0"]
+        );
+        assert_eq!(pick(6, PickCriterion::Memory), None);
+    }
+
+    #[test]
+    fn test_docker() {
+        let gpu = gen_gpu();
+        assert_eq!(
+            gpu.docker_gpus(&["GPU-a", "GPU-x", "GPU-b"]),
+            "\"device=GPU-a,GPU-b\""
+        );
+        assert_eq!(
+            gpu.docker_device_request(&["GPU-b"]),
+            json!({
+                "Driver": "nvidia",
+                "DeviceIDs": ["GPU-b"],
+                "Capabilities": [["gpu"]],
+            })
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Built-in table of official card specifications.
+//!
+//! Fills properties drivers can not report, see `GpuDetectionBuilder::enrich_specs`.
+
+use crate::model::{ComputeCapability, Device, DevicePci};
+
+/// Official specification of a card model.
+struct Spec {
+    /// Model name as reported by the driver.
+    model: &'static str,
+    /// PCI device ids (vendor `0x10de`) of the model.
+    device_ids: &'static [u16],
+    /// CUDA core count.
+    cores: u32,
+    /// CUDA compute capability, major and minor.
+    caps: (u32, u32),
+    /// Peak memory bandwidth in GB/s.
+    bandwidth_gb: u32,
+    /// Total board power in watts.
+    tdp_w: u32,
+}
+
+const NVIDIA_VENDOR_ID: u16 = 0x10de;
+
+#[rustfmt::skip]
+const SPECS: &[Spec] = &[
+    Spec { model: "Tesla K40m", device_ids: &[0x1023], cores: 2880, caps: (3, 5), bandwidth_gb: 288, tdp_w: 235 },
+    Spec { model: "Tesla K80", device_ids: &[0x102d], cores: 2496, caps: (3, 7), bandwidth_gb: 240, tdp_w: 150 },
+    Spec { model: "Tesla M40", device_ids: &[0x17fd], cores: 3072, caps: (5, 2), bandwidth_gb: 288, tdp_w: 250 },
+    Spec { model: "Tesla M60", device_ids: &[0x13f2], cores: 2048, caps: (5, 2), bandwidth_gb: 160, tdp_w: 150 },
+    Spec { model: "NVIDIA GeForce GTX 750 Ti", device_ids: &[0x1380], cores: 640, caps: (5, 0), bandwidth_gb: 86, tdp_w: 60 },
+    Spec { model: "NVIDIA GeForce GTX 780 Ti", device_ids: &[0x100a], cores: 2880, caps: (3, 5), bandwidth_gb: 336, tdp_w: 250 },
+    Spec { model: "NVIDIA GeForce GTX 970", device_ids: &[0x13c2], cores: 1664, caps: (5, 2), bandwidth_gb: 224, tdp_w: 145 },
+    Spec { model: "NVIDIA GeForce GTX 980", device_ids: &[0x13c0], cores: 2048, caps: (5, 2), bandwidth_gb: 224, tdp_w: 165 },
+    Spec { model: "NVIDIA GeForce GTX 980 Ti", device_ids: &[0x17c8], cores: 2816, caps: (5, 2), bandwidth_gb: 336, tdp_w: 250 },
+    Spec { model: "NVIDIA GeForce RTX 3060", device_ids: &[], cores: 3584, caps: (8, 6), bandwidth_gb: 360, tdp_w: 170 },
+    Spec { model: "NVIDIA GeForce RTX 3070", device_ids: &[], cores: 5888, caps: (8, 6), bandwidth_gb: 448, tdp_w: 220 },
+    Spec { model: "NVIDIA GeForce RTX 3080", device_ids: &[], cores: 8704, caps: (8, 6), bandwidth_gb: 760, tdp_w: 320 },
+    Spec { model: "NVIDIA GeForce RTX 3090", device_ids: &[0x2204], cores: 10496, caps: (8, 6), bandwidth_gb: 936, tdp_w: 350 },
+    Spec { model: "NVIDIA GeForce RTX 3090 Ti", device_ids: &[0x2203], cores: 10752, caps: (8, 6), bandwidth_gb: 1008, tdp_w: 450 },
+    Spec { model: "NVIDIA GeForce RTX 4070", device_ids: &[], cores: 5888, caps: (8, 9), bandwidth_gb: 504, tdp_w: 200 },
+    Spec { model: "NVIDIA GeForce RTX 4070 Ti", device_ids: &[], cores: 7680, caps: (8, 9), bandwidth_gb: 504, tdp_w: 285 },
+    Spec { model: "NVIDIA GeForce RTX 4080", device_ids: &[0x2704], cores: 9728, caps: (8, 9), bandwidth_gb: 717, tdp_w: 320 },
+    Spec { model: "NVIDIA GeForce RTX 4090", device_ids: &[0x2684], cores: 16384, caps: (8, 9), bandwidth_gb: 1008, tdp_w: 450 },
+    Spec { model: "Tesla T4", device_ids: &[0x1eb8], cores: 2560, caps: (7, 5), bandwidth_gb: 320, tdp_w: 70 },
+    Spec { model: "Tesla V100-PCIE-16GB", device_ids: &[], cores: 5120, caps: (7, 0), bandwidth_gb: 900, tdp_w: 250 },
+    Spec { model: "Tesla V100-SXM2-16GB", device_ids: &[], cores: 5120, caps: (7, 0), bandwidth_gb: 900, tdp_w: 300 },
+    Spec { model: "NVIDIA A10", device_ids: &[0x2236], cores: 9216, caps: (8, 6), bandwidth_gb: 600, tdp_w: 150 },
+    Spec { model: "NVIDIA A30", device_ids: &[0x20b7], cores: 3584, caps: (8, 0), bandwidth_gb: 933, tdp_w: 165 },
+    Spec { model: "NVIDIA L4", device_ids: &[0x27b8], cores: 7424, caps: (8, 9), bandwidth_gb: 300, tdp_w: 72 },
+    Spec { model: "NVIDIA A100-PCIE-40GB", device_ids: &[0x20f1], cores: 6912, caps: (8, 0), bandwidth_gb: 1555, tdp_w: 250 },
+    Spec { model: "NVIDIA A100-SXM4-40GB", device_ids: &[], cores: 6912, caps: (8, 0), bandwidth_gb: 1555, tdp_w: 400 },
+    Spec { model: "NVIDIA A100 80GB PCIe", device_ids: &[], cores: 6912, caps: (8, 0), bandwidth_gb: 1935, tdp_w: 300 },
+    Spec { model: "NVIDIA A100-SXM4-80GB", device_ids: &[], cores: 6912, caps: (8, 0), bandwidth_gb: 2039, tdp_w: 400 },
+    Spec { model: "NVIDIA H100 PCIe", device_ids: &[0x2331], cores: 14592, caps: (9, 0), bandwidth_gb: 2000, tdp_w: 350 },
+    Spec { model: "NVIDIA H100 80GB HBM3", device_ids: &[], cores: 16896, caps: (9, 0), bandwidth_gb: 3350, tdp_w: 700 },
+];
+
+/// Offer property keys of values filled from the table.
+const BANDWIDTH_KEY: &str = "memory.bandwidth.gib";
+const DEFAULT_LIMIT_KEY: &str = "power.limit.default.w";
+pub(crate) const CORES_KEY: &str = "cuda.cores";
+pub(crate) const CAPS_KEY: &str = "cuda.caps";
+
+/// Replaces estimated memory bandwidth and fills missing default power limit
+/// with official values, recording their keys in `Device::enriched`.
+pub(crate) fn enrich(dev: &mut Device) {
+    let Some(spec) = find(&dev.model, dev.pci.as_ref()) else {
+        return;
+    };
+    // driver does not report memory transfer rate, it is always estimated.
+    dev.memory.bandwidth_gib = Some(gb_to_gib(spec.bandwidth_gb));
+    dev.estimated.retain(|key| key != BANDWIDTH_KEY);
+    dev.enriched.push(BANDWIDTH_KEY.into());
+    if let Some(power) = dev
+        .power
+        .as_mut()
+        .filter(|power| power.default_limit_w.is_none())
+    {
+        power.default_limit_w = Some(spec.tdp_w);
+        dev.enriched.push(DEFAULT_LIMIT_KEY.into());
+    }
+}
+
+/// Core count of cards whose driver does not report it (Kepler, Maxwell).
+pub(crate) fn cores(model: &str, pci: Option<&DevicePci>) -> Option<u32> {
+    find(model, pci).map(|spec| spec.cores)
+}
+
+/// Compute capability of cards whose driver does not report it (old drivers, vGPU).
+pub(crate) fn compute_capability(
+    model: &str,
+    pci: Option<&DevicePci>,
+) -> Option<ComputeCapability> {
+    find(model, pci).map(|spec| ComputeCapability::new(spec.caps.0, spec.caps.1))
+}
+
+fn find(model: &str, pci: Option<&DevicePci>) -> Option<&'static Spec> {
+    let by_id = pci
+        .filter(|pci| pci.vendor_id == NVIDIA_VENDOR_ID)
+        .and_then(|pci| {
+            SPECS
+                .iter()
+                .find(|spec| spec.device_ids.contains(&pci.device_id))
+        });
+    // older drivers report GeForce names without the vendor prefix.
+    let model = model.trim();
+    let model = model.strip_prefix("NVIDIA ").unwrap_or(model);
+    by_id.or_else(|| {
+        SPECS.iter().find(|spec| {
+            let name = spec.model.strip_prefix("NVIDIA ").unwrap_or(spec.model);
+            name.eq_ignore_ascii_case(model)
+        })
+    })
+}
+
+fn gb_to_gib(gb: u32) -> u32 {
+    (gb as u64 * 1_000_000_000 / (1 << 30)) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{DeviceMemory, DevicePci, DevicePower};
+
+    #[test]
+    fn test_enrich() {
+        let mut dev = Device {
+            model: "NVIDIA GeForce RTX 3090".into(),
+            memory: DeviceMemory {
+                bandwidth_gib: Some(870),
+                ..Default::default()
+            },
+            power: Some(DevicePower::default()),
+            estimated: vec![BANDWIDTH_KEY.into()],
+            ..Default::default()
+        };
+        enrich(&mut dev);
+        assert_eq!(dev.memory.bandwidth_gib, Some(871));
+        assert_eq!(dev.power.unwrap().default_limit_w, Some(350));
+        assert_eq!(dev.enriched, vec![BANDWIDTH_KEY, DEFAULT_LIMIT_KEY]);
+        assert!(dev.estimated.is_empty());
+
+        let mut dev = Device {
+            model: "NVIDIA Graphics Device".into(),
+            pci: Some(DevicePci {
+                vendor_id: 0x10de,
+                device_id: 0x2331,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        enrich(&mut dev);
+        assert_eq!(dev.memory.bandwidth_gib, Some(1862));
+        assert_eq!(dev.enriched, vec![BANDWIDTH_KEY]);
+
+        let mut dev = Device {
+            model: "NVIDIA GeForce GT 1030".into(),
+            ..Default::default()
+        };
+        enrich(&mut dev);
+        assert!(dev.enriched.is_empty());
+    }
+
+    #[test]
+    fn test_cores() {
+        assert_eq!(cores("GeForce GTX 980", None), Some(2048));
+        let pci = DevicePci {
+            vendor_id: 0x10de,
+            device_id: 0x102d,
+            ..Default::default()
+        };
+        assert_eq!(cores("Graphics Device", Some(&pci)), Some(2496));
+        assert_eq!(cores("NVIDIA GeForce GT 1030", None), None);
+    }
+
+    #[test]
+    fn test_compute_capability() {
+        assert_eq!(
+            compute_capability("Tesla K80", None),
+            Some(ComputeCapability::new(3, 7))
+        );
+        let pci = DevicePci {
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_capability("NVIDIA GRID Device", Some(&pci)),
+            Some(ComputeCapability::new(8, 9))
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Linux sysfs helpers.
+//!
+//! All paths are relative to sysfs root, which may be mounted elsewhere in containers.
+
+use crate::model::{KernelDriver, LaptopGraphics, PowerSource};
+use crate::GpuDetectionError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[cfg(any(feature = "amd", test))]
+pub(crate) mod amdgpu;
+
+const PCI_DEVICES: &str = "bus/pci/devices";
+const MODULES: &str = "module";
+const CHASSIS_TYPE: &str = "class/dmi/id/chassis_type";
+const POWER_SUPPLIES: &str = "class/power_supply";
+
+/// SMBIOS chassis types of portable computers (portable, laptop, notebook,
+/// sub notebook, convertible, detachable).
+const LAPTOP_CHASSIS: [&str; 6] = ["8", "9", "10", "14", "31", "32"];
+
+/// Identifiers of a PCI device.
+#[cfg(feature = "amd")]
+pub(crate) struct PciIds {
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_device_id: u16,
+}
+
+/// Reads the kernel driver bound to a device given by bus id.
+pub(crate) fn kernel_driver(root: &Path, bus_id: &str) -> Option<KernelDriver> {
+    let driver = fs::read_link(root.join(PCI_DEVICES).join(bus_id).join("driver")).ok()?;
+    let name = driver.file_name()?.to_str()?.to_string();
+    // in-tree drivers (nouveau, vfio-pci) do not expose module version.
+    let version = module_version(root, &name);
+    Some(KernelDriver { name, version })
+}
+
+/// Reads version of a loaded kernel module.
+pub(crate) fn module_version(root: &Path, module: &str) -> Option<String> {
+    fs::read_to_string(root.join(MODULES).join(module).join("version"))
+        .ok()
+        .map(|version| version.trim().to_string())
+}
+
+/// Checks if a kernel module is loaded, `None` when sysfs does not list modules
+/// (e.g. it is not mounted in a container).
+pub(crate) fn module_loaded(root: &Path, module: &str) -> Option<bool> {
+    let modules = root.join(MODULES);
+    modules.is_dir().then(|| modules.join(module).is_dir())
+}
+
+/// Checks if a device is attached through an external port (Thunderbolt / USB4 enclosure).
+///
+/// Kernel marks devices behind external facing PCIe ports as `removable` (since 5.14).
+pub(crate) fn is_external(root: &Path, bus_id: &str) -> Option<bool> {
+    let removable =
+        fs::read_to_string(root.join(PCI_DEVICES).join(bus_id).join("removable")).ok()?;
+    match removable.trim() {
+        "removable" => Some(true),
+        "fixed" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads an identifying attribute (e.g. `unique_id`) of a device given by bus id,
+/// `None` if the driver does not expose it.
+#[cfg(feature = "amd")]
+pub(crate) fn device_attr(root: &Path, bus_id: &str, name: &str) -> Option<String> {
+    let value = fs::read_to_string(root.join(PCI_DEVICES).join(bus_id).join(name)).ok()?;
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Lists bus ids and vendor ids of display controllers (PCI class `0x03`).
+fn display_devices(root: &Path) -> Vec<(String, u16)> {
+    let Ok(entries) = fs::read_dir(root.join(PCI_DEVICES)) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+            if !read("class")?.trim().starts_with("0x03") {
+                return None;
+            }
+            let vendor = read("vendor")?;
+            let vendor = u16::from_str_radix(vendor.trim().trim_start_matches("0x"), 16).ok()?;
+            Some((entry.file_name().to_str()?.to_string(), vendor))
+        })
+        .collect()
+}
+
+/// Error for a kernel driver `module` which is not loaded, hosts without display
+/// controllers of `vendor_id` are reported as [`GpuDetectionError::NotFound`]
+/// unless the platform is forced.
+pub(crate) fn missing_module(
+    root: &Path,
+    module: &str,
+    vendor_id: u16,
+    force: bool,
+) -> Option<GpuDetectionError> {
+    if module_loaded(root, module) != Some(false) {
+        return None;
+    }
+    let card = display_devices(root)
+        .iter()
+        .any(|(_, vendor)| *vendor == vendor_id);
+    Some(if force || card {
+        GpuDetectionError::KernelModuleNotLoaded {
+            module: module.to_string(),
+        }
+    } else {
+        GpuDetectionError::not_found()
+    })
+}
+
+/// Role of a device given by bus id in a laptop, `None` on other computers.
+///
+/// A laptop card is hybrid when another display controller (integrated GPU) is present.
+/// MUX switched laptops in discrete mode hide the integrated GPU.
+pub(crate) fn laptop_graphics(root: &Path, bus_id: &str) -> Option<LaptopGraphics> {
+    let chassis = fs::read_to_string(root.join(CHASSIS_TYPE)).ok()?;
+    if !LAPTOP_CHASSIS.contains(&chassis.trim()) {
+        return None;
+    }
+    let integrated = display_devices(root)
+        .iter()
+        .any(|(other, _)| other != bus_id);
+    Some(if integrated {
+        LaptopGraphics::Hybrid
+    } else {
+        LaptopGraphics::Discrete
+    })
+}
+
+/// Whether the computer runs on AC or battery power, `None` without power supply information.
+///
+/// Chargers are `Mains` or `USB` (USB-C) supplies. Without a charger entry a system
+/// battery which is not discharging means AC power.
+pub(crate) fn power_source(root: &Path) -> Option<PowerSource> {
+    let entries = fs::read_dir(root.join(POWER_SUPPLIES)).ok()?;
+    let (mut online, mut charger, mut discharging, mut battery) = (false, false, false, false);
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" | "USB" => {
+                charger = true;
+                online |= read("online").trim() == "1";
+            }
+            // peripherals (e.g. a wireless mouse) report `Device` scope.
+            "Battery" if read("scope").trim() != "Device" => {
+                battery = true;
+                discharging |= read("status").trim() == "Discharging";
+            }
+            _ => (),
+        }
+    }
+    if online {
+        Some(PowerSource::Ac)
+    } else if charger || discharging {
+        Some(PowerSource::Battery)
+    } else {
+        battery.then_some(PowerSource::Ac)
+    }
+}
+
+/// Keeps display controllers of a vendor resumed from runtime suspend while alive,
+/// restoring automatic power management on drop.
+///
+/// Dormant discrete GPUs of hybrid laptops report zeros or fail queries. Switching
+/// `power/control` requires root, devices which can not be switched are left as they are.
+/// Concurrent guards share devices, the last one dropped restores them.
+pub(crate) struct RuntimeResume {
+    controls: Vec<PathBuf>,
+}
+
+// Number of guards holding each resumed `power/control`.
+static RESUMED: Mutex<BTreeMap<PathBuf, usize>> = Mutex::new(BTreeMap::new());
+
+impl RuntimeResume {
+    pub(crate) fn new(root: &Path, vendor_id: u16) -> RuntimeResume {
+        let mut resumed = RESUMED.lock().unwrap();
+        let controls = display_devices(root)
+            .into_iter()
+            .filter(|(_, vendor)| *vendor == vendor_id)
+            .map(|(bus_id, _)| root.join(PCI_DEVICES).join(bus_id).join("power/control"))
+            .filter(|control| {
+                if let Some(holders) = resumed.get_mut(control) {
+                    *holders += 1;
+                    return true;
+                }
+                let switched = fs::read_to_string(control).is_ok_and(|mode| mode.trim() == "auto")
+                    && fs::write(control, "on").is_ok();
+                if switched {
+                    resumed.insert(control.clone(), 1);
+                }
+                switched
+            })
+            .collect();
+        RuntimeResume { controls }
+    }
+}
+
+impl Drop for RuntimeResume {
+    fn drop(&mut self) {
+        let mut resumed = RESUMED.lock().unwrap_or_else(|e| e.into_inner());
+        for control in &self.controls {
+            let Some(holders) = resumed.get_mut(control) else {
+                continue;
+            };
+            *holders -= 1;
+            if *holders == 0 {
+                resumed.remove(control);
+                let _ = fs::write(control, "auto");
+            }
+        }
+    }
+}
+
+/// Reads PCI identifiers of a device given by bus id (`0000:01:00.0`).
+#[cfg(feature = "amd")]
+pub(crate) fn pci_ids(root: &Path, bus_id: &str) -> Option<PciIds> {
+    let dir = root.join(PCI_DEVICES).join(bus_id);
+    let read = |name: &str| -> Option<u16> {
+        let value = fs::read_to_string(dir.join(name)).ok()?;
+        u16::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+    };
+    Some(PciIds {
+        vendor_id: read("vendor")?,
+        device_id: read("device")?,
+        subsystem_vendor_id: read("subsystem_vendor")?,
+        subsystem_device_id: read("subsystem_device")?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_laptop_graphics() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let device = |bus_id: &str, class: &str, vendor: &str| {
+            let dir = root.join(PCI_DEVICES).join(bus_id);
+            fs::create_dir_all(dir.join("power")).unwrap();
+            fs::write(dir.join("class"), class).unwrap();
+            fs::write(dir.join("vendor"), vendor).unwrap();
+            fs::write(dir.join("power/control"), "auto\n").unwrap();
+        };
+        device("0000:01:00.0", "0x030000\n", "0x10de\n");
+        device("0000:01:00.1", "0x040300\n", "0x10de\n");
+        fs::create_dir_all(root.join("class/dmi/id")).unwrap();
+
+        fs::write(root.join(CHASSIS_TYPE), "3\n").unwrap();
+        let desktop = laptop_graphics(root, "0000:01:00.0");
+        fs::write(root.join(CHASSIS_TYPE), "10\n").unwrap();
+        let discrete = laptop_graphics(root, "0000:01:00.0");
+        device("0000:00:02.0", "0x030000\n", "0x8086\n");
+        let hybrid = laptop_graphics(root, "0000:01:00.0");
+
+        let control = root.join(PCI_DEVICES).join("0000:01:00.0/power/control");
+        let resume = RuntimeResume::new(root, 0x10de);
+        let shared = RuntimeResume::new(root, 0x10de);
+        let resumed = fs::read_to_string(&control).unwrap();
+        drop(resume);
+        let still_resumed = fs::read_to_string(&control).unwrap();
+        drop(shared);
+        let restored = fs::read_to_string(&control).unwrap();
+        let intel = fs::read_to_string(root.join(PCI_DEVICES).join("0000:00:02.0/power/control"));
+
+        assert_eq!(desktop, None);
+        assert_eq!(discrete, Some(LaptopGraphics::Discrete));
+        assert_eq!(hybrid, Some(LaptopGraphics::Hybrid));
+        assert_eq!(resumed, "on");
+        assert_eq!(still_resumed, "on");
+        assert_eq!(restored, "auto");
+        assert_eq!(intel.unwrap(), "auto\n");
+    }
+
+    #[test]
+    fn test_missing_module() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let missing = |force| missing_module(root, "nvidia", 0x10de, force);
+        let unknown = missing(true);
+        fs::create_dir_all(root.join(MODULES).join("i915")).unwrap();
+        let no_card = missing(false);
+        let forced = missing(true);
+        let card = root.join(PCI_DEVICES).join("0000:01:00.0");
+        fs::create_dir_all(&card).unwrap();
+        fs::write(card.join("class"), "0x030000\n").unwrap();
+        fs::write(card.join("vendor"), "0x10de\n").unwrap();
+        let not_loaded = missing(false);
+        fs::create_dir_all(root.join(MODULES).join("nvidia")).unwrap();
+        let loaded = missing(true);
+
+        assert!(unknown.is_none());
+        assert!(matches!(no_card, Some(GpuDetectionError::NotFound { .. })));
+        assert_eq!(
+            forced.unwrap().to_string(),
+            "nvidia kernel module not loaded"
+        );
+        assert_eq!(not_loaded.unwrap().kind(), crate::ErrorKind::DriverNotFound);
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn test_power_source() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let supply = |name: &str, attrs: &[(&str, &str)]| {
+            let dir = root.join(POWER_SUPPLIES).join(name);
+            fs::create_dir_all(&dir).unwrap();
+            for (attr, value) in attrs {
+                fs::write(dir.join(attr), format!("{value}\n")).unwrap();
+            }
+        };
+        let unknown = power_source(root);
+        supply("BAT0", &[("type", "Battery"), ("status", "Full")]);
+        supply(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+            ],
+        );
+        let full = power_source(root);
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        let unplugged = power_source(root);
+        supply(
+            "ucsi-source-psy-USBC000:001",
+            &[("type", "USB"), ("online", "1")],
+        );
+        let usb_c = power_source(root);
+
+        assert_eq!(unknown, None);
+        assert_eq!(full, Some(PowerSource::Ac));
+        assert_eq!(unplugged, Some(PowerSource::Battery));
+        assert_eq!(usb_c, Some(PowerSource::Ac));
+    }
+}
@@ -0,0 +1,309 @@
+//! amdgpu and KFD (ROCm compute driver) attributes, the files ROCm SMI reads itself.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const KFD_NODES: &str = "class/kfd/kfd/topology/nodes";
+const KFD_PROCESSES: &str = "class/kfd/kfd/proc";
+
+/// Sensors of an amdgpu device, fields are `None` when the driver does not expose them.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Sensors {
+    /// Percent of time the GPU was busy.
+    pub busy_pct: Option<u32>,
+    /// Percent of time device memory was busy.
+    pub memory_busy_pct: Option<u32>,
+    pub vram_used_bytes: Option<u64>,
+    /// Edge temperature in degrees Celsius.
+    pub temperature_c: Option<u32>,
+    pub power_w: Option<u32>,
+    /// Current shader clock.
+    pub sclk_mhz: Option<u32>,
+    /// Current memory clock.
+    pub mclk_mhz: Option<u32>,
+    /// Current video encoder clock.
+    pub vclk_mhz: Option<u32>,
+    /// Critical edge temperature, at which the GPU throttles hard and shuts down soon after.
+    pub temperature_crit_c: Option<u32>,
+    /// Uncorrectable RAS (ECC) errors of all blocks since driver load,
+    /// `None` on cards without RAS.
+    pub ras_uncorrected: Option<u64>,
+    /// Correctable RAS (ECC) errors of all blocks since driver load.
+    pub ras_corrected: Option<u64>,
+}
+
+/// Reads sensors of the device given by bus id, `None` if it is not in sysfs.
+pub(crate) fn sensors(root: &Path, bus_id: &str) -> Option<Sensors> {
+    let dir = root.join(super::PCI_DEVICES).join(bus_id);
+    if !dir.is_dir() {
+        return None;
+    }
+    let read = |path: &Path| -> Option<u64> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+    let hwmon = hwmon(&dir);
+    let hwmon_read = |name: &str| hwmon.as_ref().and_then(|hwmon| read(&hwmon.join(name)));
+    let ras = ras_errors(root, bus_id);
+    Some(Sensors {
+        busy_pct: read(&dir.join("gpu_busy_percent")).and_then(|pct| pct.try_into().ok()),
+        memory_busy_pct: read(&dir.join("mem_busy_percent")).and_then(|pct| pct.try_into().ok()),
+        vram_used_bytes: read(&dir.join("mem_info_vram_used")),
+        // hwmon reports millidegrees and microwatts, APUs only have `power1_input`.
+        temperature_c: hwmon_read("temp1_input").and_then(|mc| (mc / 1000).try_into().ok()),
+        temperature_crit_c: hwmon_read("temp1_crit").and_then(|mc| (mc / 1000).try_into().ok()),
+        power_w: hwmon_read("power1_average")
+            .or_else(|| hwmon_read("power1_input"))
+            .and_then(|uw| (uw / 1_000_000).try_into().ok()),
+        sclk_mhz: dpm_current_mhz(root, bus_id, "sclk"),
+        mclk_mhz: dpm_current_mhz(root, bus_id, "mclk"),
+        vclk_mhz: dpm_current_mhz(root, bus_id, "vclk"),
+        ras_uncorrected: ras.map(|(ue, _)| ue),
+        ras_corrected: ras.map(|(_, ce)| ce),
+    })
+}
+
+/// Sums uncorrectable and correctable error counts of all RAS blocks
+/// (`ras/<block>_err_count`, e.g. `umc_err_count` for memory) of the device given by bus id.
+pub(crate) fn ras_errors(root: &Path, bus_id: &str) -> Option<(u64, u64)> {
+    let dir = root.join(super::PCI_DEVICES).join(bus_id).join("ras");
+    let mut counts = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let name = entry.file_name();
+        if !name
+            .to_str()
+            .is_some_and(|name| name.ends_with("_err_count"))
+        {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let (ue, ce) = counts.get_or_insert((0, 0));
+        // `ue: 0\nce: 0\n`
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value: u64 = value.trim().parse().unwrap_or(0);
+            match key.trim() {
+                "ue" => *ue += value,
+                "ce" => *ce += value,
+                _ => (),
+            }
+        }
+    }
+    counts
+}
+
+/// Hardware monitoring directory of the device directory `dir`.
+fn hwmon(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.join("name").is_file())
+}
+
+/// DPM levels of clock `clock` (e.g. `vclk`) in MHz with the current one marked,
+/// read from `pp_dpm_<clock>` of the device given by bus id.
+fn dpm_levels(root: &Path, bus_id: &str, clock: &str) -> Option<Vec<(u32, bool)>> {
+    let path = root
+        .join(super::PCI_DEVICES)
+        .join(bus_id)
+        .join(format!("pp_dpm_{clock}"));
+    // one level per line, e.g. `1: 1630Mhz *` with the current level marked.
+    let levels = fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .filter_map(|line| {
+            let (_, level) = line.split_once(':')?;
+            let level = level.trim();
+            let current = level.ends_with('*');
+            let mhz = level.trim_end_matches('*').trim().strip_suffix("Mhz")?;
+            Some((mhz.parse().ok()?, current))
+        })
+        .collect();
+    Some(levels)
+}
+
+/// Highest DPM level of clock `clock` in MHz, `None` if the driver does not expose the clock.
+pub(crate) fn dpm_max_mhz(root: &Path, bus_id: &str, clock: &str) -> Option<u32> {
+    let levels = dpm_levels(root, bus_id, clock)?;
+    levels.into_iter().map(|(mhz, _)| mhz).max()
+}
+
+/// Current DPM level of clock `clock` in MHz.
+fn dpm_current_mhz(root: &Path, bus_id: &str, clock: &str) -> Option<u32> {
+    let levels = dpm_levels(root, bus_id, clock)?;
+    levels
+        .into_iter()
+        .find_map(|(mhz, current)| current.then_some(mhz))
+}
+
+/// Process with KFD resources on a GPU.
+#[derive(Debug, PartialEq)]
+pub(crate) struct KfdProcess {
+    pub pid: u32,
+    /// Device memory allocated by the process, `None` on kernels older than 5.10.
+    pub vram_bytes: Option<u64>,
+}
+
+/// KFD id of the GPU given by bus id, found in the KFD topology.
+fn kfd_gpu_id(root: &Path, bus_id: &str) -> Option<String> {
+    fs::read_dir(root.join(KFD_NODES))
+        .ok()?
+        .flatten()
+        .find_map(|node| {
+            let dir = node.path();
+            let gpu_id = fs::read_to_string(dir.join("gpu_id")).ok()?;
+            let gpu_id = gpu_id.trim();
+            // CPU nodes have no gpu id.
+            if gpu_id == "0" {
+                return None;
+            }
+            let properties = fs::read_to_string(dir.join("properties")).ok()?;
+            let property = |name: &str| -> Option<u32> {
+                properties.lines().find_map(|line| {
+                    let (key, value) = line.split_once(' ')?;
+                    if key != name {
+                        return None;
+                    }
+                    value.trim().parse().ok()
+                })
+            };
+            // `location_id` is bus << 8 | device << 3 | function.
+            let location = property("location_id")?;
+            let node_bus_id = format!(
+                "{:04x}:{:02x}:{:02x}.{:x}",
+                property("domain").unwrap_or(0),
+                location >> 8,
+                (location >> 3) & 0x1f,
+                location & 0x7
+            );
+            (node_bus_id == bus_id).then(|| gpu_id.to_string())
+        })
+}
+
+/// Processes using the GPU given by bus id, `None` if KFD does not list it.
+///
+/// ROCm runtimes open every GPU at start, a process uses a GPU when it allocated
+/// memory or created a queue on it (like `rsmi_compute_process_gpus_get`).
+pub(crate) fn kfd_processes(root: &Path, bus_id: &str) -> Option<Vec<KfdProcess>> {
+    let gpu_id = kfd_gpu_id(root, bus_id)?;
+    let entries = fs::read_dir(root.join(KFD_PROCESSES)).ok()?;
+    let mut processes: Vec<KfdProcess> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+            let vram_bytes = fs::read_to_string(dir.join(format!("vram_{gpu_id}")))
+                .ok()
+                .and_then(|bytes| bytes.trim().parse().ok());
+            let queued = fs::read_dir(dir.join("queues"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .any(|queue| {
+                    fs::read_to_string(queue.path().join("gpuid"))
+                        .is_ok_and(|id| id.trim() == gpu_id)
+                });
+            (queued || vram_bytes.unwrap_or(0) > 0).then_some(KfdProcess { pid, vram_bytes })
+        })
+        .collect();
+    processes.sort_by_key(|process| process.pid);
+    Some(processes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dpm_max_mhz() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let dir = root.join(super::super::PCI_DEVICES).join("0000:03:00.0");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("pp_dpm_vclk"), "0: 500Mhz \n1: 1630Mhz *\n").unwrap();
+        fs::write(dir.join("pp_dpm_dclk"), "").unwrap();
+
+        assert_eq!(dpm_max_mhz(root, "0000:03:00.0", "vclk"), Some(1630));
+        assert_eq!(dpm_max_mhz(root, "0000:03:00.0", "dclk"), None);
+        assert_eq!(dpm_max_mhz(root, "0000:03:00.0", "sclk"), None);
+    }
+
+    #[test]
+    fn test_sensors() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let dir = root.join(super::super::PCI_DEVICES).join("0000:03:00.0");
+        fs::create_dir_all(dir.join("hwmon/hwmon2")).unwrap();
+        fs::write(dir.join("gpu_busy_percent"), "87\n").unwrap();
+        fs::write(dir.join("mem_info_vram_used"), "1073741824\n").unwrap();
+        fs::write(dir.join("pp_dpm_sclk"), "0: 500Mhz \n1: 2100Mhz *\n").unwrap();
+        fs::write(dir.join("pp_dpm_mclk"), "0: 96Mhz \n1: 1000Mhz *\n").unwrap();
+        fs::write(dir.join("hwmon/hwmon2/name"), "amdgpu\n").unwrap();
+        fs::write(dir.join("hwmon/hwmon2/temp1_input"), "54000\n").unwrap();
+        fs::write(dir.join("hwmon/hwmon2/power1_input"), "187000000\n").unwrap();
+        fs::write(dir.join("hwmon/hwmon2/temp1_crit"), "100000\n").unwrap();
+        fs::create_dir_all(dir.join("ras")).unwrap();
+        fs::write(dir.join("ras/umc_err_count"), "ue: 2\nce: 5\n").unwrap();
+        fs::write(dir.join("ras/gfx_err_count"), "ue: 1\nce: 0\n").unwrap();
+        fs::write(dir.join("ras/features"), "feature mask: 0x3fff\n").unwrap();
+
+        assert_eq!(
+            sensors(root, "0000:03:00.0"),
+            Some(Sensors {
+                busy_pct: Some(87),
+                memory_busy_pct: None,
+                vram_used_bytes: Some(1 << 30),
+                temperature_c: Some(54),
+                power_w: Some(187),
+                sclk_mhz: Some(2100),
+                mclk_mhz: Some(1000),
+                vclk_mhz: None,
+                temperature_crit_c: Some(100),
+                ras_uncorrected: Some(3),
+                ras_corrected: Some(5),
+            })
+        );
+        assert_eq!(sensors(root, "0000:04:00.0"), None);
+    }
+
+    #[test]
+    fn test_kfd_processes() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(&format!("{KFD_NODES}/0/gpu_id"), "0\n");
+        write(&format!("{KFD_NODES}/0/properties"), "cpu_cores_count 16\n");
+        write(&format!("{KFD_NODES}/1/gpu_id"), "48223\n");
+        // 0000:03:00.0
+        write(
+            &format!("{KFD_NODES}/1/properties"),
+            "simd_count 240\nlocation_id 768\ndomain 0\n",
+        );
+        write(&format!("{KFD_PROCESSES}/4242/vram_48223"), "1073741824\n");
+        write(&format!("{KFD_PROCESSES}/4343/vram_48223"), "0\n");
+        write(&format!("{KFD_PROCESSES}/4343/queues/1/gpuid"), "48223\n");
+        // runtime started, but does not use the GPU.
+        write(&format!("{KFD_PROCESSES}/4444/vram_48223"), "0\n");
+
+        assert_eq!(
+            kfd_processes(root, "0000:03:00.0"),
+            Some(vec![
+                KfdProcess {
+                    pid: 4242,
+                    vram_bytes: Some(1 << 30),
+                },
+                KfdProcess {
+                    pid: 4343,
+                    vram_bytes: Some(0),
+                },
+            ])
+        );
+        assert_eq!(kfd_processes(root, "0000:04:00.0"), None);
+    }
+}
@@ -0,0 +1,257 @@
+//! Mock platform for testing code built on [`GpuDetection`](crate::GpuDetection)
+//! without GPUs.
+//!
+//! ```
+//! use golem_gpu_info::testing::MockPlatform;
+//! use golem_gpu_info::model::Device;
+//! use golem_gpu_info::GpuDetectionBuilder;
+//!
+//! let card = Device {
+//!     model: "NVIDIA GeForce RTX 3090".into(),
+//!     quantity: 1,
+//!     uuids: vec!["GPU-a".into()],
+//!     ..Default::default()
+//! };
+//! let detection = GpuDetectionBuilder::default()
+//!     .mock(MockPlatform::new(vec![card]))
+//!     .enable_only(&["mock"])
+//!     .init()
+//!     .unwrap();
+//! assert_eq!(detection.detect().unwrap().devices.len(), 1);
+//! ```
+
+use crate::health::{HealthReport, HealthStatus};
+use crate::model::{Cuda, Device, GpuApiInfo, GpuProcess, Telemetry};
+use crate::platform::{Detection, Flags, Platform};
+use crate::watch::DeviceErrorEvent;
+use crate::{GpuDetectionError, Result};
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+type ErrorFn = Arc<dyn Fn() -> GpuDetectionError + Send + Sync>;
+
+/// Platform serving a fixed list of cards, registered with
+/// [`GpuDetectionBuilder::mock`](crate::GpuDetectionBuilder::mock).
+///
+/// Every entry of the device list is a single card, its native index is taken
+/// from `indices`.
+#[derive(Clone)]
+pub struct MockPlatform {
+    name: String,
+    devices: Vec<Device>,
+    cuda: Option<Cuda>,
+    telemetry: Telemetry,
+    error_events: Vec<DeviceErrorEvent>,
+    version: Option<String>,
+    delay: Duration,
+    init_delay: Duration,
+    init_error: Option<ErrorFn>,
+    processes_error: Option<ErrorFn>,
+    device_errors: BTreeMap<usize, ErrorFn>,
+}
+
+impl MockPlatform {
+    /// Platform named `mock` serving given cards.
+    pub fn new(devices: Vec<Device>) -> Self {
+        MockPlatform {
+            name: "mock".into(),
+            devices,
+            cuda: None,
+            telemetry: Telemetry::default(),
+            error_events: Vec::new(),
+            version: None,
+            delay: Duration::ZERO,
+            init_delay: Duration::ZERO,
+            init_error: None,
+            processes_error: None,
+            device_errors: BTreeMap::new(),
+        }
+    }
+
+    /// Changes platform name, e.g. to `cuda` to stand in for a real backend.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Reports installed CUDA.
+    pub fn cuda(mut self, cuda: Cuda) -> Self {
+        self.cuda = Some(cuda);
+        self
+    }
+
+    /// Telemetry returned for every known card.
+    pub fn telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = telemetry;
+        self
+    }
+
+    /// Events delivered once by [`GpuDetection::watch_errors`](crate::GpuDetection::watch_errors).
+    pub fn error_events(mut self, events: Vec<DeviceErrorEvent>) -> Self {
+        self.error_events = events;
+        self
+    }
+
+    /// Management library version listed in backend info.
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Delays device enumeration, to exercise query timeouts.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Delays platform initialization, e.g. to simulate slow driver start.
+    pub fn init_delay(mut self, delay: Duration) -> Self {
+        self.init_delay = delay;
+        self
+    }
+
+    /// Fails platform initialization with error returned by `error`.
+    pub fn init_error(
+        mut self,
+        error: impl Fn() -> GpuDetectionError + Send + Sync + 'static,
+    ) -> Self {
+        self.init_error = Some(Arc::new(error));
+        self
+    }
+
+    /// Fails listing processes of known cards with error returned by `error`,
+    /// e.g. to simulate a platform without process accounting.
+    pub fn processes_error(
+        mut self,
+        error: impl Fn() -> GpuDetectionError + Send + Sync + 'static,
+    ) -> Self {
+        self.processes_error = Some(Arc::new(error));
+        self
+    }
+
+    /// Fails reading card at `position` in the device list with error returned by `error`.
+    ///
+    /// [`GpuDetection::detect`](crate::GpuDetection::detect) fails under the default
+    /// [`DetectionPolicy`](crate::DetectionPolicy),
+    /// [`GpuDetection::detect_report`](crate::GpuDetection::detect_report) skips the card.
+    pub fn device_error(
+        mut self,
+        position: usize,
+        error: impl Fn() -> GpuDetectionError + Send + Sync + 'static,
+    ) -> Self {
+        self.device_errors.insert(position, Arc::new(error));
+        self
+    }
+
+    fn find(&self, uuid: &str) -> Option<&Device> {
+        self.devices
+            .iter()
+            .find(|dev| dev.uuids.iter().any(|id| id == uuid))
+    }
+}
+
+impl Platform for MockPlatform {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn init(&self, _flags: Flags) -> Result<Box<dyn Detection>> {
+        std::thread::sleep(self.init_delay);
+        match &self.init_error {
+            Some(error) => Err(error()),
+            None => Ok(Box::new(self.clone())),
+        }
+    }
+}
+
+impl Detection for MockPlatform {
+    fn detect_api(&self, api: &mut GpuApiInfo) -> Result<()> {
+        if let Some(cuda) = &self.cuda {
+            api.cuda = Some(cuda.clone());
+        }
+        Ok(())
+    }
+
+    fn devices(&self) -> Result<Vec<Device>> {
+        self.devices_partial()?.into_iter().collect()
+    }
+
+    fn devices_partial(&self) -> Result<Vec<Result<Device>>> {
+        std::thread::sleep(self.delay);
+        Ok(self
+            .devices
+            .iter()
+            .enumerate()
+            .map(|(position, dev)| match self.device_errors.get(&position) {
+                Some(error) => Err(error()),
+                None => Ok(dev.clone()),
+            })
+            .collect())
+    }
+
+    fn device_by_uuid(&self, uuid: &str) -> Result<Option<Device>> {
+        Ok(self.find(uuid).cloned())
+    }
+
+    fn device_by_pci_bus_id(&self, bus_id: &str) -> Result<Option<Device>> {
+        Ok(self
+            .devices
+            .iter()
+            .find(|dev| dev.pci_bus_ids.iter().any(|id| id == bus_id))
+            .cloned())
+    }
+
+    fn device_by_index(&self, index: u32) -> Result<Option<Device>> {
+        Ok(self
+            .devices
+            .iter()
+            .find(|dev| dev.indices.contains(&index))
+            .cloned())
+    }
+
+    fn telemetry(&self, uuid: &str) -> Result<Option<Telemetry>> {
+        Ok(self.find(uuid).map(|_| self.telemetry.clone()))
+    }
+
+    fn processes(&self, uuid: &str) -> Result<Option<Vec<GpuProcess>>> {
+        if self.find(uuid).is_none() {
+            return Ok(None);
+        }
+        match &self.processes_error {
+            Some(error) => Err(error()),
+            None => Ok(Some(Vec::new())),
+        }
+    }
+
+    fn health_check(&self) -> Result<Vec<HealthReport>> {
+        Ok(self
+            .devices
+            .iter()
+            .flat_map(|dev| dev.uuids.iter().zip(&dev.indices))
+            .map(|(uuid, &index)| HealthReport {
+                uuid: Some(uuid.clone()),
+                index,
+                status: HealthStatus::Healthy,
+            })
+            .collect())
+    }
+
+    fn watch_errors(
+        &self,
+        on_event: &mut dyn FnMut(DeviceErrorEvent) -> bool,
+        _stop: &AtomicBool,
+    ) -> Result<()> {
+        for event in &self.error_events {
+            if !on_event(event.clone()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
+    }
+}
@@ -0,0 +1,31 @@
+//! Guards against driver calls hanging on wedged hardware.
+
+use crate::{GpuDetectionError, Result};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Runs `f` on a helper thread and waits at most `timeout` for its result.
+///
+/// On timeout the helper thread is left behind, there is no way to interrupt a hung driver call.
+pub(crate) fn run<T, F>(timeout: Option<Duration>, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return f();
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("gpu-detection".into())
+        .spawn(move || {
+            // receiver is gone after timeout.
+            let _ = tx.send(f());
+        })
+        .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?;
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => Err(GpuDetectionError::Timeout(timeout)),
+    }
+}
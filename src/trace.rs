@@ -0,0 +1,21 @@
+//! Diagnostic events, emitted through `tracing` with the `tracing` feature and
+//! compiled out otherwise.
+//!
+//! Arguments follow `tracing` macro syntax. They are not evaluated without the feature,
+//! so they must not have side effects.
+
+/// Emits a debug level event.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+/// Emits a warn level event.
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    };
+}
@@ -0,0 +1,296 @@
+//! Device hot-plug notifications.
+//!
+//! Device set is polled, so events are delivered with up to one interval delay.
+//! With the `udev` feature on Linux, GPU hot-plug and driver re-bind (e.g. to `vfio-pci`)
+//! trigger an immediate rescan with re-initialized backends instead. With the `wmi` feature
+//! on Windows, video controller arrivals, removals and changes (driver resets) do the same
+//! and are reported as [`WatchEvent::DriverReloaded`].
+
+use crate::hotplug::{self, Rescan};
+use crate::model::DriverVersion;
+use crate::{GpuDetection, GpuDetectionError, Result};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Change of the device set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// Device appeared (e.g. eGPU was attached).
+    DeviceAdded {
+        /// Device uuid.
+        uuid: String,
+    },
+    /// Device disappeared (e.g. card fell off the bus).
+    DeviceRemoved {
+        /// Device uuid.
+        uuid: String,
+    },
+    /// Driver version changed or the driver was reset (Windows TDR), offers should be rebuilt.
+    DriverReloaded {
+        /// New driver version.
+        version: Option<DriverVersion>,
+    },
+}
+
+/// Polls device set on a background thread, see [`GpuDetection::watch`].
+///
+/// Iterating blocks until next event. Polling stops when the watcher is dropped.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    events: Receiver<WatchEvent>,
+}
+
+impl Watcher {
+    pub(crate) fn start(detection: &GpuDetection, interval: Duration) -> Result<Watcher> {
+        let snapshot = Snapshot::take(detection)?;
+        let (tx, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let rescan = Arc::new(Rescan::default());
+        let handle = {
+            let detection = detection.clone();
+            let stop = stop.clone();
+            let rescan = rescan.clone();
+            thread::Builder::new()
+                .name("gpu-watch".into())
+                .spawn(move || poll(detection, snapshot, tx, interval, stop, rescan))
+                .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?
+        };
+        // the listener is not joined, it notices `stop` on its next wake-up.
+        hotplug::spawn(handle.thread().clone(), rescan, stop.clone())
+            .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?;
+
+        Ok(Watcher {
+            stop,
+            handle: Some(handle),
+            events,
+        })
+    }
+
+    /// Waits at most `timeout` for next event.
+    pub fn next_timeout(&self, timeout: Duration) -> Option<WatchEvent> {
+        self.events.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = WatchEvent;
+
+    fn next(&mut self) -> Option<WatchEvent> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Critical error reported by the driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceErrorEvent {
+    /// Uuid of affected device, `None` if it no longer responds.
+    pub uuid: Option<String>,
+    /// Kind of error.
+    pub kind: DeviceErrorKind,
+}
+
+/// Kind of driver reported error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceErrorKind {
+    /// XID error (e.g. 79, "GPU has fallen off the bus"), `None` if code is unknown.
+    Xid(Option<u64>),
+    /// Corrected (single bit) ECC error.
+    SingleBitEcc,
+    /// Uncorrected (double bit) ECC error.
+    DoubleBitEcc,
+    /// Clocks changed (e.g. due to thermal throttling).
+    ClockChange,
+}
+
+/// Delivers driver error events, see [`GpuDetection::watch_errors`].
+///
+/// Iterating blocks until next event. Items are `Err` when a backend stopped delivering events.
+pub struct ErrorWatcher {
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+    events: Receiver<Result<DeviceErrorEvent>>,
+}
+
+impl ErrorWatcher {
+    pub(crate) fn start(detection: &GpuDetection) -> Result<ErrorWatcher> {
+        let (tx, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut watcher = ErrorWatcher {
+            stop,
+            handles: Vec::new(),
+            events,
+        };
+        let backends = detection.ready()?;
+        for idx in 0..backends.list.len() {
+            let backends = backends.clone();
+            let detection = detection.clone();
+            let stop = watcher.stop.clone();
+            let tx = tx.clone();
+            let handle = thread::Builder::new()
+                .name("gpu-watch-errors".into())
+                .spawn(move || {
+                    let mut on_event = |event: DeviceErrorEvent| {
+                        // events of lost devices have no uuid to filter on.
+                        let hidden = (event.uuid.as_deref())
+                            .is_some_and(|uuid| !detection.is_visible_uuid(uuid));
+                        hidden || tx.send(Ok(event)).is_ok()
+                    };
+                    let result = backends.list[idx]
+                        .detection
+                        .watch_errors(&mut on_event, &stop);
+                    if let Err(e) = result {
+                        let _ = tx.send(Err(e));
+                    }
+                })
+                .map_err(|e| GpuDetectionError::Unknown(e.to_string()))?;
+            watcher.handles.push(handle);
+        }
+        Ok(watcher)
+    }
+
+    /// Waits at most `timeout` for next event.
+    pub fn next_timeout(&self, timeout: Duration) -> Option<Result<DeviceErrorEvent>> {
+        self.events.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for ErrorWatcher {
+    type Item = Result<DeviceErrorEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for ErrorWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Snapshot {
+    uuids: BTreeSet<String>,
+    driver_version: Option<DriverVersion>,
+}
+
+impl Snapshot {
+    fn take(detection: &GpuDetection) -> Result<Snapshot> {
+        let gpu = detection.detect()?;
+        let uuids = gpu.devices.into_iter().flat_map(|dev| dev.uuids).collect();
+        let driver_version = gpu.api.cuda.and_then(|cuda| cuda.driver_version);
+        Ok(Snapshot {
+            uuids,
+            driver_version,
+        })
+    }
+
+    /// Events between snapshots, `driver_reset` reports driver reload even if its version
+    /// did not change.
+    fn changes(&self, next: &Snapshot, driver_reset: bool) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        if driver_reset || self.driver_version != next.driver_version {
+            events.push(WatchEvent::DriverReloaded {
+                version: next.driver_version,
+            });
+        }
+        events.extend(
+            self.uuids
+                .difference(&next.uuids)
+                .map(|uuid| WatchEvent::DeviceRemoved { uuid: uuid.clone() }),
+        );
+        events.extend(
+            next.uuids
+                .difference(&self.uuids)
+                .map(|uuid| WatchEvent::DeviceAdded { uuid: uuid.clone() }),
+        );
+        events
+    }
+}
+
+fn poll(
+    mut detection: GpuDetection,
+    mut snapshot: Snapshot,
+    tx: Sender<WatchEvent>,
+    interval: Duration,
+    stop: Arc<AtomicBool>,
+    rescan: Arc<Rescan>,
+) {
+    loop {
+        thread::park_timeout(interval);
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        // backends keep handles of cards present at init, new cards need a fresh init.
+        if rescan.requested.swap(false, Ordering::Relaxed) {
+            let _ = detection.refresh();
+        }
+        // driver may be unavailable for a moment while it reloads, retry on next tick.
+        let Ok(next) = Snapshot::take(&detection) else {
+            continue;
+        };
+        let driver_reset = rescan.driver_reset.swap(false, Ordering::Relaxed);
+        for event in snapshot.changes(&next, driver_reset) {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        snapshot = next;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let before = Snapshot {
+            uuids: ["GPU-1", "GPU-2"].map(String::from).into(),
+            driver_version: Some("535.146.02".parse().unwrap()),
+        };
+        let after = Snapshot {
+            uuids: ["GPU-2", "GPU-3"].map(String::from).into(),
+            driver_version: Some("550.54.14".parse().unwrap()),
+        };
+        assert_eq!(
+            before.changes(&before, true),
+            vec![WatchEvent::DriverReloaded {
+                version: Some("535.146.02".parse().unwrap())
+            }]
+        );
+        assert_eq!(
+            before.changes(&after, false),
+            vec![
+                WatchEvent::DriverReloaded {
+                    version: Some("550.54.14".parse().unwrap())
+                },
+                WatchEvent::DeviceRemoved {
+                    uuid: "GPU-1".into()
+                },
+                WatchEvent::DeviceAdded {
+                    uuid: "GPU-3".into()
+                },
+            ]
+        );
+        assert!(before.changes(&before, false).is_empty());
+    }
+}